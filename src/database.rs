@@ -1,12 +1,60 @@
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use log::LevelFilter;
+use sqlx::{postgres::{PgConnectOptions, PgPoolOptions}, ConnectOptions, PgPool};
+use std::str::FromStr;
 use std::time::Duration;
 
+use crate::config::DatabaseConfig;
+
 pub type DbPool = PgPool;
 
-pub async fn create_db_pool(database_url: &str) -> Result<DbPool, sqlx::Error> {
+pub async fn create_db_pool(config: &DatabaseConfig) -> Result<DbPool, sqlx::Error> {
+    // Запросы дольше порога пишутся в лог с уровнем WARN вместе с временем выполнения и текстом запроса
+    let connect_options = PgConnectOptions::from_str(&config.url)?
+        .log_slow_statements(LevelFilter::Warn, Duration::from_millis(config.slow_query_ms));
+
     PgPoolOptions::new()
-        .max_connections(10)
-        .acquire_timeout(Duration::from_secs(5))
-        .connect(database_url)
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .connect_with(connect_options)
         .await
-}
\ No newline at end of file
+}
+
+// Максимальная задержка между попытками подключения
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+// Подключение с повторами: при старте в контейнерах Postgres может быть ещё не готов
+pub async fn create_db_pool_with_retry(config: &DatabaseConfig) -> Result<DbPool, sqlx::Error> {
+    let attempts = config.connect_retries + 1;
+    let mut backoff = Duration::from_millis(config.connect_backoff_ms);
+    let mut attempt = 1;
+
+    loop {
+        log::info!("Connecting to database (attempt {}/{})", attempt, attempts);
+
+        match create_db_pool(config).await {
+            Ok(pool) => return Ok(pool),
+            Err(e) if attempt < attempts => {
+                log::warn!(
+                    "Database connection attempt {}/{} failed: {}. Retrying in {} ms",
+                    attempt,
+                    attempts,
+                    e,
+                    backoff.as_millis()
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Пул для тестов, которым нужна настоящая база: DATABASE_URL из окружения или .env с применёнными миграциями.
+// Такие тесты помечены #[ignore] и запускаются через `cargo test -- --ignored`
+#[cfg(test)]
+pub async fn test_pool() -> DbPool {
+    let config = crate::config::Config::from_env().expect("test configuration");
+    create_db_pool(&config.database).await.expect("test database is not available")
+}
@@ -1,21 +1,32 @@
 use actix_web::{web, HttpResponse};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::{CreateBrandRequest, UpdateBrandRequest},
+    handlers::common::{cacheable_ok, created, log_failure, not_found_or_deleted},
+    models::{CreateBrandRequest, UpdateBrandRequest, SetBrandPriceFloorRequest, SetBrandStockDefaultsRequest, CountryMatch, DryRunQuery, DeleteQuery},
     repositories::brand_repository::BrandRepositoryImpl,
+    repositories::car_model_repository::CarModelRepositoryImpl,
 };
-use crate::repositories::BrandRepository;
+use crate::repositories::{BrandRepository, CarModelRepository};
+
+#[derive(Debug, Deserialize)]
+pub struct BrandsByCountryQuery {
+    /// `?match=exact|contains`, по умолчанию exact
+    #[serde(rename = "match", default)]
+    pub match_mode: CountryMatch,
+}
 
 // GET /api/brands - получить все бренды
-pub async fn get_brands_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+pub async fn get_brands_handler(db_pool: web::Data<DbPool>, config: web::Data<Config>) -> HttpResponse {
     let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
     match repo.find_all().await {
-        Ok(brands) => HttpResponse::Ok().json(brands),
+        Ok(brands) => cacheable_ok(config.cache.brands_max_age).json(brands),
         Err(e) => {
-            eprintln!("Error fetching brands: {}", e);
+            log_failure("fetching", "brands", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch brands"
             }))
@@ -26,18 +37,19 @@ pub async fn get_brands_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
 // GET /api/brands/{id} - получить бренд по ID
 pub async fn get_brand_by_id_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
     let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
     match repo.find_by_id(id).await {
-        Ok(Some(brand)) => HttpResponse::Ok().json(brand),
+        Ok(Some(brand)) => cacheable_ok(config.cache.brands_max_age).json(brand),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Brand not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching brand {}: {}", id, e);
+            log_failure("fetching", "brand", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch brand"
             }))
@@ -48,18 +60,19 @@ pub async fn get_brand_by_id_handler(
 // GET /api/brands/name/{name} - получить бренд по названию
 pub async fn get_brand_by_name_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
 ) -> HttpResponse {
     let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
     let name = path.into_inner();
 
     match repo.find_by_name(&name).await {
-        Ok(Some(brand)) => HttpResponse::Ok().json(brand),
+        Ok(Some(brand)) => cacheable_ok(config.cache.brands_max_age).json(brand),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Brand not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching brand by name {}: {}", name, e);
+            log_failure("fetching", "brand by name", Some(&name), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch brand"
             }))
@@ -67,18 +80,35 @@ pub async fn get_brand_by_name_handler(
     }
 }
 
+// GET /api/brands/countries - страны брендов с количеством брендов (для фильтра)
+pub async fn get_brand_countries_handler(db_pool: web::Data<DbPool>, config: web::Data<Config>) -> HttpResponse {
+    let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+
+    match repo.country_counts().await {
+        Ok(countries) => cacheable_ok(config.cache.brands_max_age).json(countries),
+        Err(e) => {
+            log_failure("fetching", "brand countries", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch brand countries"
+            }))
+        }
+    }
+}
+
 // GET /api/brands/country/{country} - получить бренды по стране
 pub async fn get_brands_by_country_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
+    query: web::Query<BrandsByCountryQuery>,
 ) -> HttpResponse {
     let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
     let country = path.into_inner();
 
-    match repo.find_by_country(&country).await {
-        Ok(brands) => HttpResponse::Ok().json(brands),
+    match repo.find_by_country(&country, query.match_mode).await {
+        Ok(brands) => cacheable_ok(config.cache.brands_max_age).json(brands),
         Err(e) => {
-            eprintln!("Error fetching brands by country {}: {}", country, e);
+            log_failure("fetching", "brands by country", Some(&country), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch brands"
             }))
@@ -106,7 +136,7 @@ pub async fn create_brand_handler(
             }));
         }
         Err(e) => {
-            eprintln!("Error checking brand name: {}", e);
+            log_failure("checking", "brand name", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to check brand name"
             }));
@@ -115,9 +145,9 @@ pub async fn create_brand_handler(
     }
 
     match repo.save(&create_request).await {
-        Ok(brand) => HttpResponse::Created().json(brand),
+        Ok(brand) => created("/api/brands", brand.id).json(brand),
         Err(e) => {
-            eprintln!("Error creating brand: {}", e);
+            log_failure("creating", "brand", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create brand"
             }))
@@ -148,7 +178,7 @@ pub async fn update_brand_handler(
                 }));
             }
             Err(e) => {
-                eprintln!("Error checking brand name: {}", e);
+                log_failure("checking", "brand name", None, &e);
                 return HttpResponse::InternalServerError().json(serde_json::json!({
                     "error": "Failed to check brand name"
                 }));
@@ -163,7 +193,7 @@ pub async fn update_brand_handler(
             "error": "Brand not found"
         })),
         Err(e) => {
-            eprintln!("Error updating brand {}: {}", id, e);
+            log_failure("updating", "brand", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update brand"
             }))
@@ -171,24 +201,247 @@ pub async fn update_brand_handler(
     }
 }
 
+// GET /api/brands/{id}/price-floor - минимальные цены продажи бренда
+pub async fn get_brand_price_floor_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    match repo.find_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Brand not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "brand", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch brand price floor"
+            }));
+        }
+    }
+
+    match repo.find_price_floor(id).await {
+        Ok(Some(floor)) => HttpResponse::Ok().json(floor),
+        // Порог не задан - ограничений нет
+        Ok(None) => HttpResponse::Ok().json(serde_json::json!({
+            "brand_id": id,
+            "min_car_price": null,
+            "min_part_price": null
+        })),
+        Err(e) => {
+            log_failure("fetching", "price floor for brand", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch brand price floor"
+            }))
+        }
+    }
+}
+
+// PUT /api/brands/{id}/price-floor - задать минимальные цены продажи бренда (null снимает порог)
+pub async fn set_brand_price_floor_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    request: web::Json<SetBrandPriceFloorRequest>,
+) -> HttpResponse {
+    let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    if let Err(validation_errors) = request.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Validation failed",
+            "details": validation_errors
+        }));
+    }
+
+    match repo.set_price_floor(id, &request).await {
+        Ok(Some(floor)) => HttpResponse::Ok().json(floor),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Brand not found"
+        })),
+        Err(e) => {
+            log_failure("setting", "price floor for brand", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to set brand price floor"
+            }))
+        }
+    }
+}
+
+// GET /api/brands/{id}/stock-defaults - уровни запаса по умолчанию для запчастей бренда
+pub async fn get_brand_stock_defaults_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    match repo.find_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Brand not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "brand", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch brand stock defaults"
+            }));
+        }
+    }
+
+    match repo.find_stock_defaults(id).await {
+        Ok(Some(defaults)) => HttpResponse::Ok().json(defaults),
+        // Не заданы - действуют общие уровни по умолчанию
+        Ok(None) => HttpResponse::Ok().json(serde_json::json!({
+            "brand_id": id,
+            "min_stock_level": null,
+            "max_stock_level": null
+        })),
+        Err(e) => {
+            log_failure("fetching", "stock defaults for brand", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch brand stock defaults"
+            }))
+        }
+    }
+}
+
+// PUT /api/brands/{id}/stock-defaults - задать уровни запаса по умолчанию для бренда (null - общий уровень)
+pub async fn set_brand_stock_defaults_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    request: web::Json<SetBrandStockDefaultsRequest>,
+) -> HttpResponse {
+    let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    if let Err(validation_errors) = request.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Validation failed",
+            "details": validation_errors
+        }));
+    }
+
+    if !request.levels_consistent() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "min_stock_level must not exceed max_stock_level"
+        }));
+    }
+
+    match repo.set_stock_defaults(id, &request).await {
+        Ok(Some(defaults)) => HttpResponse::Ok().json(defaults),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Brand not found"
+        })),
+        Err(e) => {
+            log_failure("setting", "stock defaults for brand", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to set brand stock defaults"
+            }))
+        }
+    }
+}
+
+// Сколько идентификаторов каждой зависимой сущности показывать в предпросмотре удаления
+const DEPENDENTS_SAMPLE_SIZE: i64 = 5;
+
+// GET /api/brands/{id}/dependents - предпросмотр записей, зависящих от бренда (перед удалением)
+pub async fn get_brand_dependents_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    match repo.find_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Brand not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "brand", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch brand"
+            }));
+        }
+    }
+
+    match repo.dependency_report(id, DEPENDENTS_SAMPLE_SIZE).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            log_failure("building", "dependency report for brand", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to build dependency report"
+            }))
+        }
+    }
+}
+
+// GET /api/brands/{id}/models-with-counts - модели бренда с количеством автомобилей в наличии
+pub async fn get_brand_models_with_counts_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+    let model_repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    match repo.find_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Brand not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "brand", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch brand"
+            }));
+        }
+    }
+
+    match model_repo.find_by_brand_with_counts(id).await {
+        Ok(models) => HttpResponse::Ok().json(models),
+        Err(e) => {
+            log_failure("fetching", "models with counts for brand", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch car models"
+            }))
+        }
+    }
+}
+
 // DELETE /api/brands/{id} - удалить бренд
 pub async fn delete_brand_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    query: web::Query<DryRunQuery>,
+    delete_query: web::Query<DeleteQuery>,
 ) -> HttpResponse {
     let repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
-    match repo.delete(id).await {
-        Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Brand not found"
+    match repo.delete_cascade(id, query.dry_run).await {
+        Ok(Some(report)) if report.is_blocked() => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Brand has dependent records",
+            "details": report
         })),
+        Ok(Some(report)) if report.dry_run => HttpResponse::Ok().json(report),
+        Ok(Some(_)) => HttpResponse::NoContent().finish(),
+        Ok(None) => not_found_or_deleted(delete_query.idempotent, "Brand not found"),
         Err(e) => {
-            eprintln!("Error deleting brand {}: {}", id, e);
+            log_failure("deleting", "brand", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to delete brand"
             }))
         }
     }
-}
\ No newline at end of file
+}
@@ -1,142 +1,448 @@
-use actix_web::{web, HttpResponse};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+use actix_web::{http::header, web, web::Bytes, HttpRequest, HttpResponse};
+use futures_util::stream;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::{CarStatus, CreateCarRequest, UpdateCarRequest},
+    handlers::common::{array_limit_exceeded, check_array_fields, check_batch_size, created, if_match_version, ensure_price_floor, price_below_floor, log_failure, negotiate_format, not_acceptable_response,
+                       resource_etag, ResponseFormat},
+    handlers::error::AppError,
+    models::{CarStatus, CreateCarRequest, UpdateCarRequest, DuplicateCarRequest, BatchGetCarsRequest, BatchGetCarsResponse, BulkCarImportResponse, CarChangesCursor, CarChangesResponse, DeleteQuery, ForceQuery, ApplicableWorks,
+             PartsCoverage, PricedItem, normalize_feature, is_valid_vin, to_csv},
+    repositories::brand_repository::BrandRepositoryImpl,
     repositories::car_repository::CarRepositoryImpl,
+    repositories::car_model_repository::CarModelRepositoryImpl,
+    repositories::part_repository::PartRepositoryImpl,
+    repositories::purchase_repository::PurchaseRepositoryImpl,
+    repositories::work_repository::WorkRepositoryImpl,
 };
-use crate::repositories::CarRepository;
+use crate::repositories::{BrandRepository, CarModelRepository, CarRepository, PartRepository, PurchaseRepository, WorkRepository};
+
+const DEFAULT_MOST_REQUESTED_LIMIT: i64 = 10;
+const MAX_MOST_REQUESTED_LIMIT: i64 = 100;
+
+const DEFAULT_RECENT_HOURS: i64 = 24;
+const MAX_RECENT_HOURS: i64 = 24 * 30;
+
+#[derive(Debug, Deserialize)]
+pub struct RecentCarsQuery {
+    pub hours: Option<i64>,
+}
+
+const DEFAULT_CHANGES_LIMIT: i64 = 100;
+const MAX_CHANGES_LIMIT: i64 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct CarChangesQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MostRequestedQuery {
+    pub limit: Option<i64>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Фильтры списка автомобилей. Неизвестные параметры (например, ещё не поддерживаемый `status`)
+/// отклоняются с 400, а не игнорируются молча: иначе клиент получил бы нефильтрованный список.
+///
+/// Допустимы любые сочетания:
+/// - без параметров - все автомобили, новые первыми;
+/// - только `feature` - автомобили с опцией, новые первыми;
+/// - только `min_price` и/или `max_price` - автомобили в диапазоне, по возрастанию цены;
+/// - `feature` вместе с ценой - автомобили с опцией, попавшие в диапазон, новые первыми.
+///
+/// Границы цены неотрицательные, `min_price` не больше `max_price` (равные - точная цена), иначе 400
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CarsQuery {
+    pub feature: Option<String>,
+    /// Диапазон цены, границы включительные; любую можно опустить
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+impl CarsQuery {
+    fn has_price_range(&self) -> bool {
+        self.min_price.is_some() || self.max_price.is_some()
+    }
+
+    fn price_in_range(&self, price: f64) -> bool {
+        self.min_price.is_none_or(|min| price >= min) && self.max_price.is_none_or(|max| price <= max)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CarFacetsQuery {
+    /// По умолчанию учитываются только автомобили в наличии (Available)
+    #[serde(default)]
+    pub include_unavailable: bool,
+}
+
+fn invalid_feature() -> AppError {
+    AppError::bad_request("Invalid feature: use letters, digits, '_' or '-' (up to 50 characters)")
+}
 
-// GET /api/cars - получить все автомобили
-pub async fn get_cars_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+// GET /api/cars - получить все автомобили (с фильтрами по опции и цене); JSON или CSV по заголовку Accept
+pub async fn get_cars_handler(
+    req: HttpRequest,
+    db_pool: web::Data<DbPool>,
+    query: web::Query<CarsQuery>,
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
-    match repo.find_all().await {
-        Ok(cars) => HttpResponse::Ok().json(cars),
-        Err(e) => {
-            eprintln!("Error fetching cars: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch cars"
-            }))
+
+    let format = match negotiate_format(&req) {
+        Some(format) => format,
+        None => return Ok(not_acceptable_response()),
+    };
+
+    let price_bounds = [query.min_price, query.max_price];
+    if price_bounds.iter().flatten().any(|price| !price.is_finite() || *price < 0.0) {
+        return Err(AppError::bad_request("'min_price' and 'max_price' must be non-negative numbers"));
+    }
+    if let (Some(min_price), Some(max_price)) = (query.min_price, query.max_price) {
+        if min_price > max_price {
+            return Err(AppError::bad_request("'min_price' must not be greater than 'max_price'"));
         }
     }
+
+    // Опция и диапазон цены вместе: выборка по опции, диапазон применяется к ней
+    let result = match query.feature.as_deref() {
+        Some(raw) => match normalize_feature(raw) {
+            Some(feature) => repo.find_by_feature(&feature).await.map(|mut cars| {
+                cars.retain(|car| query.price_in_range(car.price));
+                cars
+            }),
+            None => return Err(invalid_feature()),
+        },
+        None if query.has_price_range() => repo.find_by_price_range(query.min_price, query.max_price).await,
+        None => repo.find_all().await,
+    };
+
+    let cars = result?;
+    Ok(match format {
+        ResponseFormat::Json => HttpResponse::Ok()
+            .insert_header((header::VARY, "Accept"))
+            .json(cars),
+        ResponseFormat::Csv => HttpResponse::Ok()
+            .insert_header((header::VARY, "Accept"))
+            .content_type("text/csv; charset=utf-8")
+            .body(to_csv(&cars)),
+    })
+}
+// GET /api/cars/facets - значения фильтров (топливо, КПП, статус, цена) с количеством автомобилей
+pub async fn get_car_facets_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<CarFacetsQuery>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+
+    let facets = repo.facets(!query.include_unavailable).await?;
+    Ok(HttpResponse::Ok().json(facets))
 }
+
 // GET /api/cars/vin/{vin} - получить автомобиль по VIN
 pub async fn get_car_by_vin_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<String>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
     let vin = path.into_inner();
 
-    match repo.find_by_vin(&vin).await {
-        Ok(Some(car)) => HttpResponse::Ok().json(car),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found"
-        })),
-        Err(e) => {
-            eprintln!("Error fetching car by VIN {}: {}", vin, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch car"
-            }))
-        }
-    }
+    let car = repo.find_by_vin(&vin).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(HttpResponse::Ok().json(car))
 }
 
 // GET /api/cars/{id} - получить автомобиль по ID
 pub async fn get_car_by_id_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
-    match repo.find_by_id(id).await {
-        Ok(Some(car)) => HttpResponse::Ok().json(car),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found"
-        })),
-        Err(e) => {
-            eprintln!("Error fetching car {}: {}", id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch car"
-            }))
+    let car = repo.find_by_id(id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, resource_etag(car.id, car.updated_at)))
+        .json(car))
+}
+
+// GET /api/cars/{id}/detail - автомобиль с названиями бренда и модели
+pub async fn get_car_detail_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let detail = repo.find_detail_by_id(id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(HttpResponse::Ok().json(detail))
+}
+
+// POST /api/cars/batch-get - получить автомобили по списку ID (в порядке запроса)
+pub async fn batch_get_cars_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    batch_request: web::Json<BatchGetCarsRequest>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+
+    check_batch_size(batch_request.ids.len(), config.limits.max_batch_size)?;
+
+    // Повторяющиеся ID возвращаются один раз, на месте первого вхождения
+    let mut seen = HashSet::new();
+    let ids: Vec<Uuid> = batch_request.ids.iter()
+        .copied()
+        .filter(|id| seen.insert(*id))
+        .collect();
+
+    let cars = repo.find_by_ids(&ids).await?;
+    let mut cars_by_id: HashMap<Uuid, _> = cars.into_iter().map(|car| (car.id, car)).collect();
+    let mut items = Vec::with_capacity(ids.len());
+    let mut not_found = Vec::new();
+    for id in ids {
+        match cars_by_id.remove(&id) {
+            Some(car) => items.push(car),
+            None => not_found.push(id),
         }
     }
+
+    Ok(HttpResponse::Ok().json(BatchGetCarsResponse { items, not_found }))
 }
 
 // GET /api/cars/status/{status} - получить автомобили по статусу
 pub async fn get_cars_by_status_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<CarStatus>
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
     let status = path.into_inner();
 
-    match repo.find_by_status(status).await {
-        Ok(cars) => HttpResponse::Ok().json(cars),
-        Err(e) => {
-            eprintln!("Error fetching cars by status: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch cars"
-            }))
-        }
+    let cars = repo.find_by_status(status).await?;
+    Ok(HttpResponse::Ok().json(cars))
+}
+
+// Единая проверка соответствия модели бренду для создания и обновления автомобиля
+async fn ensure_model_belongs_to_brand(
+    db_pool: &DbPool,
+    model_id: Uuid,
+    brand_id: Uuid,
+) -> Result<(), AppError> {
+    let model_repo = CarModelRepositoryImpl::new(db_pool.clone());
+
+    if model_repo.belongs_to_brand(model_id, brand_id).await? {
+        Ok(())
+    } else {
+        Err(model_not_in_brand())
     }
 }
 
+fn model_not_in_brand() -> AppError {
+    AppError::bad_request("Car model does not belong to the specified brand")
+}
+
+// Ошибка записи пакета: к details добавляется индекс записи (с нуля)
+fn failed_at(index: usize, error: AppError) -> AppError {
+    error.with_details(serde_json::json!({ "failed_index": index }))
+}
+
 // POST /api/cars - создать автомобиль
 pub async fn create_car_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     create_request: web::Json<CreateCarRequest>,
-) -> HttpResponse {
-    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_price_rounding(config.pricing.price_rounding);
 
-    if let Err(validation_errors) = create_request.validate() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        }));
+    create_request.validate()?;
+
+    ensure_model_belongs_to_brand(
+        db_pool.get_ref(),
+        create_request.model_id,
+        create_request.brand_id,
+    ).await?;
+
+    ensure_price_floor(
+        db_pool.get_ref(),
+        create_request.brand_id,
+        PricedItem::Car,
+        repo.round_price(create_request.price),
+    ).await?;
+
+    if repo.exists_by_vin(&create_request.vin).await? {
+        return Err(AppError::conflict("VIN already exists"));
+    }
+
+    let car = repo.save(&create_request).await?;
+    Ok(created("/api/cars", car.id).json(car))
+}
+
+// POST /api/cars/bulk - массовый импорт автомобилей (всё или ничего)
+pub async fn bulk_import_cars_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    create_requests: web::Json<Vec<CreateCarRequest>>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_price_rounding(config.pricing.price_rounding);
+    let model_repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
+    let brand_repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+
+    check_batch_size(create_requests.len(), config.limits.max_batch_size)?;
+
+    // Сначала проверки без обращения к базе: валидация полей и повторы VIN внутри пакета
+    let mut seen_vins: HashSet<&str> = HashSet::new();
+    for (index, create_request) in create_requests.iter().enumerate() {
+        create_request.validate().map_err(|errors| failed_at(index, errors.into()))?;
+        if !seen_vins.insert(&create_request.vin) {
+            return Err(failed_at(index, AppError::bad_request("Duplicate VIN in batch")));
+        }
     }
 
-    match repo.save(&create_request).await {
-        Ok(car) => HttpResponse::Created().json(car),
-        Err(e) => {
-            eprintln!("Error creating car: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to create car"
-            }))
+    // Кэш проверок: (model_id, brand_id) -> модель принадлежит бренду, brand_id -> минимальная цена автомобиля
+    let mut models: HashMap<(Uuid, Uuid), bool> = HashMap::new();
+    let mut price_floors: HashMap<Uuid, Option<f64>> = HashMap::new();
+
+    for (index, create_request) in create_requests.iter().enumerate() {
+        if repo.exists_by_vin(&create_request.vin).await? {
+            return Err(failed_at(index, AppError::conflict("VIN already exists")));
+        }
+
+        let model_key = (create_request.model_id, create_request.brand_id);
+        if let Entry::Vacant(entry) = models.entry(model_key) {
+            entry.insert(model_repo.belongs_to_brand(create_request.model_id, create_request.brand_id).await?);
+        }
+        if !models[&model_key] {
+            return Err(failed_at(index, model_not_in_brand()));
+        }
+
+        if let Entry::Vacant(entry) = price_floors.entry(create_request.brand_id) {
+            let floor = brand_repo.find_price_floor(create_request.brand_id).await?;
+            entry.insert(floor.and_then(|floor| floor.min_price(PricedItem::Car)));
+        }
+        if let Some(min_price) = price_floors[&create_request.brand_id] {
+            let price = repo.round_price(create_request.price);
+            if price < min_price {
+                return Err(failed_at(index, price_below_floor(price, min_price)));
+            }
         }
     }
+
+    let cars = repo.save_many(&create_requests).await?;
+    Ok(HttpResponse::Created().json(BulkCarImportResponse::inserted(cars)))
+}
+
+// POST /api/cars/{id}/duplicate - создать копию автомобиля с новым VIN
+pub async fn duplicate_car_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    duplicate_request: web::Json<DuplicateCarRequest>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let car_id = path.into_inner();
+
+    duplicate_request.validate()?;
+
+    let vin = duplicate_request.vin.trim().to_uppercase();
+    if !is_valid_vin(&vin) {
+        return Err(AppError::bad_request("Invalid VIN").with_details(serde_json::json!({ "vin": vin })));
+    }
+
+    if repo.exists_by_vin(&vin).await? {
+        return Err(AppError::conflict("Car with this VIN already exists"));
+    }
+
+    let car = repo.duplicate(car_id, &vin, duplicate_request.mileage).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(created("/api/cars", car.id).json(car))
 }
 
 // PUT /api/cars/{id} - обновить автомобиль
 pub async fn update_car_handler(
+    req: HttpRequest,
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
     update_request: web::Json<UpdateCarRequest>,
-) -> HttpResponse {
-    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_price_rounding(config.pricing.price_rounding);
     let id = path.into_inner();
 
-    if let Err(validation_errors) = update_request.validate() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        }));
+    update_request.validate()?;
+    check_array_fields(&*update_request, config.limits.max_array_len)?;
+
+    // VIN можно оставить прежним, но нельзя забрать у другого автомобиля
+    if let Some(vin) = &update_request.vin {
+        if repo.find_id_by_vin(vin).await?.is_some_and(|owner_id| owner_id != id) {
+            return Err(AppError::conflict("VIN already exists"));
+        }
     }
 
-    match repo.update(id, &update_request).await {
-        Ok(Some(car)) => HttpResponse::Ok().json(car),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found"
-        })),
-        Err(e) => {
-            eprintln!("Error updating car {}: {}", id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to update car"
-            }))
+    // Текущая запись нужна для проверки If-Match, при смене бренда или модели и при смене цены
+    let changes_model = update_request.brand_id.is_some() || update_request.model_id.is_some();
+    let changes_price = update_request.brand_id.is_some() || update_request.price.is_some();
+    let mut expected_updated_at = None;
+    if changes_model || changes_price || req.headers().contains_key(header::IF_MATCH) {
+        let car = repo.find_by_id(id).await?
+            .ok_or_else(|| AppError::not_found("Car not found"))?;
+
+        expected_updated_at = if_match_version(&req, car.id, car.updated_at)?;
+
+        // При смене бренда или модели недостающее значение берём из текущей записи
+        if changes_model {
+            ensure_model_belongs_to_brand(
+                db_pool.get_ref(),
+                update_request.model_id.unwrap_or(car.model_id),
+                update_request.brand_id.unwrap_or(car.brand_id),
+            ).await?;
         }
+
+        // Порог проверяется и при смене бренда: текущая цена может оказаться ниже порога нового бренда
+        if changes_price {
+            ensure_price_floor(
+                db_pool.get_ref(),
+                update_request.brand_id.unwrap_or(car.brand_id),
+                PricedItem::Car,
+                update_request.price.map(|price| repo.round_price(price)).unwrap_or(car.price),
+            ).await?;
+        }
+    }
+
+    let car = match repo.update(id, &update_request, expected_updated_at).await? {
+        Some(car) => car,
+        None => return Err(update_missed(&repo, id, expected_updated_at).await),
+    };
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, resource_etag(car.id, car.updated_at)))
+        .json(car))
+}
+
+// Условный UPDATE не затронул ни одной строки: автомобиль удалён (404) или изменён после проверки If-Match (412)
+async fn update_missed(repo: &CarRepositoryImpl, id: Uuid, expected_updated_at: Option<DateTime<Utc>>) -> AppError {
+    if expected_updated_at.is_none() {
+        return AppError::not_found("Car not found");
+    }
+
+    match repo.find_by_id(id).await {
+        Ok(Some(car)) => AppError::precondition_failed(
+            "Resource has been modified (If-Match does not match current ETag)",
+            Some(&resource_etag(car.id, car.updated_at)),
+        ),
+        Ok(None) => AppError::not_found("Car not found"),
+        Err(e) => e.into(),
     }
 }
 
@@ -144,66 +450,95 @@ pub async fn update_car_handler(
 pub async fn delete_car_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
+    query: web::Query<DeleteQuery>,
+    force_query: web::Query<ForceQuery>,
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let purchase_repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
-    match repo.delete(id).await {
-        Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found"
-        })),
-        Err(e) => {
-            eprintln!("Error deleting car {}: {}", id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to delete car"
-            }))
+    // При удалении незавершённые заявки отклоняются, поэтому без force они блокируют удаление
+    if !force_query.force {
+        let count = purchase_repo.count_active_for_car(id).await?;
+        if count > 0 {
+            return Err(AppError::conflict("Car has active purchase requests")
+                .with_details(serde_json::json!({ "active_purchase_requests": count })));
         }
     }
+
+    if repo.delete(id).await? || query.idempotent {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(AppError::not_found("Car not found"))
+    }
+}
+
+// POST /api/cars/{id}/restore - восстановить удалённый автомобиль
+pub async fn restore_car_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let record = repo.find_including_deleted(id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    if record.deleted_at.is_none() {
+        return Err(AppError::conflict("Car is not deleted"));
+    }
+
+    // None - восстановлен параллельным запросом между проверкой и обновлением
+    let car = repo.restore(id).await?
+        .ok_or_else(|| AppError::conflict("Car is not deleted"))?;
+    Ok(HttpResponse::Ok().json(car))
 }
 
 // PATCH /api/cars/{id}/status - обновить статус автомобиля
 pub async fn update_car_status_handler(
+    req: HttpRequest,
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
     status: web::Json<CarStatus>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
     let new_status = status.into_inner();
 
-    match repo.update_status(id, new_status).await {
-        Ok(Some(car)) => HttpResponse::Ok().json(car),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found"
-        })),
-        Err(e) => {
-            eprintln!("Error updating car status {}: {}", id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to update car status"
-            }))
-        }
+    let mut expected_updated_at = None;
+    if req.headers().contains_key(header::IF_MATCH) {
+        let car = repo.find_by_id(id).await?
+            .ok_or_else(|| AppError::not_found("Car not found"))?;
+        expected_updated_at = if_match_version(&req, car.id, car.updated_at)?;
     }
+
+    let car = match repo.update_status(id, new_status, expected_updated_at).await? {
+        Some(car) => car,
+        None => return Err(update_missed(&repo, id, expected_updated_at).await),
+    };
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, resource_etag(car.id, car.updated_at)))
+        .json(car))
 }
 // PATCH /api/cars/{car_id}/completed-campaigns/{campaign_id} - добавить выполненную сервисную кампанию
 pub async fn add_completed_campaign_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<(Uuid, Uuid)>,
-) -> HttpResponse {
-    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_max_array_len(config.limits.max_array_len);
     let (car_id, campaign_id) = path.into_inner();
 
-    match repo.add_completed_campaign(car_id, campaign_id).await {
-        Ok(Some(car)) => HttpResponse::Ok().json(car),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found or campaign already added"
-        })),
-        Err(e) => {
-            eprintln!("Error adding completed campaign to car {}: {}", car_id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to add completed campaign"
-            }))
+    if let Some(car) = repo.add_completed_campaign(car_id, campaign_id).await? {
+        return Ok(HttpResponse::Ok().json(car));
+    }
+
+    // Ничего не добавлено: автомобиля нет, кампания уже отмечена или список заполнен до лимита
+    match repo.find_by_id(car_id).await? {
+        Some(car) if !car.completed_service_campaigns.contains(&campaign_id) => {
+            Err(array_limit_exceeded("completed_service_campaigns", config.limits.max_array_len))
         }
+        _ => Err(AppError::not_found("Car not found or campaign already added")),
     }
 }
 
@@ -211,80 +546,947 @@ pub async fn add_completed_campaign_handler(
 pub async fn remove_completed_campaign_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<(Uuid, Uuid)>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
     let (car_id, campaign_id) = path.into_inner();
 
-    match repo.remove_completed_campaign(car_id, campaign_id).await {
-        Ok(Some(car)) => HttpResponse::Ok().json(car),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found"
-        })),
-        Err(e) => {
-            eprintln!("Error removing completed campaign from car {}: {}", car_id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to remove completed campaign"
-            }))
-        }
-    }
+    let car = repo.remove_completed_campaign(car_id, campaign_id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(HttpResponse::Ok().json(car))
 }
 
 // DELETE /api/cars/{car_id}/completed-campaigns - очистить все выполненные сервисные кампании
 pub async fn clear_completed_campaigns_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
     let car_id = path.into_inner();
 
-    match repo.clear_completed_campaigns(car_id).await {
-        Ok(Some(car)) => HttpResponse::Ok().json(car),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car not found"
-        })),
-        Err(e) => {
-            eprintln!("Error clearing completed campaigns for car {}: {}", car_id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to clear completed campaigns"
-            }))
-        }
+    let car = repo.clear_completed_campaigns(car_id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(HttpResponse::Ok().json(car))
+}
+
+// PATCH /api/cars/{car_id}/features/{feature} - добавить опцию автомобилю
+pub async fn add_car_feature_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    path: web::Path<(Uuid, String)>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_max_array_len(config.limits.max_array_len);
+    let (car_id, raw_feature) = path.into_inner();
+
+    let feature = match normalize_feature(&raw_feature) {
+        Some(feature) => feature,
+        None => return Err(invalid_feature()),
+    };
+
+    if let Some(car) = repo.add_feature(car_id, &feature).await? {
+        return Ok(HttpResponse::Ok().json(car));
+    }
+
+    // Существующий автомобиль не изменился только при заполненном до лимита списке опций
+    match repo.find_by_id(car_id).await? {
+        Some(_) => Err(array_limit_exceeded("features", config.limits.max_array_len)),
+        None => Err(AppError::not_found("Car not found")),
     }
 }
 
+// DELETE /api/cars/{car_id}/features/{feature} - удалить опцию автомобиля
+pub async fn remove_car_feature_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<(Uuid, String)>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let (car_id, raw_feature) = path.into_inner();
+
+    let feature = match normalize_feature(&raw_feature) {
+        Some(feature) => feature,
+        None => return Err(invalid_feature()),
+    };
+
+    let car = repo.remove_feature(car_id, &feature).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(HttpResponse::Ok().json(car))
+}
+
 // GET /api/cars/{car_id}/pending-campaigns - получить ожидающие сервисные кампании для автомобиля
 pub async fn get_pending_campaigns_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
-    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_strict_enum_decoding(config.database.strict_enum_decoding);
     let car_id = path.into_inner();
 
-    match repo.get_pending_campaigns_for_car(car_id).await {
-        Ok(campaigns) => HttpResponse::Ok().json(campaigns),
-        Err(e) => {
-            eprintln!("Error fetching pending campaigns for car {}: {}", car_id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch pending campaigns"
-            }))
-        }
-    }
+    let campaigns = repo.get_pending_campaigns_for_car(car_id).await?;
+    Ok(HttpResponse::Ok().json(campaigns))
 }
 
 // GET /api/cars/completed-campaign/{campaign_id} - получить автомобили с выполненной сервисной кампанией
 pub async fn get_cars_by_completed_campaign_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
     let campaign_id = path.into_inner();
 
-    match repo.get_cars_by_completed_campaign(campaign_id).await {
-        Ok(cars) => HttpResponse::Ok().json(cars),
-        Err(e) => {
-            eprintln!("Error fetching cars by completed campaign {}: {}", campaign_id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch cars"
-            }))
+    let cars = repo.get_cars_by_completed_campaign(campaign_id).await?;
+    Ok(HttpResponse::Ok().json(cars))
+}
+
+// GET /api/cars/most-requested - получить автомобили с наибольшим количеством заявок
+pub async fn get_most_requested_cars_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<MostRequestedQuery>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+    let limit = query.limit
+        .unwrap_or(DEFAULT_MOST_REQUESTED_LIMIT)
+        .clamp(1, MAX_MOST_REQUESTED_LIMIT);
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from >= to {
+            return Err(AppError::bad_request("'from' must be earlier than 'to'"));
         }
     }
-}
\ No newline at end of file
+
+    let cars = repo.most_requested_cars(limit, query.from, query.to).await?;
+    Ok(HttpResponse::Ok().json(cars))
+}
+
+// GET /api/cars/changes - получить изменённые автомобили порциями (курсорная пагинация)
+pub async fn get_car_changes_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<CarChangesQuery>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let limit = query.limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, MAX_CHANGES_LIMIT);
+
+    let cursor = match query.cursor.as_deref() {
+        Some(token) => match CarChangesCursor::decode(token) {
+            Some(cursor) => Some(cursor),
+            None => {
+                return Err(AppError::bad_request("Invalid cursor"));
+            }
+        },
+        None => None,
+    };
+
+    // Запрашиваем на одну запись больше, чтобы понять, есть ли следующая страница
+    let mut records = repo.find_changed_after_cursor(cursor, limit + 1).await?;
+    let has_more = records.len() as i64 > limit;
+    records.truncate(limit as usize);
+    let next_cursor = records.last()
+        .map(|record| CarChangesCursor::from_car(&record.car).encode())
+        .or_else(|| query.cursor.clone());
+
+    Ok(HttpResponse::Ok().json(CarChangesResponse {
+        items: records,
+        next_cursor,
+        has_more,
+    }))
+}
+
+// GET /api/cars/recent - получить автомобили, изменённые за последние N часов
+pub async fn get_recent_cars_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<RecentCarsQuery>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let hours = query.hours.unwrap_or(DEFAULT_RECENT_HOURS);
+
+    if hours <= 0 {
+        return Err(AppError::bad_request("'hours' must be greater than 0"));
+    }
+
+    let cars = repo.find_recently_updated(hours.min(MAX_RECENT_HOURS)).await?;
+    Ok(HttpResponse::Ok().json(cars))
+}
+
+// Размер пачки при выгрузке: в памяти одновременно держится не больше одной пачки
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct CarExportQuery {
+    #[serde(default)]
+    pub include_names: bool,
+}
+
+struct CarExportState {
+    repo: CarRepositoryImpl,
+    include_names: bool,
+    after_id: Option<Uuid>,
+    started: bool,
+    written: bool,
+    finished: bool,
+}
+
+// GET /api/cars/export.json - выгрузить все автомобили одним JSON-массивом (потоково)
+pub async fn export_cars_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<CarExportQuery>,
+) -> HttpResponse {
+    let state = CarExportState {
+        repo: CarRepositoryImpl::new(db_pool.get_ref().clone()),
+        include_names: query.include_names,
+        after_id: None,
+        started: false,
+        written: false,
+        finished: false,
+    };
+
+    let body = stream::unfold(state, |mut state| async move {
+        if state.finished {
+            return None;
+        }
+
+        let mut chunk = Vec::new();
+        if !state.started {
+            chunk.push(b'[');
+            state.started = true;
+        }
+
+        let batch = match state.repo.find_export_batch(state.after_id, EXPORT_BATCH_SIZE).await {
+            Ok(batch) => batch,
+            Err(e) => {
+                // Заголовки уже отправлены - обрываем поток, клиент получит незавершённый JSON
+                log_failure("exporting", "cars", None, &e);
+                state.finished = true;
+                return Some((Err(actix_web::error::ErrorInternalServerError("Failed to export cars")), state));
+            }
+        };
+
+        state.after_id = batch.last().map(|car| car.id);
+        state.finished = (batch.len() as i64) < EXPORT_BATCH_SIZE;
+
+        for mut car in batch {
+            if !state.include_names {
+                car.brand_name = None;
+                car.model_name = None;
+            }
+            if state.written {
+                chunk.push(b',');
+            }
+            if let Err(e) = serde_json::to_writer(&mut chunk, &car) {
+                log_failure("serializing", "car", Some(&car.id), &e);
+                state.finished = true;
+                return Some((Err(actix_web::error::ErrorInternalServerError("Failed to export cars")), state));
+            }
+            state.written = true;
+        }
+
+        if state.finished {
+            chunk.push(b']');
+        }
+
+        Some((Ok::<_, actix_web::Error>(Bytes::from(chunk)), state))
+    });
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header(("Content-Disposition", "attachment; filename=\"cars.json\""))
+        .streaming(body)
+}
+
+// GET /api/cars/{id}/timeline - полная история автомобиля в хронологическом порядке
+pub async fn get_car_timeline_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let timeline = repo.find_timeline(id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+    Ok(HttpResponse::Ok().json(timeline))
+}
+
+// GET /api/cars/{id}/applicable-works - работы для модели автомобиля с оценкой стоимости
+pub async fn get_car_applicable_works_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let work_repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let car = repo.find_by_id(id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+
+    let works = work_repo.find_by_car_model(car.model_id).await?;
+    Ok(HttpResponse::Ok().json(ApplicableWorks::new(
+        car.id,
+        car.model_id,
+        config.pricing.labor_hour_rate,
+        works,
+    )))
+}
+
+// GET /api/cars/{id}/parts-coverage - запчасти модели автомобиля и их наличие на складе
+pub async fn get_car_parts_coverage_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let part_repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let car = repo.find_by_id(id).await?
+        .ok_or_else(|| AppError::not_found("Car not found"))?;
+
+    let parts = part_repo.find_stock_by_car_model(car.model_id).await?;
+    Ok(HttpResponse::Ok().json(PartsCoverage::new(car.id, car.model_id, parts)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use crate::database::test_pool;
+    use crate::models::{car::Car, FuelType, Transmission};
+
+    // Тесты с базой идут параллельно, поэтому у каждого свой бренд: модель - тот же id с другим последним байтом
+    struct TestBrand {
+        brand_id: Uuid,
+        model_id: Uuid,
+    }
+
+    impl TestBrand {
+        fn new(n: u8) -> Self {
+            Self {
+                brand_id: Uuid::from_bytes([0xab, 0xab, 0xab, 0xab, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, 1]),
+                model_id: Uuid::from_bytes([0xab, 0xab, 0xab, 0xab, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, 2]),
+            }
+        }
+
+        // Бренд с моделью и порогом цены автомобиля; удаление бренда каскадом убирает модель и порог
+        async fn insert_with_floor(&self, pool: &DbPool, min_car_price: f64) {
+            self.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(self.brand_id)
+                .bind(format!("Test Brand {}", self.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(self.model_id)
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO brand_price_floors (brand_id, min_car_price) VALUES ($1, $2)")
+                .bind(self.brand_id)
+                .bind(min_car_price)
+                .execute(pool).await.unwrap();
+        }
+
+        // Покупатель бренда: тот же id с последним байтом 3
+        fn customer_id(&self) -> Uuid {
+            let mut bytes = *self.brand_id.as_bytes();
+            bytes[15] = 3;
+            Uuid::from_bytes(bytes)
+        }
+
+        // Заявка покупателя бренда на автомобиль в заданном статусе
+        async fn purchase(&self, pool: &DbPool, car_id: Uuid, status: &str) -> Uuid {
+            sqlx::query(
+                "INSERT INTO customers (id, first_name, last_name, email, phone) VALUES ($1, 'Test', 'Buyer', $2, '')
+                 ON CONFLICT (id) DO NOTHING"
+            )
+                .bind(self.customer_id())
+                .bind(format!("buyer-{}@example.com", self.customer_id()))
+                .execute(pool).await.unwrap();
+            sqlx::query_scalar("INSERT INTO purchase_requests (car_id, customer_id, status) VALUES ($1, $2, $3) RETURNING id")
+                .bind(car_id)
+                .bind(self.customer_id())
+                .bind(status)
+                .fetch_one(pool).await.unwrap()
+        }
+
+        // Заявки удаляются каскадом вместе с покупателем и автомобилями
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM customers WHERE id = $1")
+                .bind(self.customer_id())
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM cars WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+
+        fn create_request(&self, vin: &str, price: f64) -> CreateCarRequest {
+            CreateCarRequest {
+                brand_id: self.brand_id,
+                model_id: self.model_id,
+                year: 2020,
+                price,
+                mileage: 0,
+                color: "Black".to_string(),
+                vin: vin.to_string(),
+                fuel_type: FuelType::Petrol,
+                transmission: Transmission::Automatic,
+            }
+        }
+    }
+
+    fn config_with_rounding(price_rounding: f64) -> web::Data<Config> {
+        let mut config = Config::from_env().expect("test configuration");
+        config.pricing.price_rounding = Some(price_rounding);
+        web::Data::new(config)
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn create_car_checks_price_floor_against_rounded_price() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(1);
+        brand.insert_with_floor(&pool, 1_000_300.0).await;
+
+        // 1 000 400 выше порога, но после округления до 1000 записалось бы 1 000 000
+        let error = create_car_handler(
+            web::Data::new(pool.clone()),
+            config_with_rounding(1000.0),
+            web::Json(brand.create_request("ABABAB00000000001", 1_000_400.0)),
+        ).await.unwrap_err();
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM cars WHERE brand_id = $1")
+            .bind(brand.brand_id)
+            .fetch_one(&pool).await.unwrap();
+        brand.remove(&pool).await;
+
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(stored, 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn create_car_sets_location_header() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(6);
+        brand.insert_with_floor(&pool, 0.0).await;
+
+        let response = create_car_handler(
+            web::Data::new(pool.clone()),
+            config_with_rounding(1.0),
+            web::Json(brand.create_request("ABABAB00000000006", 1_500_000.0)),
+        ).await.unwrap();
+        let status = response.status();
+        let location = response.headers().get(header::LOCATION).cloned();
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        brand.remove(&pool).await;
+
+        let car: Car = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status, actix_web::http::StatusCode::CREATED);
+        assert_eq!(location.unwrap(), format!("/api/cars/{}", car.id).as_str());
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn bulk_import_checks_price_floor_against_rounded_price() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(2);
+        brand.insert_with_floor(&pool, 1_000_300.0).await;
+
+        let error = bulk_import_cars_handler(
+            web::Data::new(pool.clone()),
+            config_with_rounding(1000.0),
+            web::Json(vec![brand.create_request("ABABAB00000000002", 1_000_400.0)]),
+        ).await.unwrap_err();
+        brand.remove(&pool).await;
+
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(error.error_response().into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["details"]["price"], 1_000_000.0);
+        assert_eq!(body["error"]["details"]["failed_index"], 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn update_car_checks_price_floor_against_rounded_price() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(3);
+        brand.insert_with_floor(&pool, 1_000_300.0).await;
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000003", 2_000_000.0)).await.unwrap();
+
+        let update_request: UpdateCarRequest = serde_json::from_value(serde_json::json!({ "price": 1_000_400.0 })).unwrap();
+        let error = update_car_handler(
+            actix_web::test::TestRequest::default().to_http_request(),
+            web::Data::new(pool.clone()),
+            config_with_rounding(1000.0),
+            web::Path::from(car.id),
+            web::Json(update_request),
+        ).await.unwrap_err();
+        let stored: f64 = sqlx::query_scalar("SELECT price FROM cars WHERE id = $1")
+            .bind(car.id)
+            .fetch_one(&pool).await.unwrap();
+        brand.remove(&pool).await;
+
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(stored, 2_000_000.0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn car_changes_include_deleted_cars() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(4);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let repo = CarRepositoryImpl::new(pool.clone());
+        let car = repo.save(&brand.create_request("ABABAB00000000004", 10_000.0)).await.unwrap();
+        let before_create = CarChangesCursor { updated_at: car.updated_at - chrono::Duration::microseconds(1), id: Uuid::nil() };
+        assert!(repo.delete(car.id).await.unwrap());
+
+        let resp = get_car_changes_handler(
+            web::Data::new(pool.clone()),
+            web::Query(CarChangesQuery { cursor: Some(before_create.encode()), limit: Some(1000) }),
+        ).await.unwrap();
+        brand.remove(&pool).await;
+
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let item = body["items"].as_array().unwrap().iter()
+            .find(|item| item["id"] == car.id.to_string())
+            .expect("deleted car is missing from the changes feed");
+        assert!(item["deleted_at"].is_string());
+        assert_eq!(item["vin"], "ABABAB00000000004");
+    }
+
+    async fn car_changes_page(pool: &DbPool, cursor: String, limit: i64) -> serde_json::Value {
+        let resp = get_car_changes_handler(
+            web::Data::new(pool.clone()),
+            web::Query(CarChangesQuery { cursor: Some(cursor), limit: Some(limit) }),
+        ).await.unwrap();
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn car_changes_pages_are_stable_across_inserts() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(11);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let repo = CarRepositoryImpl::new(pool.clone());
+        // Автомобили теста - единственные с updated_at в 2003 году: лента по ним детерминирована
+        let backdate = |vin: &'static str, updated_at: &'static str| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query("UPDATE cars SET updated_at = $1::timestamptz WHERE vin = $2")
+                    .bind(updated_at)
+                    .bind(vin)
+                    .execute(&pool).await.unwrap();
+            }
+        };
+        let mut ids = Vec::new();
+        for (vin, updated_at) in [
+            ("ABABAB00000000111", "2003-01-01T00:00:01Z"),
+            ("ABABAB00000000112", "2003-01-01T00:00:02Z"),
+            ("ABABAB00000000113", "2003-01-01T00:00:03Z"),
+        ] {
+            ids.push(repo.save(&brand.create_request(vin, 10_000.0)).await.unwrap().id.to_string());
+            backdate(vin, updated_at).await;
+        }
+        let start = CarChangesCursor { updated_at: "2003-01-01T00:00:00Z".parse().unwrap(), id: Uuid::nil() }.encode();
+
+        let first_page = car_changes_page(&pool, start, 2).await;
+        // Вставки между страницами: одна раньше курсора, другая - новое изменение в конце ленты
+        let earlier = repo.save(&brand.create_request("ABABAB00000000114", 10_000.0)).await.unwrap();
+        backdate("ABABAB00000000114", "2003-01-01T00:00:01.5Z").await;
+        let fresh = repo.save(&brand.create_request("ABABAB00000000115", 10_000.0)).await.unwrap();
+        let second_page = car_changes_page(&pool, first_page["next_cursor"].as_str().unwrap().to_string(), 1000).await;
+        brand.remove(&pool).await;
+
+        let page_ids = |page: &serde_json::Value| -> Vec<String> {
+            page["items"].as_array().unwrap().iter().map(|item| item["id"].as_str().unwrap().to_string()).collect()
+        };
+        let second_ids = page_ids(&second_page);
+        assert_eq!(page_ids(&first_page), ids[..2].to_vec());
+        assert_eq!(first_page["has_more"], true);
+        // Вторая страница продолжает с третьего автомобиля: без повторов и пропусков
+        assert_eq!(second_ids.first(), Some(&ids[2]));
+        assert!(!second_ids.contains(&ids[0]) && !second_ids.contains(&ids[1]));
+        assert!(!second_ids.contains(&earlier.id.to_string()));
+        assert!(second_ids.contains(&fresh.id.to_string()));
+    }
+
+    // Пул без соединения: обработчик отвечает ошибкой до первого запроса к базе
+    fn lazy_pool() -> web::Data<DbPool> {
+        web::Data::new(sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap())
+    }
+
+    #[actix_web::test]
+    async fn invalid_cursor_uses_app_error_body() {
+        let error = get_car_changes_handler(
+            lazy_pool(),
+            web::Query(CarChangesQuery { cursor: Some("not-a-cursor".to_string()), limit: None }),
+        ).await.unwrap_err();
+
+        let response = error.error_response();
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({
+            "error": { "code": "bad_request", "message": "Invalid cursor" }
+        }));
+    }
+
+    #[actix_web::test]
+    async fn bulk_import_validation_error_reports_failed_index() {
+        let brand = TestBrand::new(0);
+        let mut invalid = brand.create_request("ABABAB00000000005", 10_000.0);
+        invalid.year = 1900;
+        let error = bulk_import_cars_handler(
+            lazy_pool(),
+            web::Data::new(Config::from_env().expect("test configuration")),
+            web::Json(vec![brand.create_request("ABABAB00000000006", 10_000.0), invalid]),
+        ).await.unwrap_err();
+
+        let body = actix_web::body::to_bytes(error.error_response().into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "validation_failed");
+        assert_eq!(body["error"]["details"]["failed_index"], 1);
+        assert_eq!(body["error"]["details"]["fields"]["year"][0]["code"], "range");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn conditional_update_rejects_stale_version() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(5);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let repo = CarRepositoryImpl::new(pool.clone());
+        let car = repo.save(&brand.create_request("ABABAB00000000007", 10_000.0)).await.unwrap();
+
+        // Запись изменилась между проверкой If-Match и UPDATE
+        let current = repo.update_status(car.id, CarStatus::Reserved, Some(car.updated_at)).await.unwrap().unwrap();
+        let update_request: UpdateCarRequest = serde_json::from_value(serde_json::json!({ "price": 12_000.0 })).unwrap();
+        let stale_update = repo.update(car.id, &update_request, Some(car.updated_at)).await.unwrap();
+        let stale_status = repo.update_status(car.id, CarStatus::Sold, Some(car.updated_at)).await.unwrap();
+        let error = update_missed(&repo, car.id, Some(car.updated_at)).await;
+        let stored = repo.find_by_id(car.id).await.unwrap().unwrap();
+        brand.remove(&pool).await;
+
+        assert!(stale_update.is_none());
+        assert!(stale_status.is_none());
+        assert_eq!(stored.price, 10_000.0);
+        assert_eq!(stored.status, CarStatus::Reserved);
+        let response = error.error_response();
+        assert_eq!(response.status(), actix_web::http::StatusCode::PRECONDITION_FAILED);
+        assert_eq!(response.headers().get(header::ETAG).unwrap().to_str().unwrap(), resource_etag(current.id, current.updated_at));
+    }
+
+    async fn delete_car(pool: &DbPool, uri: &str) -> actix_web::dev::ServiceResponse {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .route("/api/cars/{id}", web::delete().to(delete_car_handler)),
+        ).await;
+        actix_web::test::call_service(&app, actix_web::test::TestRequest::delete().uri(uri).to_request()).await
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn delete_car_with_active_purchase_is_blocked_without_force() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(7);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000008", 10_000.0)).await.unwrap();
+        brand.purchase(&pool, car.id, "Pending").await;
+        brand.purchase(&pool, car.id, "Completed").await;
+
+        let resp = delete_car(&pool, &format!("/api/cars/{}", car.id)).await;
+        let status = resp.status();
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        let deleted_at: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT deleted_at FROM cars WHERE id = $1")
+            .bind(car.id)
+            .fetch_one(&pool).await.unwrap();
+        brand.remove(&pool).await;
+
+        assert_eq!(status, actix_web::http::StatusCode::CONFLICT);
+        assert_eq!(body["error"]["details"]["active_purchase_requests"], 1);
+        assert!(deleted_at.is_none());
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn forced_car_delete_rejects_active_purchases() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(8);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000009", 10_000.0)).await.unwrap();
+        let pending = brand.purchase(&pool, car.id, "Pending").await;
+        let completed = brand.purchase(&pool, car.id, "Completed").await;
+
+        let resp = delete_car(&pool, &format!("/api/cars/{}?force=true", car.id)).await;
+        let status = resp.status();
+        let statuses: Vec<(Uuid, String)> = sqlx::query_as("SELECT id, status FROM purchase_requests WHERE car_id = $1")
+            .bind(car.id)
+            .fetch_all(&pool).await.unwrap();
+        let deleted_at: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT deleted_at FROM cars WHERE id = $1")
+            .bind(car.id)
+            .fetch_one(&pool).await.unwrap();
+        brand.remove(&pool).await;
+
+        assert_eq!(status, actix_web::http::StatusCode::NO_CONTENT);
+        assert!(deleted_at.is_some());
+        assert!(statuses.contains(&(pending, "Rejected".to_string())));
+        assert!(statuses.contains(&(completed, "Completed".to_string())));
+    }
+
+    async fn get_cars(uri: &str) -> (actix_web::http::StatusCode, serde_json::Value) {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(lazy_pool())
+                .app_data(web::QueryConfig::default().error_handler(crate::handlers::common::query_error_handler))
+                .route("/api/cars", web::get().to(get_cars_handler)),
+        ).await;
+        let resp = actix_web::test::call_service(&app, actix_web::test::TestRequest::get().uri(uri).to_request()).await;
+        let status = resp.status();
+        (status, actix_web::test::read_body_json(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn get_cars_rejects_unknown_query_parameter() {
+        let (status, body) = get_cars("/api/cars?feature=sunroof&status=Sold").await;
+
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "bad_request");
+        assert_eq!(body["error"]["message"], "Invalid query parameters");
+        assert!(body["error"]["details"].as_str().unwrap().contains("unknown field `status`"));
+    }
+
+    #[actix_web::test]
+    async fn get_cars_rejects_min_price_above_max_price() {
+        let (status, body) = get_cars("/api/cars?feature=sunroof&min_price=2000000&max_price=1000000").await;
+
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["message"], "'min_price' must not be greater than 'max_price'");
+    }
+
+    async fn get_cars_as(pool: web::Data<DbPool>, uri: &str, accept: &str) -> actix_web::dev::ServiceResponse {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(pool)
+                .route("/api/cars", web::get().to(get_cars_handler)),
+        ).await;
+        let req = actix_web::test::TestRequest::get()
+            .uri(uri)
+            .insert_header((header::ACCEPT, accept))
+            .to_request();
+        actix_web::test::call_service(&app, req).await
+    }
+
+    #[actix_web::test]
+    async fn get_cars_without_acceptable_format_is_406() {
+        let resp = get_cars_as(lazy_pool(), "/api/cars", "application/xml").await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_ACCEPTABLE);
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert_eq!(body["supported"], serde_json::json!(["application/json", "text/csv"]));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn get_cars_returns_csv_or_json_by_accept_header() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(10);
+        brand.insert_with_floor(&pool, 0.0).await;
+        // Цена с копейками, чтобы диапазон выбрал только автомобиль теста
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000011", 7_654_321.5)).await.unwrap();
+        let uri = "/api/cars?min_price=7654321.5&max_price=7654321.5";
+
+        let csv = get_cars_as(web::Data::new(pool.clone()), uri, "text/csv").await;
+        let csv_content_type = csv.headers().get(header::CONTENT_TYPE).cloned();
+        let csv_body = actix_web::test::read_body(csv).await;
+        let json = get_cars_as(web::Data::new(pool.clone()), uri, "application/json").await;
+        let json_content_type = json.headers().get(header::CONTENT_TYPE).cloned();
+        let json_body: serde_json::Value = actix_web::test::read_body_json(json).await;
+        // Формат с большим q выигрывает
+        let preferred = get_cars_as(web::Data::new(pool.clone()), uri, "text/csv;q=0.5, application/json").await;
+        let preferred_content_type = preferred.headers().get(header::CONTENT_TYPE).cloned();
+        brand.remove(&pool).await;
+
+        let csv_body = String::from_utf8(csv_body.to_vec()).unwrap();
+        assert_eq!(csv_content_type.unwrap(), "text/csv; charset=utf-8");
+        assert!(csv_body.starts_with("id,brand_id,model_id,"));
+        assert_eq!(csv_body.lines().count(), 2);
+        assert!(csv_body.contains("ABABAB00000000011"));
+        assert_eq!(json_content_type.unwrap(), "application/json");
+        assert_eq!(json_body[0]["id"], car.id.to_string());
+        assert_eq!(json_body.as_array().unwrap().len(), 1);
+        assert_eq!(preferred_content_type.unwrap(), "application/json");
+    }
+
+    #[test]
+    fn cars_query_accepts_feature_with_inclusive_price_range() {
+        let query = web::Query::<CarsQuery>::from_query("feature=sunroof&min_price=1000&max_price=2000").unwrap();
+
+        assert!(query.has_price_range());
+        assert!(query.price_in_range(1000.0) && query.price_in_range(2000.0));
+        assert!(!query.price_in_range(999.99) && !query.price_in_range(2000.01));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn completed_campaigns_over_limit_are_rejected() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(9);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000010", 10_000.0)).await.unwrap();
+        let mut config = Config::from_env().expect("test configuration");
+        config.limits.max_array_len = 1;
+        let config = web::Data::new(config);
+        let add = |campaign_id: Uuid| add_completed_campaign_handler(
+            web::Data::new(pool.clone()),
+            config.clone(),
+            web::Path::from((car.id, campaign_id)),
+        );
+
+        let first = add(Uuid::new_v4()).await;
+        let error = add(Uuid::new_v4()).await.unwrap_err();
+        let stored = CarRepositoryImpl::new(pool.clone()).find_by_id(car.id).await.unwrap().unwrap();
+        brand.remove(&pool).await;
+
+        assert!(first.is_ok());
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(error.to_string(), "completed_service_campaigns must contain at most 1 items");
+        assert_eq!(stored.completed_service_campaigns.len(), 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn model_of_another_brand_is_rejected_on_create_and_update() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(12);
+        let other = TestBrand::new(13);
+        brand.insert_with_floor(&pool, 0.0).await;
+        other.insert_with_floor(&pool, 0.0).await;
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000012", 10_000.0)).await.unwrap();
+
+        let mismatched = CreateCarRequest { model_id: other.model_id, ..brand.create_request("ABABAB00000000013", 10_000.0) };
+        let create_error = create_car_handler(
+            web::Data::new(pool.clone()),
+            config_with_rounding(1.0),
+            web::Json(mismatched),
+        ).await.unwrap_err();
+        // Меняется только модель: бренд для проверки берётся из текущей записи
+        let update_request: UpdateCarRequest = serde_json::from_value(serde_json::json!({ "model_id": other.model_id })).unwrap();
+        let update_error = update_car_handler(
+            actix_web::test::TestRequest::default().to_http_request(),
+            web::Data::new(pool.clone()),
+            config_with_rounding(1.0),
+            web::Path::from(car.id),
+            web::Json(update_request),
+        ).await.unwrap_err();
+        let stored: Vec<Uuid> = sqlx::query_scalar("SELECT model_id FROM cars WHERE brand_id = $1")
+            .bind(brand.brand_id)
+            .fetch_all(&pool).await.unwrap();
+        brand.remove(&pool).await;
+        other.remove(&pool).await;
+
+        assert_eq!(create_error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(update_error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(stored, vec![brand.model_id]);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn export_streams_all_cars_as_one_json_array() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(14);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000014", 10_000.0)).await.unwrap();
+
+        let mut exports = Vec::new();
+        for include_names in [false, true] {
+            let response = export_cars_handler(
+                web::Data::new(pool.clone()),
+                web::Query(CarExportQuery { include_names }),
+            ).await;
+            assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+            let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+            let cars: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+            exports.push(cars.into_iter().find(|exported| exported["id"] == car.id.to_string()));
+        }
+        brand.remove(&pool).await;
+
+        // Названия бренда и модели попадают в выгрузку только по include_names
+        let without_names = exports[0].as_ref().unwrap();
+        let with_names = exports[1].as_ref().unwrap();
+        assert_eq!(without_names["vin"], "ABABAB00000000014");
+        assert!(without_names.get("brand_name").is_none());
+        assert_eq!(with_names["brand_name"], format!("Test Brand {}", brand.brand_id));
+        assert_eq!(with_names["model_name"], "Test Model");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn deleting_twice_is_not_found_unless_idempotent() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(15);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let car = CarRepositoryImpl::new(pool.clone())
+            .save(&brand.create_request("ABABAB00000000015", 10_000.0)).await.unwrap();
+        let delete = |idempotent: bool| delete_car_handler(
+            web::Data::new(pool.clone()),
+            web::Path::from(car.id),
+            web::Query(DeleteQuery { idempotent }),
+            web::Query(ForceQuery { force: false }),
+        );
+
+        let first = delete(false).await.unwrap();
+        let second = delete(false).await.unwrap_err();
+        let idempotent = delete(true).await.unwrap();
+        brand.remove(&pool).await;
+
+        assert_eq!(first.status(), actix_web::http::StatusCode::NO_CONTENT);
+        assert_eq!(second.status_code(), actix_web::http::StatusCode::NOT_FOUND);
+        assert_eq!(idempotent.status(), actix_web::http::StatusCode::NO_CONTENT);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn duplicate_copies_the_car_without_its_status() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(16);
+        brand.insert_with_floor(&pool, 0.0).await;
+        let repo = CarRepositoryImpl::new(pool.clone());
+        let source = repo.save(&brand.create_request("ABABAB00000000016", 25_000.0)).await.unwrap();
+        repo.update_status(source.id, CarStatus::Sold, None).await.unwrap();
+        repo.add_feature(source.id, "test-sunroof-16").await.unwrap();
+        let duplicate = |vin: &str| duplicate_car_handler(
+            web::Data::new(pool.clone()),
+            web::Path::from(source.id),
+            web::Json(DuplicateCarRequest { vin: vin.to_string(), mileage: Some(120) }),
+        );
+
+        // VIN приводится к верхнему регистру; повтор того же VIN - конфликт
+        let response = duplicate("ababab00000000017").await.unwrap();
+        let status = response.status();
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        let conflict = duplicate("ABABAB00000000017").await.unwrap_err();
+        brand.remove(&pool).await;
+
+        let copy: Car = serde_json::from_slice(&body).unwrap();
+        assert_eq!(status, actix_web::http::StatusCode::CREATED);
+        assert_ne!(copy.id, source.id);
+        assert_eq!(copy.vin, "ABABAB00000000017");
+        assert_eq!((copy.brand_id, copy.model_id, copy.price), (source.brand_id, source.model_id, source.price));
+        assert_eq!(copy.mileage, 120);
+        assert_eq!(copy.status, CarStatus::Available);
+        assert!(copy.features.is_empty());
+        assert_eq!(conflict.status_code(), actix_web::http::StatusCode::CONFLICT);
+    }
+}
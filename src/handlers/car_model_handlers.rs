@@ -1,21 +1,25 @@
 use actix_web::{web, HttpResponse};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::{CreateCarModelRequest, UpdateCarModelRequest},
+    handlers::common::{cacheable_ok, created, log_failure, not_found_or_deleted},
+    models::{CreateCarModelRequest, UpdateCarModelRequest, DryRunQuery, DeleteQuery},
     repositories::car_model_repository::CarModelRepositoryImpl,
+    repositories::warehouse_repository::{WarehouseRepository, WarehouseRepositoryImpl},
 };
 use crate::repositories::CarModelRepository;
 
 // GET /api/car-models - получить все модели автомобилей
-pub async fn get_car_models_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+pub async fn get_car_models_handler(db_pool: web::Data<DbPool>, config: web::Data<Config>) -> HttpResponse {
     let repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
     match repo.find_all().await {
-        Ok(models) => HttpResponse::Ok().json(models),
+        Ok(models) => cacheable_ok(config.cache.car_models_max_age).json(models),
         Err(e) => {
-            eprintln!("Error fetching car models: {}", e);
+            log_failure("fetching", "car models", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch car models"
             }))
@@ -26,18 +30,19 @@ pub async fn get_car_models_handler(db_pool: web::Data<DbPool>) -> HttpResponse
 // GET /api/car-models/{id} - получить модель по ID
 pub async fn get_car_model_by_id_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
     let repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
     match repo.find_by_id(id).await {
-        Ok(Some(model)) => HttpResponse::Ok().json(model),
+        Ok(Some(model)) => cacheable_ok(config.cache.car_models_max_age).json(model),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Car model not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching car model {}: {}", id, e);
+            log_failure("fetching", "car model", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch car model"
             }))
@@ -48,15 +53,16 @@ pub async fn get_car_model_by_id_handler(
 // GET /api/car-models/brand/{brand_id} - получить модели по бренду
 pub async fn get_car_models_by_brand_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
     let repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
     let brand_id = path.into_inner();
 
     match repo.find_by_brand_id(brand_id).await {
-        Ok(models) => HttpResponse::Ok().json(models),
+        Ok(models) => cacheable_ok(config.cache.car_models_max_age).json(models),
         Err(e) => {
-            eprintln!("Error fetching car models by brand {}: {}", brand_id, e);
+            log_failure("fetching", "car models by brand", Some(&brand_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch car models"
             }))
@@ -64,18 +70,36 @@ pub async fn get_car_models_by_brand_handler(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CarModelsByNameQuery {
+    /// `?expand=brand` добавляет к моделям название и страну бренда
+    pub expand: Option<String>,
+}
+
 // GET /api/car-models/name/{name} - получить модели по названию
 pub async fn get_car_models_by_name_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
+    query: web::Query<CarModelsByNameQuery>,
 ) -> HttpResponse {
     let repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
     let name = path.into_inner();
 
-    match repo.find_by_name(&name).await {
-        Ok(models) => HttpResponse::Ok().json(models),
+    let result = match query.expand.as_deref() {
+        None => repo.find_by_name(&name).await.map(|models| cacheable_ok(config.cache.car_models_max_age).json(models)),
+        Some("brand") => repo.find_by_name_detailed(&name).await.map(|models| cacheable_ok(config.cache.car_models_max_age).json(models)),
+        Some(other) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Unsupported expand value '{}', expected 'brand'", other)
+            }));
+        }
+    };
+
+    match result {
+        Ok(response) => response,
         Err(e) => {
-            eprintln!("Error fetching car models by name {}: {}", name, e);
+            log_failure("fetching", "car models by name", Some(&name), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch car models"
             }))
@@ -103,7 +127,7 @@ pub async fn create_car_model_handler(
             }));
         }
         Err(e) => {
-            eprintln!("Error checking car model: {}", e);
+            log_failure("checking", "car model", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to check car model"
             }));
@@ -112,9 +136,9 @@ pub async fn create_car_model_handler(
     }
 
     match repo.save(&create_request).await {
-        Ok(model) => HttpResponse::Created().json(model),
+        Ok(model) => created("/api/car-models", model.id).json(model),
         Err(e) => {
-            eprintln!("Error creating car model: {}", e);
+            log_failure("creating", "car model", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create car model"
             }))
@@ -144,7 +168,7 @@ pub async fn update_car_model_handler(
                 "error": "Car model not found"
             })),
             Err(e) => {
-                eprintln!("Error fetching car model: {}", e);
+                log_failure("fetching", "car model", None, &e);
                 return HttpResponse::InternalServerError().json(serde_json::json!({
                     "error": "Failed to fetch car model"
                 }));
@@ -161,7 +185,7 @@ pub async fn update_car_model_handler(
                     }));
                 }
                 Err(e) => {
-                    eprintln!("Error checking car model: {}", e);
+                    log_failure("checking", "car model", None, &e);
                     return HttpResponse::InternalServerError().json(serde_json::json!({
                         "error": "Failed to check car model"
                     }));
@@ -177,7 +201,7 @@ pub async fn update_car_model_handler(
             "error": "Car model not found"
         })),
         Err(e) => {
-            eprintln!("Error updating car model {}: {}", id, e);
+            log_failure("updating", "car model", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update car model"
             }))
@@ -189,20 +213,60 @@ pub async fn update_car_model_handler(
 pub async fn delete_car_model_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    query: web::Query<DryRunQuery>,
+    delete_query: web::Query<DeleteQuery>,
 ) -> HttpResponse {
     let repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
-    match repo.delete(id).await {
-        Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Car model not found"
+    match repo.delete_cascade(id, query.dry_run).await {
+        Ok(Some(report)) if report.is_blocked() => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Car model has dependent records",
+            "details": report
         })),
+        Ok(Some(report)) if report.dry_run => HttpResponse::Ok().json(report),
+        Ok(Some(_)) => HttpResponse::NoContent().finish(),
+        Ok(None) => not_found_or_deleted(delete_query.idempotent, "Car model not found"),
         Err(e) => {
-            eprintln!("Error deleting car model {}: {}", id, e);
+            log_failure("deleting", "car model", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to delete car model"
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+// GET /api/car-models/{id}/low-stock-parts - получить запчасти модели с низким запасом
+pub async fn get_car_model_low_stock_parts_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let model_repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
+    let warehouse_repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    match model_repo.find_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Car model not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "car model", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch car model"
+            }));
+        }
+    }
+
+    match warehouse_repo.low_stock_by_model(id).await {
+        Ok(parts) => HttpResponse::Ok().json(parts),
+        Err(e) => {
+            log_failure("fetching", "low stock parts for car model", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch low stock parts"
+            }))
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use actix_web::{error, http::header, mime, HttpRequest, HttpResponse, HttpResponseBuilder, ResponseError};
+use actix_web::http::header::Header;
+use chrono::{DateTime, Utc};
+use std::fmt::Display;
+use uuid::Uuid;
+
+use crate::{
+    database::DbPool,
+    handlers::error::AppError,
+    models::{ArrayFields, PricedItem},
+    repositories::brand_repository::{BrandRepository, BrandRepositoryImpl},
+};
+
+// Ошибка операции в журнал через log::error! в едином формате: операция, сущность, идентификатор (если есть)
+pub fn log_failure(operation: &str, entity: &str, id: Option<&dyn Display>, error: &dyn Display) {
+    match id {
+        Some(id) => log::error!("{} {} {} failed: {}", operation, entity, id, error),
+        None => log::error!("{} {} failed: {}", operation, entity, error),
+    }
+}
+
+// Ошибка разбора параметров пути (некорректный UUID, дата и т.п.) в едином JSON-формате:
+// в details - имена параметров пути и причина, например "id: UUID parsing failed: ..."
+pub fn path_error_handler(err: error::PathError, req: &HttpRequest) -> actix_web::Error {
+    let reason = match &err {
+        error::PathError::Deserialize(inner) => inner.to_string(),
+        other => other.to_string(),
+    };
+    let parameters: Vec<&str> = req.match_info().iter().map(|(name, _)| name).collect();
+    let details = format!("{}: {}", parameters.join(", "), reason);
+    let response = AppError::bad_request("Invalid path parameters")
+        .with_details(serde_json::Value::String(details))
+        .error_response();
+    error::InternalError::from_response(err, response).into()
+}
+
+// Ошибка разбора query-параметров (неизвестный параметр, неверное значение) в едином JSON-формате
+pub fn query_error_handler(err: error::QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let details = match &err {
+        error::QueryPayloadError::Deserialize(inner) => inner.to_string(),
+        other => other.to_string(),
+    };
+    let response = AppError::bad_request("Invalid query parameters")
+        .with_details(serde_json::Value::String(details))
+        .error_response();
+    error::InternalError::from_response(err, response).into()
+}
+
+// Ответ на удаление отсутствующей записи: 404, а при идемпотентном удалении - 204 (желаемое состояние уже достигнуто)
+pub fn not_found_or_deleted(idempotent: bool, message: &str) -> HttpResponse {
+    if idempotent {
+        HttpResponse::NoContent().finish()
+    } else {
+        HttpResponse::NotFound().json(serde_json::json!({
+            "error": message
+        }))
+    }
+}
+
+// Успешный ответ для справочных данных с Cache-Control: max-age; при 0 кеширование запрещено
+pub fn cacheable_ok(max_age: u64) -> HttpResponseBuilder {
+    let directives = if max_age == 0 {
+        vec![header::CacheDirective::NoStore]
+    } else {
+        vec![header::CacheDirective::Public, header::CacheDirective::MaxAge(max_age as u32)]
+    };
+
+    let mut builder = HttpResponse::Ok();
+    builder.insert_header(header::CacheControl(directives));
+    builder
+}
+
+/// Формат тела ответа, согласованный по заголовку Accept
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResponseFormat {
+    Json,
+    Csv,
+}
+
+// Качество (q) формата по самому точному подходящему диапазону из Accept: text/csv > text/* > */*
+fn accepted_quality(accept: &header::Accept, target: &mime::Mime) -> header::Quality {
+    accept.iter()
+        .filter_map(|item| {
+            let range = &item.item;
+            let specificity = if range.type_() == mime::STAR {
+                0
+            } else if range.type_() != target.type_() {
+                return None;
+            } else if range.subtype() == mime::STAR {
+                1
+            } else if range.subtype() == target.subtype() {
+                2
+            } else {
+                return None;
+            };
+            Some((specificity, item.quality))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, quality)| quality)
+        .unwrap_or(header::Quality::ZERO)
+}
+
+// Без Accept - JSON; иначе формат с наибольшим q, при равенстве JSON.
+// None - клиент не принимает ни один из поддерживаемых форматов (ответ 406)
+pub fn negotiate_format(req: &HttpRequest) -> Option<ResponseFormat> {
+    if !req.headers().contains_key(header::ACCEPT) {
+        return Some(ResponseFormat::Json);
+    }
+
+    let accept = header::Accept::parse(req).ok()?;
+    let json = accepted_quality(&accept, &mime::APPLICATION_JSON);
+    let csv = accepted_quality(&accept, &mime::TEXT_CSV);
+
+    if json == header::Quality::ZERO && csv == header::Quality::ZERO {
+        None
+    } else if csv > json {
+        Some(ResponseFormat::Csv)
+    } else {
+        Some(ResponseFormat::Json)
+    }
+}
+
+pub fn not_acceptable_response() -> HttpResponse {
+    HttpResponse::NotAcceptable().json(serde_json::json!({
+        "error": "Not acceptable",
+        "supported": ["application/json", "text/csv"]
+    }))
+}
+
+// 201 Created с Location на созданный ресурс: `{base}/{id}`, где base - путь scope ресурса (например, /api/cars),
+// id - UUID или другой ключ ресурса (дата снимка остатков)
+pub fn created(base: &str, id: impl Display) -> HttpResponseBuilder {
+    let mut builder = HttpResponse::Created();
+    builder.insert_header((header::LOCATION, format!("{}/{}", base, id)));
+    builder
+}
+
+// ETag ресурса: меняется при каждом обновлении записи (по updated_at)
+pub fn resource_etag(id: Uuid, updated_at: DateTime<Utc>) -> String {
+    format!("\"{}-{:x}\"", id.simple(), updated_at.timestamp_micros())
+}
+
+// Проверка заголовка If-Match для оптимистичной блокировки: без заголовка изменение разрешено,
+// при несовпадении ни с одним из перечисленных ETag - 412 Precondition Failed.
+// Слабые ETag (W/"...") по RFC 9110 с If-Match не совпадают никогда.
+pub fn check_if_match(req: &HttpRequest, current_etag: &str) -> Result<(), AppError> {
+    let Some(value) = req.headers().get(header::IF_MATCH) else {
+        return Ok(());
+    };
+
+    let matches = value.to_str().is_ok_and(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag == current_etag)
+    });
+
+    if matches {
+        Ok(())
+    } else {
+        Err(AppError::precondition_failed(
+            "Resource has been modified (If-Match does not match current ETag)",
+            Some(current_etag),
+        ))
+    }
+}
+
+// Версия записи для условного UPDATE (WHERE updated_at = ...): Some(updated_at), если If-Match совпал с текущим ETag;
+// None - заголовка нет или указан If-Match: * (подходит любая версия). Несовпадение - 412, как в check_if_match
+pub fn if_match_version(req: &HttpRequest, id: Uuid, updated_at: DateTime<Utc>) -> Result<Option<DateTime<Utc>>, AppError> {
+    check_if_match(req, &resource_etag(id, updated_at))?;
+
+    let any_version = req.headers().get(header::IF_MATCH).is_none_or(|value| {
+        value.to_str().is_ok_and(|value| value.split(',').any(|tag| tag.trim() == "*"))
+    });
+    Ok((!any_version).then_some(updated_at))
+}
+
+// Размер пакета в пакетных операциях: от 1 до max_batch_size элементов, иначе 400 с указанием лимита
+pub fn check_batch_size(len: usize, max_batch_size: usize) -> Result<(), AppError> {
+    if (1..=max_batch_size).contains(&len) {
+        return Ok(());
+    }
+
+    Err(AppError::bad_request(&format!("Batch must contain from 1 to {} items", max_batch_size))
+        .with_details(serde_json::json!({ "max_batch_size": max_batch_size })))
+}
+
+// Массивные поля запроса: не больше max_array_len элементов в каждом, иначе 400 с именем поля и лимитом
+pub fn check_array_fields(request: &impl ArrayFields, max_array_len: usize) -> Result<(), AppError> {
+    match request.array_fields().into_iter().find(|(_, len)| *len > max_array_len) {
+        Some((field, _)) => Err(array_limit_exceeded(field, max_array_len)),
+        None => Ok(()),
+    }
+}
+
+pub fn array_limit_exceeded(field: &str, max_array_len: usize) -> AppError {
+    AppError::bad_request(&format!("{} must contain at most {} items", field, max_array_len))
+        .with_details(serde_json::json!({ "field": field, "max_array_len": max_array_len }))
+}
+
+// Проверка минимальной цены продажи бренда при создании и обновлении автомобилей и запчастей
+pub async fn ensure_price_floor(
+    db_pool: &DbPool,
+    brand_id: Uuid,
+    item: PricedItem,
+    price: f64,
+) -> Result<(), AppError> {
+    let repo = BrandRepositoryImpl::new(db_pool.clone());
+
+    let floor = repo.find_price_floor(brand_id).await?;
+    match floor.and_then(|floor| floor.min_price(item)) {
+        Some(min_price) if price < min_price => Err(price_below_floor(price, min_price)),
+        _ => Ok(()),
+    }
+}
+
+pub fn price_below_floor(price: f64, min_price: f64) -> AppError {
+    AppError::bad_request("Price is below the brand minimum")
+        .with_details(serde_json::json!({ "price": price, "min_price": min_price }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn version() -> (Uuid, DateTime<Utc>) {
+        (Uuid::nil(), DateTime::<Utc>::from_timestamp_micros(1_700_000_000_000_000).unwrap())
+    }
+
+    #[test]
+    fn if_match_version_is_none_without_header() {
+        let (id, updated_at) = version();
+        let req = TestRequest::default().to_http_request();
+
+        assert_eq!(if_match_version(&req, id, updated_at).unwrap(), None);
+    }
+
+    #[test]
+    fn if_match_version_returns_matched_version() {
+        let (id, updated_at) = version();
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, format!("\"other\", {}", resource_etag(id, updated_at))))
+            .to_http_request();
+
+        assert_eq!(if_match_version(&req, id, updated_at).unwrap(), Some(updated_at));
+    }
+
+    #[test]
+    fn if_match_version_accepts_any_version_for_wildcard() {
+        let (id, updated_at) = version();
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "*")).to_http_request();
+
+        assert_eq!(if_match_version(&req, id, updated_at).unwrap(), None);
+    }
+
+    #[test]
+    fn if_match_version_rejects_stale_etag() {
+        let (id, updated_at) = version();
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "\"stale\"")).to_http_request();
+
+        let error = if_match_version(&req, id, updated_at).unwrap_err();
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[test]
+    fn check_batch_size_accepts_one_to_max_items() {
+        assert!(check_batch_size(1, 3).is_ok());
+        assert!(check_batch_size(3, 3).is_ok());
+    }
+
+    #[test]
+    fn check_batch_size_rejects_empty_and_oversized_batches() {
+        for len in [0, 4] {
+            let error = check_batch_size(len, 3).unwrap_err();
+            assert_eq!(error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+            assert_eq!(error.to_string(), "Batch must contain from 1 to 3 items");
+        }
+    }
+
+    #[actix_web::test]
+    async fn path_error_reports_parse_error_in_details() {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(actix_web::web::PathConfig::default().error_handler(path_error_handler))
+                .route("/items/{id}", actix_web::web::get().to(|id: actix_web::web::Path<Uuid>| async move {
+                    HttpResponse::Ok().body(id.to_string())
+                })),
+        ).await;
+
+        let resp = actix_web::test::call_service(&app, TestRequest::get().uri("/items/not-a-uuid").to_request()).await;
+        let status = resp.status();
+        let body: serde_json::Value = actix_web::test::read_body_json(resp).await;
+
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "bad_request");
+        assert_eq!(body["error"]["message"], "Invalid path parameters");
+        assert!(body["error"]["details"].as_str().unwrap().starts_with("id: UUID parsing failed"));
+    }
+
+    struct Arrays(Vec<(&'static str, usize)>);
+
+    impl ArrayFields for Arrays {
+        fn array_fields(&self) -> Vec<(&'static str, usize)> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn check_array_fields_names_the_first_field_over_limit() {
+        assert!(check_array_fields(&Arrays(vec![("target_vins", 3), ("required_parts", 0)]), 3).is_ok());
+
+        let error = check_array_fields(&Arrays(vec![("target_vins", 3), ("required_parts", 4)]), 3).unwrap_err();
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(error.to_string(), "required_parts must contain at most 3 items");
+    }
+}
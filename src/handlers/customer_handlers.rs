@@ -1,21 +1,69 @@
+use std::collections::HashMap;
+
 use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::CreateCustomerRequest,
+    handlers::common::{created, log_failure, not_found_or_deleted},
+    models::{CreateCustomerRequest, UpdateCustomerRequest, CustomerDataExport, ExportedPurchaseRequest, CustomerPendingCampaign, RequestStatus, DeleteQuery, PaginatedResponse},
     repositories::customer_repository::CustomerRepositoryImpl,
+    repositories::car_repository::CarRepositoryImpl,
+    repositories::purchase_repository::PurchaseRepositoryImpl,
 };
-use crate::repositories::CustomerRepository;
+use crate::repositories::{CarRepository, CustomerRepository, PurchaseRepository};
+
+const DEFAULT_CUSTOMERS_PAGE_LIMIT: i64 = 50;
+const MAX_CUSTOMERS_PAGE_LIMIT: i64 = 500;
+
+const DEFAULT_TOP_CUSTOMERS_LIMIT: i64 = 10;
+const MAX_TOP_CUSTOMERS_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct CustomersQuery {
+    pub registered_after: Option<DateTime<Utc>>,
+    pub registered_before: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopCustomersQuery {
+    pub limit: Option<i64>,
+}
 
-// GET /api/customers - получить всех клиентов
-pub async fn get_customers_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+// GET /api/customers - получить клиентов постранично (с датами регистрации - только из диапазона)
+pub async fn get_customers_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<CustomersQuery>,
+) -> HttpResponse {
     let repo = CustomerRepositoryImpl::new(db_pool.get_ref().clone());
-    match repo.find_all().await {
-        Ok(customers) => HttpResponse::Ok().json(customers),
+
+    if let (Some(after), Some(before)) = (query.registered_after, query.registered_before) {
+        if after >= before {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "'registered_after' must be earlier than 'registered_before'"
+            }));
+        }
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_CUSTOMERS_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    if limit <= 0 || offset < 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "'limit' must be greater than 0 and 'offset' must not be negative"
+        }));
+    }
+    let limit = limit.min(MAX_CUSTOMERS_PAGE_LIMIT);
+
+    match repo.find_by_registration_range(query.registered_after, query.registered_before, limit, offset).await {
+        Ok((customers, total)) => HttpResponse::Ok().json(PaginatedResponse::new(customers, total, limit, offset)),
         Err(e) => {
-            eprintln!("Error fetching customers: {}", e);
+            log_failure("fetching", "customers", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch customers"
             }))
@@ -37,7 +85,7 @@ pub async fn get_customer_by_id_handler(
             "error": "Customer not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching customer {}: {}", id, e);
+            log_failure("fetching", "customer", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch customer"
             }))
@@ -66,7 +114,7 @@ pub async fn create_customer_handler(
             }));
         }
         Err(e) => {
-            eprintln!("Error checking email: {}", e);
+            log_failure("checking", "email", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to check email"
             }));
@@ -75,9 +123,9 @@ pub async fn create_customer_handler(
     }
 
     match repo.save(&create_request).await {
-        Ok(customer) => HttpResponse::Created().json(customer),
+        Ok(customer) => created("/api/customers", customer.id).json(customer),
         Err(e) => {
-            eprintln!("Error creating customer: {}", e);
+            log_failure("creating", "customer", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create customer"
             }))
@@ -89,7 +137,7 @@ pub async fn create_customer_handler(
 pub async fn update_customer_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-    update_request: web::Json<CreateCustomerRequest>,
+    update_request: web::Json<UpdateCustomerRequest>,
 ) -> HttpResponse {
     let repo = CustomerRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
@@ -101,13 +149,31 @@ pub async fn update_customer_handler(
         }));
     }
 
+    // Адрес можно оставить прежним, но нельзя занять адрес другого клиента
+    if let Some(email) = &update_request.email {
+        match repo.find_by_email(email).await {
+            Ok(Some(existing)) if existing.id != id => {
+                return HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "Email already exists"
+                }));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log_failure("checking", "email", None, &e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to check email"
+                }));
+            }
+        }
+    }
+
     match repo.update(id, &update_request).await {
         Ok(Some(customer)) => HttpResponse::Ok().json(customer),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Customer not found"
         })),
         Err(e) => {
-            eprintln!("Error updating customer {}: {}", id, e);
+            log_failure("updating", "customer", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update customer"
             }))
@@ -119,20 +185,262 @@ pub async fn update_customer_handler(
 pub async fn delete_customer_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    query: web::Query<DeleteQuery>,
 ) -> HttpResponse {
     let repo = CustomerRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
     match repo.delete(id).await {
         Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+        Ok(false) => not_found_or_deleted(query.idempotent, "Customer not found"),
+        Err(e) => {
+            log_failure("deleting", "customer", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete customer"
+            }))
+        }
+    }
+}
+
+// POST /api/customers/{id}/anonymize - обезличить клиента вместо удаления (история покупок сохраняется)
+pub async fn anonymize_customer_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = CustomerRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    match repo.anonymize(id).await {
+        Ok(Some(customer)) => HttpResponse::Ok().json(customer),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Customer not found"
         })),
         Err(e) => {
-            eprintln!("Error deleting customer {}: {}", id, e);
+            log_failure("anonymizing", "customer", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to delete customer"
+                "error": "Failed to anonymize customer"
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+// GET /api/customers/{id}/pending-campaigns - получить невыполненные сервисные кампании по всем автомобилям клиента
+pub async fn get_customer_pending_campaigns_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let customer_repo = CustomerRepositoryImpl::new(db_pool.get_ref().clone());
+    let purchase_repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+    let car_repo = CarRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_strict_enum_decoding(config.database.strict_enum_decoding);
+    let id = path.into_inner();
+
+    match customer_repo.find_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Customer not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "customer", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch customer"
+            }));
+        }
+    }
+
+    // Автомобили клиента - это автомобили из его завершённых заявок на покупку
+    let purchases = match purchase_repo.find_by_customer_id(id).await {
+        Ok(purchases) => purchases,
+        Err(e) => {
+            log_failure("fetching", "purchases for customer", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch customer pending campaigns"
+            }));
+        }
+    };
+
+    let mut car_ids: Vec<Uuid> = Vec::new();
+    for purchase in purchases.iter().filter(|p| p.status == RequestStatus::Completed) {
+        if !car_ids.contains(&purchase.car_id) {
+            car_ids.push(purchase.car_id);
+        }
+    }
+
+    // Одна кампания может относиться к нескольким автомобилям - собираем их в car_ids
+    let mut result: Vec<CustomerPendingCampaign> = Vec::new();
+    let mut index_by_campaign: HashMap<Uuid, usize> = HashMap::new();
+
+    for car_id in car_ids {
+        let campaigns = match car_repo.get_pending_campaigns_for_car(car_id).await {
+            Ok(campaigns) => campaigns,
+            Err(e) => {
+                log_failure("fetching", "pending campaigns for car", Some(&car_id), &e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to fetch customer pending campaigns"
+                }));
+            }
+        };
+
+        for campaign in campaigns {
+            match index_by_campaign.get(&campaign.id) {
+                Some(&index) => result[index].car_ids.push(car_id),
+                None => {
+                    index_by_campaign.insert(campaign.id, result.len());
+                    result.push(CustomerPendingCampaign { campaign, car_ids: vec![car_id] });
+                }
+            }
+        }
+    }
+
+    HttpResponse::Ok().json(result)
+}
+
+// GET /api/customers/{id}/export - выгрузить все данные клиента (заявки вместе с автомобилями)
+pub async fn export_customer_data_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let customer_repo = CustomerRepositoryImpl::new(db_pool.get_ref().clone());
+    let purchase_repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+    let car_repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let customer = match customer_repo.find_by_id(id).await {
+        Ok(Some(customer)) => customer,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Customer not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "customer", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to export customer data"
+            }));
+        }
+    };
+
+    let purchases = match purchase_repo.find_by_customer_id(id).await {
+        Ok(purchases) => purchases,
+        Err(e) => {
+            log_failure("fetching", "purchases for customer", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to export customer data"
+            }));
+        }
+    };
+
+    let mut car_ids: Vec<Uuid> = purchases.iter().map(|p| p.car_id).collect();
+    car_ids.sort();
+    car_ids.dedup();
+
+    let cars_by_id = match car_repo.find_by_ids(&car_ids).await {
+        Ok(cars) => cars.into_iter().map(|car| (car.id, car)).collect::<HashMap<_, _>>(),
+        Err(e) => {
+            log_failure("fetching", "cars for customer", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to export customer data"
+            }));
+        }
+    };
+
+    let purchase_requests = purchases
+        .into_iter()
+        .map(|purchase| {
+            let car = cars_by_id.get(&purchase.car_id).cloned();
+            ExportedPurchaseRequest { purchase, car }
+        })
+        .collect();
+
+    HttpResponse::Ok().json(CustomerDataExport {
+        exported_at: Utc::now(),
+        customer,
+        purchase_requests,
+    })
+}
+
+// GET /api/customers/top - клиенты с наибольшей суммой завершённых покупок
+pub async fn get_top_customers_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<TopCustomersQuery>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+    let limit = query.limit
+        .unwrap_or(DEFAULT_TOP_CUSTOMERS_LIMIT)
+        .clamp(1, MAX_TOP_CUSTOMERS_LIMIT);
+
+    match repo.top_customers(limit).await {
+        Ok(customers) => HttpResponse::Ok().json(customers),
+        Err(e) => {
+            log_failure("fetching", "top customers", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch top customers"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    async fn get_customers(pool: &DbPool, uri: &str) -> (actix_web::http::StatusCode, serde_json::Value) {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .route("/api/customers", web::get().to(get_customers_handler)),
+        ).await;
+        let response = actix_web::test::call_service(&app, actix_web::test::TestRequest::get().uri(uri).to_request()).await;
+        let status = response.status();
+        (status, actix_web::test::read_body_json(response).await)
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn customers_without_parameters_are_paginated() {
+        let pool = test_pool().await;
+
+        let (status, body) = get_customers(&pool, "/api/customers?limit=1").await;
+
+        assert_eq!(status, 200);
+        assert!(body["items"].is_array());
+        assert_eq!(body["limit"], 1);
+        assert_eq!(body["offset"], 0);
+        assert!(body["total"].is_i64());
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn empty_registration_range_returns_an_empty_page() {
+        let pool = test_pool().await;
+
+        // Клиентов, зарегистрированных в 1990 году, в базе нет: ответ - пустая страница, а не массив
+        let (status, body) = get_customers(
+            &pool,
+            "/api/customers?registered_after=1990-01-01T00:00:00Z&registered_before=1991-01-01T00:00:00Z",
+        ).await;
+
+        assert_eq!(status, 200);
+        assert_eq!(body["items"], serde_json::json!([]));
+        assert_eq!(body["total"], 0);
+        assert_eq!(body["limit"], DEFAULT_CUSTOMERS_PAGE_LIMIT);
+    }
+
+    #[actix_web::test]
+    async fn inverted_registration_range_is_rejected() {
+        // Пул без соединения: запрос отклоняется до обращения к базе
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+
+        let (status, body) = get_customers(
+            &pool,
+            "/api/customers?registered_after=2024-02-01T00:00:00Z&registered_before=2024-01-01T00:00:00Z",
+        ).await;
+
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("registered_after"));
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt;
+
+use actix_web::{http::{header, StatusCode}, HttpResponse, ResponseError};
+use validator::ValidationErrors;
+
+/// Ошибка обработчика с единым JSON-телом `{"error": {"code", "message", "details"}}`, details - только если есть.
+/// Ошибки БД конвертируются через `?`: RowNotFound - 404, нарушение уникальности - 409, остальное - 500
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    Conflict(String),
+    BadRequest(String),
+    Validation(ValidationErrors),
+    /// If-Match не совпал: etag - текущий ETag ресурса, если известен (уходит в заголовок ETag)
+    PreconditionFailed { message: String, etag: Option<String> },
+    Database(sqlx::Error),
+    /// Ошибка с дополнительными полями в details (цена и порог, индекс записи пакета и т.п.)
+    WithDetails(Box<AppError>, serde_json::Value),
+}
+
+impl AppError {
+    pub fn not_found(message: &str) -> Self {
+        AppError::NotFound(message.to_string())
+    }
+
+    pub fn conflict(message: &str) -> Self {
+        AppError::Conflict(message.to_string())
+    }
+
+    pub fn bad_request(message: &str) -> Self {
+        AppError::BadRequest(message.to_string())
+    }
+
+    pub fn precondition_failed(message: &str, etag: Option<&str>) -> Self {
+        AppError::PreconditionFailed { message: message.to_string(), etag: etag.map(str::to_string) }
+    }
+
+    // Поля объекта details добавляются к details ошибки (при совпадении ключей побеждают новые)
+    pub fn with_details(self, details: serde_json::Value) -> Self {
+        AppError::WithDetails(Box::new(self), details)
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Validation(_) => "validation_failed",
+            AppError::PreconditionFailed { .. } => "precondition_failed",
+            AppError::Database(_) => "internal_error",
+            AppError::WithDetails(inner, _) => inner.code(),
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::Validation(errors) => Some(serde_json::json!({ "fields": errors })),
+            AppError::WithDetails(inner, extra) => match (inner.details(), extra) {
+                (Some(serde_json::Value::Object(mut details)), serde_json::Value::Object(extra)) => {
+                    details.extend(extra.clone());
+                    Some(serde_json::Value::Object(details))
+                }
+                _ => Some(extra.clone()),
+            },
+            _ => None,
+        }
+    }
+
+    fn etag(&self) -> Option<&str> {
+        match self {
+            AppError::PreconditionFailed { etag, .. } => etag.as_deref(),
+            AppError::WithDetails(inner, _) => inner.etag(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(message) | AppError::Conflict(message) | AppError::BadRequest(message) => {
+                write!(f, "{}", message)
+            }
+            AppError::Validation(_) => write!(f, "Validation failed"),
+            AppError::PreconditionFailed { message, .. } => write!(f, "{}", message),
+            AppError::Database(e) => write!(f, "Database error: {}", e),
+            AppError::WithDetails(inner, _) => write!(f, "{}", inner),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => AppError::not_found("Resource not found"),
+            sqlx::Error::Database(db_error) if db_error.is_unique_violation() => {
+                AppError::conflict("Resource already exists")
+            }
+            _ => AppError::Database(e),
+        }
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        AppError::Validation(errors)
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::BadRequest(_) | AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
+            AppError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::WithDetails(inner, _) => inner.status_code(),
+        }
+    }
+
+    // Текст ошибки БД только в лог: клиенту уходит стабильное сообщение без деталей запроса
+    fn error_response(&self) -> HttpResponse {
+        let message = match self {
+            AppError::Database(e) => {
+                log::error!("Database error: {}", e);
+                "Internal server error".to_string()
+            }
+            other => other.to_string(),
+        };
+
+        let mut error = serde_json::json!({
+            "code": self.code(),
+            "message": message
+        });
+        if let Some(details) = self.details() {
+            error["details"] = details;
+        }
+
+        let mut response = HttpResponse::build(self.status_code());
+        if let Some(etag) = self.etag() {
+            response.insert_header((header::ETAG, etag));
+        }
+        response.json(serde_json::json!({ "error": error }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use validator::Validate;
+
+    async fn body_of(error: AppError) -> (StatusCode, serde_json::Value) {
+        let response = error.error_response();
+        let status = response.status();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[derive(Validate)]
+    struct Sample {
+        #[validate(range(min = 1))]
+        count: i32,
+    }
+
+    #[actix_web::test]
+    async fn bad_request_has_code_and_message_without_details() {
+        let (status, body) = body_of(AppError::bad_request("Invalid cursor")).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, serde_json::json!({
+            "error": { "code": "bad_request", "message": "Invalid cursor" }
+        }));
+    }
+
+    #[actix_web::test]
+    async fn validation_errors_go_to_details_fields() {
+        let errors = Sample { count: 0 }.validate().unwrap_err();
+        let (status, body) = body_of(AppError::from(errors)).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "validation_failed");
+        assert_eq!(body["error"]["message"], "Validation failed");
+        assert_eq!(body["error"]["details"]["fields"]["count"][0]["code"], "range");
+    }
+
+    #[actix_web::test]
+    async fn with_details_merges_into_inner_details() {
+        let errors = Sample { count: 0 }.validate().unwrap_err();
+        let (_, body) = body_of(AppError::from(errors).with_details(serde_json::json!({ "failed_index": 2 }))).await;
+
+        assert_eq!(body["error"]["details"]["failed_index"], 2);
+        assert!(body["error"]["details"]["fields"]["count"].is_array());
+    }
+
+    #[actix_web::test]
+    async fn with_details_keeps_status_and_code() {
+        let error = AppError::conflict("VIN already exists").with_details(serde_json::json!({ "failed_index": 0 }));
+        let (status, body) = body_of(error).await;
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body, serde_json::json!({
+            "error": { "code": "conflict", "message": "VIN already exists", "details": { "failed_index": 0 } }
+        }));
+    }
+
+    #[actix_web::test]
+    async fn precondition_failed_sets_etag_header() {
+        let response = AppError::precondition_failed("Resource has been modified", Some("\"abc-1\"")).error_response();
+
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), "\"abc-1\"");
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "precondition_failed");
+    }
+
+    #[actix_web::test]
+    async fn database_error_hides_details() {
+        let (status, body) = body_of(AppError::from(sqlx::Error::PoolTimedOut)).await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body, serde_json::json!({
+            "error": { "code": "internal_error", "message": "Internal server error" }
+        }));
+    }
+}
@@ -0,0 +1,114 @@
+use actix_web::{web, HttpResponse};
+
+use crate::{
+    config::Config,
+    handlers::common::cacheable_ok,
+    models::{CarStatus, EnumVariants, FuelType, RequestStatus, ResourceSchema, ServiceCampaignStatus, Transmission},
+};
+
+// GET /api/meta/enums - получить допустимые значения перечислений для выпадающих списков
+pub async fn get_enums_handler(config: web::Data<Config>) -> HttpResponse {
+    cacheable_ok(config.cache.meta_max_age).json(serde_json::json!({
+        "fuel_type": FuelType::options(),
+        "transmission": Transmission::options(),
+        "car_status": CarStatus::options(),
+        "request_status": RequestStatus::options(),
+        "campaign_status": ServiceCampaignStatus::options(),
+    }))
+}
+
+// GET /api/meta/schema/{resource} - получить описание полей формы создания ресурса
+pub async fn get_resource_schema_handler(
+    config: web::Data<Config>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match ResourceSchema::for_resource(&path.into_inner(), config.limits.max_array_len) {
+        Some(schema) => cacheable_ok(config.cache.meta_max_age).json(schema),
+        None => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Unknown resource"
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use serde::Serialize;
+
+    // Полные списки вариантов. match без `_` не скомпилируется после добавления нового варианта,
+    // пока его не внесут и сюда - тогда тест сверит его с variants() и /api/meta/enums
+    fn all_fuel_types() -> Vec<FuelType> {
+        let all = vec![FuelType::Petrol, FuelType::Diesel, FuelType::Electric, FuelType::Hybrid];
+        all.iter().for_each(|variant| match variant {
+            FuelType::Petrol | FuelType::Diesel | FuelType::Electric | FuelType::Hybrid => {}
+        });
+        all
+    }
+
+    fn all_transmissions() -> Vec<Transmission> {
+        let all = vec![Transmission::Manual, Transmission::Automatic, Transmission::CVT];
+        all.iter().for_each(|variant| match variant {
+            Transmission::Manual | Transmission::Automatic | Transmission::CVT => {}
+        });
+        all
+    }
+
+    fn all_car_statuses() -> Vec<CarStatus> {
+        let all = vec![CarStatus::Available, CarStatus::Reserved, CarStatus::Sold, CarStatus::Maintenance];
+        all.iter().for_each(|variant| match variant {
+            CarStatus::Available | CarStatus::Reserved | CarStatus::Sold | CarStatus::Maintenance => {}
+        });
+        all
+    }
+
+    fn all_request_statuses() -> Vec<RequestStatus> {
+        let all = vec![RequestStatus::Pending, RequestStatus::Approved, RequestStatus::Rejected, RequestStatus::Completed];
+        all.iter().for_each(|variant| match variant {
+            RequestStatus::Pending | RequestStatus::Approved | RequestStatus::Rejected | RequestStatus::Completed => {}
+        });
+        all
+    }
+
+    fn all_campaign_statuses() -> Vec<ServiceCampaignStatus> {
+        let all = vec![ServiceCampaignStatus::Active, ServiceCampaignStatus::Completed, ServiceCampaignStatus::Cancelled];
+        all.iter().for_each(|variant| match variant {
+            ServiceCampaignStatus::Active | ServiceCampaignStatus::Completed | ServiceCampaignStatus::Cancelled => {}
+        });
+        all
+    }
+
+    // Каждый вариант есть в variants(), а value() совпадает с тем, как вариант сериализуется в JSON
+    fn expected_values<T: EnumVariants + PartialEq + Serialize + std::fmt::Debug>(all: &[T]) -> Vec<String> {
+        assert_eq!(T::variants(), all);
+        all.iter()
+            .map(|variant| {
+                assert_eq!(serde_json::to_value(variant).unwrap(), variant.value());
+                variant.value().to_string()
+            })
+            .collect()
+    }
+
+    #[actix_web::test]
+    async fn enums_endpoint_lists_every_variant() {
+        let config = Config::from_env().expect("test configuration");
+        let response = get_enums_handler(web::Data::new(config)).await;
+        assert!(response.status().is_success());
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let values = |key: &str| -> Vec<String> {
+            body[key].as_array()
+                .unwrap_or_else(|| panic!("missing {}", key))
+                .iter()
+                .map(|option| option["value"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(values("fuel_type"), expected_values(&all_fuel_types()));
+        assert_eq!(values("transmission"), expected_values(&all_transmissions()));
+        assert_eq!(values("car_status"), expected_values(&all_car_statuses()));
+        assert_eq!(values("request_status"), expected_values(&all_request_statuses()));
+        assert_eq!(values("campaign_status"), expected_values(&all_campaign_statuses()));
+    }
+}
@@ -7,6 +7,11 @@ pub mod car_model_handlers;
 pub mod work_handlers;
 pub mod service_campaign_handlers;
 pub mod warehouse_handler;
+pub mod report_handlers;
+pub mod meta_handlers;
+pub mod search_handlers;
+pub mod common;
+pub mod error;
 
 pub use car_handlers::*;
 pub use customer_handlers::*;
@@ -16,4 +21,4 @@ pub use brand_handlers::*;
 pub use car_model_handlers::*;
 pub use work_handlers::*;
 pub use service_campaign_handlers::*;
-pub use warehouse_handler::*;
\ No newline at end of file
+pub use warehouse_handler::*;
@@ -1,216 +1,629 @@
-use actix_web::{web, HttpResponse};
+use std::collections::HashMap;
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::{CreatePartRequest, UpdatePartRequest},
+    handlers::common::{check_array_fields, check_batch_size, created, ensure_price_floor, if_match_version, resource_etag},
+    handlers::error::AppError,
+    models::{
+        CreatePartRequest, UpdatePartRequest, BatchDeleteRequest, DryRunQuery, DeleteQuery,
+        CompatibleModel, PartCompatibleModels, PricedItem, AddCompatibleVinsRequest, PartCampaignDemand, is_valid_vin, validate_sale_price,
+        PartCatalogFilter, PartCatalogSort, SortOrder, PaginatedResponse
+    },
+    repositories::car_repository::CarRepositoryImpl,
+    repositories::car_model_repository::CarModelRepositoryImpl,
     repositories::part_repository::PartRepositoryImpl,
 };
-use crate::repositories::PartRepository;
+use crate::repositories::{CarModelRepository, CarRepository, PartRepository};
 
-// GET /api/parts - получить все запчасти
-pub async fn get_parts_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
-    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
-    match repo.find_all().await {
-        Ok(parts) => HttpResponse::Ok().json(parts),
-        Err(e) => {
-            eprintln!("Error fetching parts: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch parts"
-            }))
+const DEFAULT_CATALOG_PAGE_LIMIT: i64 = 50;
+const MAX_CATALOG_PAGE_LIMIT: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct PartCatalogQuery {
+    pub brand_id: Option<Uuid>,
+    pub car_model_id: Option<Uuid>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    #[serde(default)]
+    pub sort: PartCatalogSort,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+impl PartCatalogQuery {
+    fn filter(&self) -> PartCatalogFilter {
+        PartCatalogFilter {
+            brand_id: self.brand_id,
+            car_model_id: self.car_model_id,
+            min_price: self.min_price,
+            max_price: self.max_price,
         }
     }
 }
 
+// GET /api/parts - получить все запчасти
+pub async fn get_parts_handler(db_pool: web::Data<DbPool>) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+    let parts = repo.find_all().await?;
+    Ok(HttpResponse::Ok().json(parts))
+}
+
 // GET /api/parts/{id} - получить запчасть по ID
 pub async fn get_part_by_id_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
-    match repo.find_by_id(id).await {
-        Ok(Some(part)) => HttpResponse::Ok().json(part),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Part not found"
-        })),
-        Err(e) => {
-            eprintln!("Error fetching part {}: {}", id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch part"
-            }))
-        }
-    }
+    let part = repo.find_by_id(id).await?
+        .ok_or_else(|| AppError::not_found("Part not found"))?;
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, resource_etag(part.id, part.updated_at)))
+        .json(part))
 }
 
 // GET /api/parts/article/{article} - получить запчасть по артикулу
 pub async fn get_part_by_article_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<String>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
     let article = path.into_inner();
 
-    match repo.find_by_article(&article).await {
-        Ok(Some(part)) => HttpResponse::Ok().json(part),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Part not found"
-        })),
-        Err(e) => {
-            eprintln!("Error fetching part by article {}: {}", article, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch part"
-            }))
-        }
-    }
+    let part = repo.find_by_article(&article).await?
+        .ok_or_else(|| AppError::not_found("Part not found"))?;
+    Ok(HttpResponse::Ok().json(part))
 }
 
 // GET /api/parts/brand/{brand_id} - получить запчасти по бренду
 pub async fn get_parts_by_brand_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
     let brand_id = path.into_inner();
 
-    match repo.find_by_brand(brand_id).await {
-        Ok(parts) => HttpResponse::Ok().json(parts),
-        Err(e) => {
-            eprintln!("Error fetching parts by brand {}: {}", brand_id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch parts"
-            }))
-        }
-    }
+    let parts = repo.find_by_brand(brand_id).await?;
+    Ok(HttpResponse::Ok().json(parts))
 }
 
 // GET /api/parts/car-model/{car_model_id} - получить запчасти по модели автомобиля
 pub async fn get_parts_by_car_model_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
     let car_model_id = path.into_inner();
 
-    match repo.find_by_car_model(car_model_id).await {
-        Ok(parts) => HttpResponse::Ok().json(parts),
-        Err(e) => {
-            eprintln!("Error fetching parts by car model {}: {}", car_model_id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch parts"
-            }))
-        }
-    }
+    let parts = repo.find_by_car_model(car_model_id).await?;
+    Ok(HttpResponse::Ok().json(parts))
 }
 
 // GET /api/parts/vin/{vin} - получить запчасти по VIN коду
 pub async fn get_parts_by_vin_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<String>,
-) -> HttpResponse {
+) -> Result<HttpResponse, AppError> {
     let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
     let vin = path.into_inner();
 
-    match repo.find_by_vin(&vin).await {
-        Ok(parts) => HttpResponse::Ok().json(parts),
-        Err(e) => {
-            eprintln!("Error fetching parts by VIN {}: {}", vin, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch parts"
-            }))
-        }
-    }
+    let parts = repo.find_by_vin(&vin).await?;
+    Ok(HttpResponse::Ok().json(parts))
+}
+
+// GET /api/parts/margins - запчасти с маржой, начиная с наименьшей наценки
+pub async fn get_part_margins_handler(
+    db_pool: web::Data<DbPool>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+
+    let parts = repo.find_all_with_margin().await?;
+    Ok(HttpResponse::Ok().json(parts))
+}
+
+// GET /api/parts/name/{name} - поиск запчастей по части названия (без учёта регистра)
+pub async fn get_parts_by_name_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+    let name = path.into_inner();
+
+    let parts = repo.find_by_name(&name).await?;
+    Ok(HttpResponse::Ok().json(parts))
 }
 
 // POST /api/parts - создать запчасть
 pub async fn create_part_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     create_request: web::Json<CreatePartRequest>,
-) -> HttpResponse {
-    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_article_case(config.catalog.article_case);
 
-    if let Err(validation_errors) = create_request.validate() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        }));
-    }
-    match repo.exists_by_article(&create_request.article).await {
-        Ok(true) => {
-            return HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "Article already exists"
-            }));
-        }
-        Err(e) => {
-            eprintln!("Error checking article: {}", e);
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to check article"
-            }));
-        }
-        _ => {}
+    create_request.validate()?;
+    check_array_fields(&*create_request, config.limits.max_array_len)?;
+    if repo.exists_by_article(&create_request.article).await? {
+        return Err(AppError::conflict("Article already exists"));
     }
 
-    match repo.save(&create_request).await {
-        Ok(part) => HttpResponse::Created().json(part),
-        Err(e) => {
-            eprintln!("Error creating part: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to create part"
-            }))
-        }
-    }
+    ensure_price_floor(
+        db_pool.get_ref(),
+        create_request.brand_id,
+        PricedItem::Part,
+        create_request.sale_price,
+    ).await?;
+
+    let part = repo.save(&create_request).await?;
+    Ok(created("/api/parts", part.id).json(part))
 }
 
 // PUT /api/parts/{id} - обновить запчасть
 pub async fn update_part_handler(
+    req: HttpRequest,
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
     update_request: web::Json<UpdatePartRequest>,
-) -> HttpResponse {
-    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_article_case(config.catalog.article_case);
     let id = path.into_inner();
 
-    if let Err(validation_errors) = update_request.validate() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Validation failed",
-            "details": validation_errors
-        }));
-    }
-
-    match repo.update(id, &update_request).await {
-        Ok(Some(part)) => HttpResponse::Ok().json(part),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Part not found"
-        })),
-        Err(e) => {
-            eprintln!("Error updating part {}: {}", id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to update part"
-            }))
+    update_request.validate()?;
+    check_array_fields(&*update_request, config.limits.max_array_len)?;
+    // Артикул сравнивается без учёта регистра; смена регистра собственного артикула - не конфликт
+    if let Some(new_article) = &update_request.article {
+        if let Some(existing) = repo.find_by_article(new_article).await? {
+            if existing.id != id {
+                return Err(AppError::conflict("Article already exists"));
+            }
+        }
+    }
+
+    // Текущая запись нужна для проверки If-Match, порога цены при смене цены или бренда
+    // и соотношения цен, когда меняется только одна из них
+    let changes_price = update_request.brand_id.is_some() || update_request.sale_price.is_some();
+    let changes_one_price = update_request.purchase_price.is_some() != update_request.sale_price.is_some();
+    let mut expected_updated_at = None;
+    if changes_price || changes_one_price || req.headers().contains_key(header::IF_MATCH) {
+        let part = repo.find_by_id(id).await?
+            .ok_or_else(|| AppError::not_found("Part not found"))?;
+
+        // Проверенная версия уходит в UPDATE: параллельное изменение между чтением и записью тоже даст 412
+        expected_updated_at = if_match_version(&req, part.id, part.updated_at)?;
+
+        if changes_one_price {
+            if let Err(error) = validate_sale_price(
+                update_request.purchase_price.unwrap_or(part.purchase_price),
+                update_request.sale_price.unwrap_or(part.sale_price),
+            ) {
+                let mut validation_errors = ValidationErrors::new();
+                validation_errors.add("__all__", error);
+                return Err(validation_errors.into());
+            }
+        }
+
+        if changes_price {
+            ensure_price_floor(
+                db_pool.get_ref(),
+                update_request.brand_id.unwrap_or(part.brand_id),
+                PricedItem::Part,
+                update_request.sale_price.unwrap_or(part.sale_price),
+            ).await?;
         }
     }
+
+    let part = match repo.update(id, &update_request, expected_updated_at).await? {
+        Some(part) => part,
+        None => return Err(update_missed(&repo, id, expected_updated_at).await),
+    };
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, resource_etag(part.id, part.updated_at)))
+        .json(part))
+}
+
+// Условное обновление не затронуло строк: запчасть удалена (404) или изменена после проверки If-Match (412)
+async fn update_missed(repo: &PartRepositoryImpl, id: Uuid, expected_updated_at: Option<DateTime<Utc>>) -> AppError {
+    if expected_updated_at.is_none() {
+        return AppError::not_found("Part not found");
+    }
+
+    match repo.find_by_id(id).await {
+        Ok(Some(part)) => AppError::precondition_failed(
+            "Resource has been modified (If-Match does not match current ETag)",
+            Some(&resource_etag(part.id, part.updated_at)),
+        ),
+        Ok(None) => AppError::not_found("Part not found"),
+        Err(e) => e.into(),
+    }
 }
 
 // DELETE /api/parts/{id} - удалить запчасть
 pub async fn delete_part_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
-) -> HttpResponse {
+    query: web::Query<DeleteQuery>,
+) -> Result<HttpResponse, AppError> {
     let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
-    match repo.delete(id).await {
-        Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Part not found"
-        })),
-        Err(e) => {
-            eprintln!("Error deleting part {}: {}", id, e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to delete part"
-            }))
+    if repo.delete(id).await? || query.idempotent {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(AppError::not_found("Part not found"))
+    }
+}
+
+// POST /api/parts/batch-delete - удалить несколько запчастей (с dry_run - только отчёт)
+pub async fn batch_delete_parts_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    query: web::Query<DryRunQuery>,
+    delete_request: web::Json<BatchDeleteRequest>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+
+    check_batch_size(delete_request.ids.len(), config.limits.max_batch_size)?;
+
+    let report = repo.delete_batch(&delete_request.ids, query.dry_run).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+// POST /api/parts/add-compatible-vins - добавить VIN в список совместимости нескольких запчастей
+pub async fn add_compatible_vins_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    add_request: web::Json<AddCompatibleVinsRequest>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_max_array_len(config.limits.max_array_len);
+
+    check_batch_size(add_request.part_ids.len(), config.limits.max_batch_size)?;
+    add_request.validate()?;
+    check_array_fields(&*add_request, config.limits.max_array_len)?;
+
+    // Все VIN проверяются до изменений: пакет применяется целиком или не применяется вовсе
+    let mut vins: Vec<String> = Vec::with_capacity(add_request.vins.len());
+    let mut invalid_vins: Vec<String> = Vec::new();
+    for raw in &add_request.vins {
+        let vin = raw.trim().to_uppercase();
+        if !is_valid_vin(&vin) {
+            invalid_vins.push(raw.clone());
+        } else if !vins.contains(&vin) {
+            vins.push(vin);
         }
     }
-}
\ No newline at end of file
+    if !invalid_vins.is_empty() {
+        return Err(AppError::bad_request("Invalid VINs").with_details(serde_json::json!({ "invalid_vins": invalid_vins })));
+    }
+
+    let results = repo.add_compatible_vins_batch(&add_request.part_ids, &vins).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
+
+// GET /api/parts/cross-compatible - запчасти, совместимые с несколькими моделями (по списку VIN)
+pub async fn get_cross_compatible_parts_handler(db_pool: web::Data<DbPool>) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+
+    let parts = repo.find_cross_compatible().await?;
+    Ok(HttpResponse::Ok().json(parts))
+}
+
+// GET /api/parts/{id}/compatible-models - получить модели автомобилей по списку совместимых VIN
+pub async fn get_part_compatible_models_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+    let car_repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let model_repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let part = repo.find_by_id(id).await?
+        .ok_or_else(|| AppError::not_found("Part not found"))?;
+
+    let cars = car_repo.find_by_vins(&part.compatible_vins).await?;
+
+    // VIN -> модель; VIN без автомобиля попадают в unmatched_vins
+    let model_by_vin: HashMap<&str, Uuid> = cars.iter()
+        .map(|car| (car.vin.as_str(), car.model_id))
+        .collect();
+    let mut vins_by_model: HashMap<Uuid, Vec<String>> = HashMap::new();
+    let mut unmatched_vins = Vec::new();
+    for vin in &part.compatible_vins {
+        match model_by_vin.get(vin.as_str()) {
+            Some(model_id) => vins_by_model.entry(*model_id).or_default().push(vin.clone()),
+            None => unmatched_vins.push(vin.clone()),
+        }
+    }
+
+    let model_ids: Vec<Uuid> = vins_by_model.keys().copied().collect();
+    let models = model_repo.find_with_brand_by_ids(&model_ids).await?
+        .into_iter()
+        .map(|model| CompatibleModel {
+            vins: vins_by_model.remove(&model.id).unwrap_or_default(),
+            model_id: model.id,
+            model_name: model.name,
+            brand_id: model.brand_id,
+            brand_name: model.brand_name,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(PartCompatibleModels {
+        part_id: part.id,
+        models,
+        unmatched_vins,
+    }))
+}
+
+// GET /api/parts/{id}/campaign-demand - потребность в запчасти по активным сервисным кампаниям
+pub async fn get_part_campaign_demand_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    if repo.find_by_id(id).await?.is_none() {
+        return Err(AppError::not_found("Part not found"));
+    }
+
+    let campaigns = repo.campaign_demand(id).await?;
+    Ok(HttpResponse::Ok().json(PartCampaignDemand::new(id, campaigns)))
+}
+
+// GET /api/parts/catalog - каталог запчастей с остатком и маржой (фильтры, сортировка, пагинация)
+pub async fn get_parts_catalog_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<PartCatalogQuery>,
+) -> Result<HttpResponse, AppError> {
+    let repo = PartRepositoryImpl::new(db_pool.get_ref().clone());
+    let limit = query.limit.unwrap_or(DEFAULT_CATALOG_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    if limit <= 0 || offset < 0 {
+        return Err(AppError::bad_request("'limit' must be greater than 0 and 'offset' must not be negative"));
+    }
+    if let (Some(min_price), Some(max_price)) = (query.min_price, query.max_price) {
+        if min_price > max_price {
+            return Err(AppError::bad_request("'min_price' must not be greater than 'max_price'"));
+        }
+    }
+    let limit = limit.min(MAX_CATALOG_PAGE_LIMIT);
+
+    let (rows, total) = repo.catalog(&query.filter(), limit, offset, query.sort, query.order).await?;
+    Ok(HttpResponse::Ok().json(PaginatedResponse::new(rows, total, limit, offset)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App, ResponseError};
+    use crate::database::test_pool;
+
+    // Тесты с базой идут параллельно, поэтому у каждого свой бренд: модель - тот же id с другим последним байтом
+    struct TestBrand {
+        brand_id: Uuid,
+        model_id: Uuid,
+    }
+
+    impl TestBrand {
+        fn new(n: u8) -> Self {
+            Self {
+                brand_id: Uuid::from_bytes([0xbe, 0xbe, 0xbe, 0xbe, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, 1]),
+                model_id: Uuid::from_bytes([0xbe, 0xbe, 0xbe, 0xbe, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, 2]),
+            }
+        }
+
+        async fn insert(&self, pool: &DbPool, min_part_price: Option<f64>) {
+            self.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(self.brand_id)
+                .bind(format!("Test Brand {}", self.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(self.model_id)
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO brand_price_floors (brand_id, min_part_price) VALUES ($1, $2)")
+                .bind(self.brand_id)
+                .bind(min_part_price)
+                .execute(pool).await.unwrap();
+        }
+
+        // Складские позиции удаляются каскадом вместе с запчастями, модели и порог - вместе с брендом
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM parts WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+
+        fn create_request(&self, article: &str, sale_price: f64) -> CreatePartRequest {
+            CreatePartRequest {
+                article: article.to_string(),
+                name: format!("Test Part {}", article),
+                brand_id: self.brand_id,
+                car_model_id: self.model_id,
+                purchase_price: sale_price / 2.0,
+                sale_price,
+                compatible_vins: Vec::new(),
+            }
+        }
+    }
+
+    fn test_config() -> web::Data<Config> {
+        web::Data::new(Config::from_env().expect("test configuration"))
+    }
+
+    fn rename(name: &str) -> UpdatePartRequest {
+        serde_json::from_value(serde_json::json!({ "name": name })).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn batch_delete_over_limit_is_rejected() {
+        let mut config = Config::from_env().expect("test configuration");
+        config.limits.max_batch_size = 2;
+        // Пул без соединения: запрос отклоняется до обращения к базе
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(config))
+                .route("/api/parts/batch-delete", web::post().to(batch_delete_parts_handler)),
+        ).await;
+
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        let req = test::TestRequest::post()
+            .uri("/api/parts/batch-delete")
+            .set_json(serde_json::json!({ "ids": ids }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["message"], "Batch must contain from 1 to 2 items");
+        assert_eq!(body["error"]["details"]["max_batch_size"], 2);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn update_part_with_stale_if_match_is_rejected() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(1);
+        brand.insert(&pool, None).await;
+        let repo = PartRepositoryImpl::new(pool.clone());
+        let part = repo.save(&brand.create_request("TEST-IFMATCH-1", 100.0)).await.unwrap();
+        let stale_etag = resource_etag(part.id, part.updated_at);
+
+        // Параллельное изменение после чтения версии: условный UPDATE со старой версией ничего не меняет
+        let concurrent = repo.update(part.id, &rename("First writer"), None).await.unwrap().unwrap();
+        let missed = repo.update(part.id, &rename("Second writer"), Some(part.updated_at)).await.unwrap();
+
+        let req = test::TestRequest::default().insert_header((header::IF_MATCH, stale_etag)).to_http_request();
+        let error = update_part_handler(
+            req,
+            web::Data::new(pool.clone()),
+            test_config(),
+            web::Path::from(part.id),
+            web::Json(rename("Second writer")),
+        ).await.unwrap_err();
+        let stored = repo.find_by_id(part.id).await.unwrap().unwrap();
+        brand.remove(&pool).await;
+
+        assert!(missed.is_none());
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::PRECONDITION_FAILED);
+        let response = error.error_response();
+        assert_eq!(
+            response.headers().get(header::ETAG).unwrap(),
+            resource_etag(concurrent.id, concurrent.updated_at).as_str(),
+        );
+        assert_eq!(stored.name, "First writer");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn article_differing_only_in_case_is_a_conflict() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(2);
+        brand.insert(&pool, None).await;
+        let mut preserve = Config::from_env().expect("test configuration");
+        preserve.catalog.article_case = crate::config::ArticleCase::Preserve;
+
+        let response = create_part_handler(
+            web::Data::new(pool.clone()),
+            test_config(),
+            web::Json(brand.create_request("test-case-1", 100.0)),
+        ).await.unwrap();
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        let part: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        // Без нормализации регистра дубликат всё равно отклоняется
+        let error = create_part_handler(
+            web::Data::new(pool.clone()),
+            web::Data::new(preserve),
+            web::Json(brand.create_request("Test-Case-1", 100.0)),
+        ).await.unwrap_err();
+        brand.remove(&pool).await;
+
+        assert_eq!(part["article"], "TEST-CASE-1");
+        assert_eq!(error.status_code(), actix_web::http::StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn create_part_with_too_many_vins_is_rejected() {
+        let mut config = Config::from_env().expect("test configuration");
+        config.limits.max_array_len = 2;
+        // Пул без соединения: запрос отклоняется до обращения к базе
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(config))
+                .route("/api/parts", web::post().to(create_part_handler)),
+        ).await;
+
+        let mut part = serde_json::to_value(TestBrand::new(0).create_request("TEST-ARRAY-1", 100.0)).unwrap();
+        part["compatible_vins"] = serde_json::json!(["JTDBR32E720012345", "JTDBR32E720012346", "JTDBR32E720012347"]);
+        let req = test::TestRequest::post().uri("/api/parts").set_json(part).to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["message"], "compatible_vins must contain at most 2 items");
+        assert_eq!(body["error"]["details"], serde_json::json!({ "field": "compatible_vins", "max_array_len": 2 }));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn part_price_floor_applies_to_create_and_update() {
+        let pool = test_pool().await;
+        let brand = TestBrand::new(3);
+        brand.insert(&pool, Some(500.0)).await;
+        let create = |article: &str, sale_price: f64| create_part_handler(
+            web::Data::new(pool.clone()),
+            test_config(),
+            web::Json(brand.create_request(article, sale_price)),
+        );
+
+        let below = create("TEST-FLOOR-1", 499.99).await.unwrap_err();
+        let at_floor = create("TEST-FLOOR-2", 500.0).await.unwrap();
+        let above = create("TEST-FLOOR-3", 750.0).await.unwrap();
+        let body = actix_web::body::to_bytes(above.into_body()).await.unwrap();
+        let part: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let part_id: Uuid = part["id"].as_str().unwrap().parse().unwrap();
+        let update = |sale_price: f64| update_part_handler(
+            test::TestRequest::default().to_http_request(),
+            web::Data::new(pool.clone()),
+            test_config(),
+            web::Path::from(part_id),
+            web::Json(serde_json::from_value(serde_json::json!({ "sale_price": sale_price })).unwrap()),
+        );
+        let lowered = update(400.0).await.unwrap_err();
+        let raised = update(900.0).await.unwrap();
+        brand.remove(&pool).await;
+
+        assert_eq!(below.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(below.error_response().into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["details"]["min_price"], 500.0);
+        assert_eq!(body["error"]["details"]["price"], 499.99);
+        assert_eq!(at_floor.status(), actix_web::http::StatusCode::CREATED);
+        assert_eq!(part["sale_price"], 750.0);
+        assert_eq!(lowered.status_code(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(raised.status(), actix_web::http::StatusCode::OK);
+    }
+}
@@ -1,25 +1,76 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::{RequestStatus, CreatePurchaseRequest},
+    handlers::common::{check_batch_size, created, log_failure, not_found_or_deleted},
+    models::{
+        EnumVariants, RequestStatus, CreatePurchaseRequest, CreatedPurchaseRequest, OfferPriceWarning, DeleteQuery,
+        BatchStatusUpdateRequest, BatchStatusUpdateResponse, PurchaseFilter, PaginatedResponse,
+        money::round_money,
+    },
     repositories::{
         purchase_repository::PurchaseRepositoryImpl,
         car_repository::CarRepositoryImpl,
         customer_repository::CustomerRepositoryImpl,
     },
 };
-use crate::repositories::{CarRepository, CustomerRepository, PurchaseRepository};
+use crate::repositories::{ApproveAndSellError, CarRepository, CustomerRepository, PurchaseRepository};
+
+const DEFAULT_PURCHASES_PAGE_LIMIT: i64 = 50;
+const MAX_PURCHASES_PAGE_LIMIT: i64 = 500;
 
-// GET /api/purchases - получить все заявки
-pub async fn get_purchases_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+#[derive(Debug, Deserialize)]
+pub struct PurchasesQuery {
+    pub status: Option<RequestStatus>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+impl PurchasesQuery {
+    fn filter(&self) -> PurchaseFilter {
+        PurchaseFilter {
+            status: self.status.clone(),
+            from: self.from,
+            to: self.to,
+        }
+    }
+}
+
+// GET /api/purchases - получить заявки постранично (новые первыми, с фильтрами по статусу и датам)
+pub async fn get_purchases_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<PurchasesQuery>,
+) -> HttpResponse {
     let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
-    match repo.find_all().await {
-        Ok(requests) => HttpResponse::Ok().json(requests),
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from >= to {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "'from' must be earlier than 'to'"
+            }));
+        }
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PURCHASES_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    if limit <= 0 || offset < 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "'limit' must be greater than 0 and 'offset' must not be negative"
+        }));
+    }
+    let limit = limit.min(MAX_PURCHASES_PAGE_LIMIT);
+
+    match repo.find_filtered_paginated(&query.filter(), limit, offset).await {
+        Ok((requests, total)) => HttpResponse::Ok().json(PaginatedResponse::new(requests, total, limit, offset)),
         Err(e) => {
-            eprintln!("Error fetching purchase requests: {}", e);
+            log_failure("fetching", "purchase requests", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch purchase requests"
             }))
@@ -41,7 +92,7 @@ pub async fn get_purchase_by_id_handler(
             "error": "Purchase request not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching purchase request {}: {}", id, e);
+            log_failure("fetching", "purchase request", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch purchase request"
             }))
@@ -60,7 +111,7 @@ pub async fn get_purchases_by_customer_handler(
     match repo.find_by_customer_id(customer_id).await {
         Ok(requests) => HttpResponse::Ok().json(requests),
         Err(e) => {
-            eprintln!("Error fetching purchases for customer {}: {}", customer_id, e);
+            log_failure("fetching", "purchases for customer", Some(&customer_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch purchase requests"
             }))
@@ -79,7 +130,7 @@ pub async fn get_purchases_by_car_handler(
     match repo.find_by_car_id(car_id).await {
         Ok(requests) => HttpResponse::Ok().json(requests),
         Err(e) => {
-            eprintln!("Error fetching purchases for car {}: {}", car_id, e);
+            log_failure("fetching", "purchases for car", Some(&car_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch purchase requests"
             }))
@@ -87,9 +138,44 @@ pub async fn get_purchases_by_car_handler(
     }
 }
 
+// GET /api/purchases/status/{status} - получить заявки по статусу (без учёта регистра)
+pub async fn get_purchases_by_status_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+    let value = path.into_inner();
+
+    let Some(status) = RequestStatus::from_value_ignore_case(&value) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Unknown purchase request status",
+            "status": value,
+            "allowed": RequestStatus::variants().iter().map(|status| status.value()).collect::<Vec<_>>()
+        }));
+    };
+
+    match repo.find_by_status(status.clone()).await {
+        Ok(requests) => HttpResponse::Ok().json(requests),
+        Err(e) => {
+            log_failure("fetching", "purchases by status", Some(&status.value()), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch purchase requests"
+            }))
+        }
+    }
+}
+
+// Минимально допустимое предложение - доля цены автомобиля, округлённая до копеек.
+// Some(минимум), если предложение ниже него; предложение ровно на пороге допустимо
+fn offer_below_minimum(offer_price: f64, car_price: f64, min_offer_fraction: f64) -> Option<f64> {
+    let min_offer_price = round_money(car_price * min_offer_fraction);
+    (offer_price < min_offer_price).then_some(min_offer_price)
+}
+
 // POST /api/purchases - создать заявку на покупку
 pub async fn create_purchase_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     create_request: web::Json<CreatePurchaseRequest>,
 ) -> HttpResponse {
     let purchase_repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
@@ -103,19 +189,45 @@ pub async fn create_purchase_handler(
         }));
     }
     
-    match car_repo.find_by_id(create_request.car_id).await {
-        Ok(Some(_)) => {},
+    let car = match car_repo.find_by_id(create_request.car_id).await {
+        Ok(Some(car)) => car,
         Ok(None) => {
             return HttpResponse::BadRequest().json(serde_json::json!({
                 "error": "Car not found"
             }));
         }
         Err(e) => {
-            eprintln!("Error checking car: {}", e);
+            log_failure("checking", "car", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to validate car"
             }));
         }
+    };
+
+    // Предложение ниже заданной доли цены автомобиля не принимается; ровно на пороге - допустимо
+    if let Some(offer_price) = create_request.offer_price {
+        if let Some(min_offer_price) = offer_below_minimum(offer_price, car.price, config.pricing.min_offer_fraction) {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Offer price is below the minimum acceptable offer",
+                "offer_price": offer_price,
+                "min_offer_price": min_offer_price
+            }));
+        }
+    }
+
+    // Предложение сильно выше или ниже цены автомобиля - скорее всего ошибка ввода
+    let warning = match (create_request.offer_price, config.pricing.offer_band_percent) {
+        (Some(offer_price), Some(band_percent)) => OfferPriceWarning::check(offer_price, car.price, band_percent),
+        _ => None,
+    };
+    if let Some(warning) = &warning {
+        if config.pricing.reject_out_of_band_offers {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": warning.message,
+                "offer_price": warning.offer_price,
+                "car_price": warning.car_price
+            }));
+        }
     }
 
     // Проверяем что клиент существует
@@ -127,7 +239,7 @@ pub async fn create_purchase_handler(
             }));
         }
         Err(e) => {
-            eprintln!("Error checking customer: {}", e);
+            log_failure("checking", "customer", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to validate customer"
             }));
@@ -142,7 +254,7 @@ pub async fn create_purchase_handler(
             }));
         }
         Err(e) => {
-            eprintln!("Error checking existing request: {}", e);
+            log_failure("checking", "existing request", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to check existing requests"
             }));
@@ -151,9 +263,9 @@ pub async fn create_purchase_handler(
     }
 
     match purchase_repo.save(&create_request).await {
-        Ok(request) => HttpResponse::Created().json(request),
+        Ok(purchase) => created("/api/purchases", purchase.id).json(CreatedPurchaseRequest { purchase, warning }),
         Err(e) => {
-            eprintln!("Error creating purchase request: {}", e);
+            log_failure("creating", "purchase request", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create purchase request"
             }))
@@ -177,7 +289,7 @@ pub async fn update_purchase_status_handler(
             "error": "Purchase request not found"
         })),
         Err(e) => {
-            eprintln!("Error updating purchase status {}: {}", id, e);
+            log_failure("updating", "purchase status", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update purchase status"
             }))
@@ -185,24 +297,267 @@ pub async fn update_purchase_status_handler(
     }
 }
 
+// POST /api/purchases/{id}/approve-and-sell - одобрить заявку и пометить автомобиль проданным в одной транзакции
+pub async fn approve_and_sell_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    match repo.approve_and_sell(id).await {
+        Ok(request) => HttpResponse::Ok().json(request),
+        Err(ApproveAndSellError::PurchaseNotFound) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Purchase request not found"
+        })),
+        Err(e @ (ApproveAndSellError::InvalidTransition(_) | ApproveAndSellError::CarAlreadySold)) => {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": e.to_string()
+            }))
+        }
+        Err(ApproveAndSellError::Database(e)) => {
+            log_failure("approving and selling", "purchase request", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to approve purchase request"
+            }))
+        }
+    }
+}
+
+// POST /api/purchases/status/batch - сменить статус нескольких заявок с проверкой допустимости перехода
+pub async fn batch_update_purchase_status_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    batch_request: web::Json<BatchStatusUpdateRequest>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+
+    if let Err(error) = check_batch_size(batch_request.ids.len(), config.limits.max_batch_size) {
+        return error.error_response();
+    }
+
+    let batch_request = batch_request.into_inner();
+    match repo.update_status_batch(&batch_request.ids, batch_request.status.clone()).await {
+        Ok(results) => HttpResponse::Ok().json(BatchStatusUpdateResponse::new(batch_request.status, results)),
+        Err(e) => {
+            log_failure("updating", "purchase statuses in batch", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update purchase statuses"
+            }))
+        }
+    }
+}
+
 // DELETE /api/purchases/{id} - удалить заявку
 pub async fn delete_purchase_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    query: web::Query<DeleteQuery>,
 ) -> HttpResponse {
     let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
     match repo.delete(id).await {
         Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Purchase request not found"
-        })),
+        Ok(false) => not_found_or_deleted(query.idempotent, "Purchase request not found"),
         Err(e) => {
-            eprintln!("Error deleting purchase request {}: {}", id, e);
+            log_failure("deleting", "purchase request", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to delete purchase request"
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+const DEFAULT_STALE_DAYS: i32 = 7;
+
+#[derive(Debug, Deserialize)]
+pub struct StalePurchasesQuery {
+    pub days: Option<i32>,
+}
+
+// GET /api/purchases/oldest-pending-per-car - самая ранняя заявка Pending по каждому автомобилю
+pub async fn get_oldest_pending_per_car_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+
+    match repo.find_oldest_pending_per_car().await {
+        Ok(requests) => HttpResponse::Ok().json(requests),
+        Err(e) => {
+            log_failure("fetching", "oldest pending purchase requests", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch oldest pending purchase requests"
+            }))
+        }
+    }
+}
+
+// GET /api/purchases/stale - получить заявки в статусе Pending старше N дней
+pub async fn get_stale_purchases_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<StalePurchasesQuery>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+    let days = query.days.unwrap_or(DEFAULT_STALE_DAYS);
+
+    if days <= 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "'days' must be greater than 0"
+        }));
+    }
+
+    match repo.find_stale_pending(days).await {
+        Ok(requests) => HttpResponse::Ok().json(requests),
+        Err(e) => {
+            log_failure("fetching", "stale purchase requests", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch stale purchase requests"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Автомобиль и клиент для заявок; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestSale {
+        brand_id: Uuid,
+        car_id: Uuid,
+        customer_id: Uuid,
+    }
+
+    impl TestSale {
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xd1, 0xd1, 0xd1, 0xd1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let fixture = Self { brand_id: id(1), car_id: id(3), customer_id: id(4) };
+            fixture.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(fixture.brand_id)
+                .bind(format!("Test Brand {}", fixture.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(id(2))
+                .bind(fixture.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query(
+                "INSERT INTO cars (id, brand_id, model_id, year, price, mileage, color, fuel_type, transmission, vin)
+                 VALUES ($1, $2, $3, 2020, 1000000, 0, 'White', 'Petrol', 'Manual', $4)"
+            )
+                .bind(fixture.car_id)
+                .bind(fixture.brand_id)
+                .bind(id(2))
+                .bind(format!("D1D1D1000000000{:02}", n))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO customers (id, first_name, last_name, email, phone) VALUES ($1, 'Ivan', 'Petrov', $2, '+70000000000')")
+                .bind(fixture.customer_id)
+                .bind(format!("test-purchases-{}@example.com", n))
+                .execute(pool).await.unwrap();
+            fixture
+        }
+
+        async fn purchase(&self, pool: &DbPool, status: &str, created_at: &str) -> Uuid {
+            sqlx::query_scalar(
+                "INSERT INTO purchase_requests (car_id, customer_id, status, offer_price, created_at, updated_at)
+                 VALUES ($1, $2, $3, 950000, $4::timestamptz, $4::timestamptz)
+                 RETURNING id"
+            )
+                .bind(self.car_id)
+                .bind(self.customer_id)
+                .bind(status)
+                .bind(created_at)
+                .fetch_one(pool).await.unwrap()
+        }
+
+        // Заявки удаляются каскадом вместе с клиентом и автомобилем
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM customers WHERE id = $1")
+                .bind(self.customer_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM cars WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    async fn get_purchases(pool: &DbPool, uri: &str) -> (actix_web::http::StatusCode, serde_json::Value) {
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .route("/api/purchases", web::get().to(get_purchases_handler)),
+        ).await;
+        let response = actix_web::test::call_service(&app, actix_web::test::TestRequest::get().uri(uri).to_request()).await;
+        let status = response.status();
+        (status, actix_web::test::read_body_json(response).await)
+    }
+
+    fn ids(page: &serde_json::Value) -> Vec<Uuid> {
+        page["items"].as_array().unwrap().iter()
+            .map(|item| item["id"].as_str().unwrap().parse().unwrap())
+            .collect()
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn purchases_are_filtered_by_status_and_dates_and_paginated() {
+        let pool = test_pool().await;
+        let sale = TestSale::insert(&pool, 1).await;
+        // Заявки в марте 2001 года есть только у этого теста
+        let first = sale.purchase(&pool, "Approved", "2001-03-01T10:00:00Z").await;
+        let second = sale.purchase(&pool, "Approved", "2001-03-02T10:00:00Z").await;
+        let third = sale.purchase(&pool, "Approved", "2001-03-03T10:00:00Z").await;
+        sale.purchase(&pool, "Rejected", "2001-03-02T12:00:00Z").await;
+        sale.purchase(&pool, "Approved", "2001-04-01T00:00:00Z").await;
+
+        let filter = "status=Approved&from=2001-03-01T00:00:00Z&to=2001-04-01T00:00:00Z";
+        let (status, page) = get_purchases(&pool, &format!("/api/purchases?{}&limit=2", filter)).await;
+        let (_, last_page) = get_purchases(&pool, &format!("/api/purchases?{}&limit=2&offset=2", filter)).await;
+        sale.remove(&pool).await;
+
+        assert_eq!(status, 200);
+        // Граница `to` не включается, отклонённая заявка отсекается по статусу
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["limit"], 2);
+        assert_eq!(ids(&page), vec![third, second]);
+        assert_eq!(last_page["total"], 3);
+        assert_eq!(last_page["offset"], 2);
+        assert_eq!(ids(&last_page), vec![first]);
+    }
+
+    #[actix_web::test]
+    async fn inverted_date_range_is_rejected() {
+        // Пул без соединения: запрос отклоняется до обращения к базе
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+
+        let (status, body) = get_purchases(&pool, "/api/purchases?from=2024-02-01T00:00:00Z&to=2024-01-01T00:00:00Z").await;
+
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("'from'"));
+    }
+
+    #[test]
+    fn offer_exactly_at_threshold_is_accepted() {
+        assert_eq!(offer_below_minimum(10_000.0, 20_000.0, 0.5), None);
+    }
+
+    #[test]
+    fn offer_one_cent_below_threshold_is_rejected() {
+        assert_eq!(offer_below_minimum(9_999.99, 20_000.0, 0.5), Some(10_000.0));
+    }
+
+    #[test]
+    fn threshold_is_compared_after_rounding_to_cents() {
+        // 33 333.33 * 0.3 = 9 999.999 - порог округляется до 10 000.00, а не остаётся дробным
+        assert_eq!(offer_below_minimum(10_000.0, 33_333.33, 0.3), None);
+        assert_eq!(offer_below_minimum(9_999.99, 33_333.33, 0.3), Some(10_000.0));
+    }
+
+    #[test]
+    fn offer_above_threshold_is_accepted() {
+        assert_eq!(offer_below_minimum(15_000.0, 20_000.0, 0.5), None);
+    }
+}
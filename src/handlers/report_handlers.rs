@@ -0,0 +1,109 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{
+    database::DbPool,
+    handlers::common::log_failure,
+    models::{AvgDaysToSaleReport, SaleMarginsReport},
+    repositories::purchase_repository::PurchaseRepositoryImpl,
+};
+use crate::repositories::PurchaseRepository;
+
+#[derive(Debug, Deserialize)]
+pub struct ReportDateRangeQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ReportDateRangeQuery {
+    fn is_valid(&self) -> bool {
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => from < to,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AvgDaysToSaleQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Добавить в отчёт разбивку по брендам
+    #[serde(default)]
+    pub by_brand: bool,
+}
+
+// GET /api/reports/avg-price-by-model - средняя цена продажи по моделям
+pub async fn get_avg_price_by_model_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<ReportDateRangeQuery>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+
+    if !query.is_valid() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "'from' must be earlier than 'to'"
+        }));
+    }
+
+    match repo.avg_price_by_model(query.from, query.to).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            log_failure("building", "average price by model report", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to build average price report"
+            }))
+        }
+    }
+}
+
+// GET /api/reports/sale-margins - маржа по завершённым продажам (сумма и среднее за период)
+pub async fn get_sale_margins_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<ReportDateRangeQuery>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+
+    if !query.is_valid() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "'from' must be earlier than 'to'"
+        }));
+    }
+
+    match repo.sale_margins(query.from, query.to).await {
+        Ok(sales) => HttpResponse::Ok().json(SaleMarginsReport::from_sales(sales)),
+        Err(e) => {
+            log_failure("building", "sale margins report", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to build sale margins report"
+            }))
+        }
+    }
+}
+
+// GET /api/reports/avg-days-to-sale - средний срок продажи автомобиля (от поступления до завершения заявки)
+pub async fn get_avg_days_to_sale_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<AvgDaysToSaleQuery>,
+) -> HttpResponse {
+    let repo = PurchaseRepositoryImpl::new(db_pool.get_ref().clone());
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from >= to {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "'from' must be earlier than 'to'"
+            }));
+        }
+    }
+
+    match repo.avg_days_to_sale(query.from, query.to).await {
+        Ok(brands) => HttpResponse::Ok().json(AvgDaysToSaleReport::from_brands(brands, query.by_brand)),
+        Err(e) => {
+            log_failure("building", "average days to sale report", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to build average days to sale report"
+            }))
+        }
+    }
+}
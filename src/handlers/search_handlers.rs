@@ -0,0 +1,107 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+
+use crate::{
+    config::Config,
+    database::DbPool,
+    handlers::common::log_failure,
+    models::{SearchResults, SearchType},
+    repositories::car_repository::CarRepositoryImpl,
+    repositories::customer_repository::CustomerRepositoryImpl,
+    repositories::part_repository::PartRepositoryImpl,
+    repositories::service_campaign_repository::ServiceCampaignRepositoryImpl,
+    repositories::work_repository::WorkRepositoryImpl,
+};
+use crate::repositories::{CarRepository, CustomerRepository, PartRepository, WorkRepository};
+use crate::repositories::service_campaign_repository::ServiceCampaignRepository;
+
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+const MAX_SEARCH_LIMIT: i64 = 50;
+const MAX_QUERY_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    pub types: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// GET /api/search?q=...&types=cars,parts - общий поиск по автомобилям, запчастям, работам, клиентам и кампаниям
+pub async fn search_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    query: web::Query<SearchQuery>,
+) -> HttpResponse {
+    let term = query.q.as_deref().map(str::trim).unwrap_or_default();
+    if term.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Query parameter 'q' is required"
+        }));
+    }
+    if term.chars().count() > MAX_QUERY_LENGTH {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Query parameter 'q' must not exceed {} characters", MAX_QUERY_LENGTH)
+        }));
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if limit <= 0 || limit > MAX_SEARCH_LIMIT {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("'limit' must be between 1 and {}", MAX_SEARCH_LIMIT)
+        }));
+    }
+
+    let types = match query.types.as_deref().map(SearchType::parse_list) {
+        None => SearchType::ALL.to_vec(),
+        Some(Ok(types)) if types.is_empty() => SearchType::ALL.to_vec(),
+        Some(Ok(types)) => types,
+        Some(Err(unknown)) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!(
+                    "Unknown search type '{}', expected one of: cars, parts, works, customers, campaigns",
+                    unknown
+                )
+            }));
+        }
+    };
+
+    let pool = db_pool.get_ref();
+    let car_repo = CarRepositoryImpl::new(pool.clone());
+    let part_repo = PartRepositoryImpl::new(pool.clone());
+    let work_repo = WorkRepositoryImpl::new(pool.clone());
+    let customer_repo = CustomerRepositoryImpl::new(pool.clone());
+    let campaign_repo = ServiceCampaignRepositoryImpl::new(pool.clone())
+        .with_strict_enum_decoding(config.database.strict_enum_decoding);
+
+    // Невыбранные типы сразу возвращают None и не обращаются к базе
+    let selected = |search_type| types.contains(&search_type);
+    let (cars, parts, works, customers, campaigns) = tokio::join!(
+        async { if selected(SearchType::Cars) { Some(car_repo.search(term, limit).await) } else { None } },
+        async { if selected(SearchType::Parts) { Some(part_repo.search(term, limit).await) } else { None } },
+        async { if selected(SearchType::Works) { Some(work_repo.search(term, limit).await) } else { None } },
+        async { if selected(SearchType::Customers) { Some(customer_repo.search(term, limit).await) } else { None } },
+        async { if selected(SearchType::Campaigns) { Some(campaign_repo.search(term, limit).await) } else { None } },
+    );
+
+    let results = (|| -> Result<SearchResults, sqlx::Error> {
+        Ok(SearchResults {
+            query: term.to_string(),
+            limit_per_type: limit,
+            cars: cars.transpose()?,
+            parts: parts.transpose()?,
+            works: works.transpose()?,
+            customers: customers.transpose()?,
+            campaigns: campaigns.transpose()?,
+        })
+    })();
+
+    match results {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => {
+            log_failure("searching", "catalog for term", Some(&format!("'{}'", term)), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to perform search"
+            }))
+        }
+    }
+}
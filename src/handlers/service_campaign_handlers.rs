@@ -1,21 +1,98 @@
-use actix_web::{web, HttpResponse};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+use actix_web::{web, HttpResponse, ResponseError};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::{CreateServiceCampaignRequest, UpdateServiceCampaignRequest, ServiceCampaignStatus},
-    repositories::service_campaign_repository::ServiceCampaignRepositoryImpl,
+    handlers::common::{check_array_fields, check_batch_size, created, log_failure, not_found_or_deleted},
+    models::{
+        CreateServiceCampaignRequest, CreatedServiceCampaign, UpdateServiceCampaignRequest, ServiceCampaignStatus,
+        ValidateVinsRequest, VinValidationResult,
+        BulkImportQuery, BulkImportRowStatus, BulkImportRowResult, BulkImportResponse, DeleteQuery,
+        ServiceCampaignFilter, PaginatedResponse, CampaignTargetCar, CampaignTargetCars
+    },
+    repositories::{
+        service_campaign_repository::ServiceCampaignRepositoryImpl,
+        car_repository::CarRepositoryImpl,
+        brand_repository::BrandRepositoryImpl,
+        car_model_repository::CarModelRepositoryImpl,
+    },
 };
 use crate::repositories::service_campaign_repository::ServiceCampaignRepository;
+use crate::repositories::{BrandRepository, CarModelRepository, CarRepository};
 
-// GET /api/service-campaigns - получить все сервисные кампании
-pub async fn get_service_campaigns_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
-    match repo.find_all().await {
-        Ok(campaigns) => HttpResponse::Ok().json(campaigns),
+const DEFAULT_CAMPAIGNS_PAGE_LIMIT: i64 = 50;
+const MAX_CAMPAIGNS_PAGE_LIMIT: i64 = 200;
+
+// Репозиторий кампаний с настройками из конфигурации: регистр артикулов и строгий разбор статусов
+fn campaign_repo(db_pool: &DbPool, config: &Config) -> ServiceCampaignRepositoryImpl {
+    ServiceCampaignRepositoryImpl::new(db_pool.clone())
+        .with_article_case(config.catalog.article_case)
+        .with_strict_enum_decoding(config.database.strict_enum_decoding)
+}
+
+const EMPTY_CAMPAIGN_MESSAGE: &str = "Campaign has neither required_parts nor required_works";
+
+#[derive(Debug, Deserialize)]
+pub struct ServiceCampaignsQuery {
+    pub brand_id: Option<Uuid>,
+    pub car_model_id: Option<Uuid>,
+    pub status: Option<ServiceCampaignStatus>,
+    pub is_mandatory: Option<bool>,
+    pub is_completed: Option<bool>,
+    pub vin: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TargetedVinsQuery {
+    pub status: Option<ServiceCampaignStatus>,
+    /// Кампании без списка VIN (на всю модель) разворачивать в VIN автомобилей модели
+    #[serde(default)]
+    pub expand_models: bool,
+}
+
+impl ServiceCampaignsQuery {
+    fn filter(&self) -> ServiceCampaignFilter {
+        ServiceCampaignFilter {
+            brand_id: self.brand_id,
+            car_model_id: self.car_model_id,
+            status: self.status.clone(),
+            is_mandatory: self.is_mandatory,
+            is_completed: self.is_completed,
+            vin: self.vin.as_ref()
+                .map(|vin| vin.trim().to_uppercase())
+                .filter(|vin| !vin.is_empty()),
+        }
+    }
+}
+
+// GET /api/service-campaigns - получить сервисные кампании (фильтры и постраничный вывод)
+pub async fn get_service_campaigns_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    query: web::Query<ServiceCampaignsQuery>,
+) -> HttpResponse {
+    let repo = campaign_repo(&db_pool, &config);
+    let limit = query.limit.unwrap_or(DEFAULT_CAMPAIGNS_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    if limit <= 0 || offset < 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "'limit' must be greater than 0 and 'offset' must not be negative"
+        }));
+    }
+    let limit = limit.min(MAX_CAMPAIGNS_PAGE_LIMIT);
+
+    match repo.find_filtered_paginated(&query.filter(), limit, offset).await {
+        Ok((campaigns, total)) => HttpResponse::Ok().json(PaginatedResponse::new(campaigns, total, limit, offset)),
         Err(e) => {
-            eprintln!("Error fetching service campaigns: {}", e);
+            log_failure("fetching", "service campaigns", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaigns"
             }))
@@ -26,9 +103,10 @@ pub async fn get_service_campaigns_handler(db_pool: web::Data<DbPool>) -> HttpRe
 // GET /api/service-campaigns/{id} - получить сервисную кампанию по ID
 pub async fn get_service_campaign_by_id_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let id = path.into_inner();
 
     match repo.find_by_id(id).await {
@@ -37,7 +115,7 @@ pub async fn get_service_campaign_by_id_handler(
             "error": "Service campaign not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching service campaign {}: {}", id, e);
+            log_failure("fetching", "service campaign", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaign"
             }))
@@ -48,9 +126,10 @@ pub async fn get_service_campaign_by_id_handler(
 // GET /api/service-campaigns/article/{article} - получить сервисную кампанию по артикулу
 pub async fn get_service_campaign_by_article_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let article = path.into_inner();
 
     match repo.find_by_article(&article).await {
@@ -59,7 +138,7 @@ pub async fn get_service_campaign_by_article_handler(
             "error": "Service campaign not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching service campaign by article {}: {}", article, e);
+            log_failure("fetching", "service campaign by article", Some(&article), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaign"
             }))
@@ -70,15 +149,16 @@ pub async fn get_service_campaign_by_article_handler(
 // GET /api/service-campaigns/brand/{brand_id} - получить сервисные кампании по бренду
 pub async fn get_service_campaigns_by_brand_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let brand_id = path.into_inner();
 
     match repo.find_by_brand(brand_id).await {
         Ok(campaigns) => HttpResponse::Ok().json(campaigns),
         Err(e) => {
-            eprintln!("Error fetching service campaigns by brand {}: {}", brand_id, e);
+            log_failure("fetching", "service campaigns by brand", Some(&brand_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaigns"
             }))
@@ -89,15 +169,16 @@ pub async fn get_service_campaigns_by_brand_handler(
 // GET /api/service-campaigns/car-model/{car_model_id} - получить сервисные кампании по модели автомобиля
 pub async fn get_service_campaigns_by_car_model_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let car_model_id = path.into_inner();
 
     match repo.find_by_car_model(car_model_id).await {
         Ok(campaigns) => HttpResponse::Ok().json(campaigns),
         Err(e) => {
-            eprintln!("Error fetching service campaigns by car model {}: {}", car_model_id, e);
+            log_failure("fetching", "service campaigns by car model", Some(&car_model_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaigns"
             }))
@@ -108,9 +189,10 @@ pub async fn get_service_campaigns_by_car_model_handler(
 // GET /api/service-campaigns/status/{status} - получить сервисные кампании по статусу
 pub async fn get_service_campaigns_by_status_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let status_str = path.into_inner();
 
     let status = match status_str.to_lowercase().as_str() {
@@ -127,7 +209,7 @@ pub async fn get_service_campaigns_by_status_handler(
     match repo.find_by_status(status).await {
         Ok(campaigns) => HttpResponse::Ok().json(campaigns),
         Err(e) => {
-            eprintln!("Error fetching service campaigns by status {}: {}", status_str, e);
+            log_failure("fetching", "service campaigns by status", Some(&status_str), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaigns"
             }))
@@ -138,15 +220,16 @@ pub async fn get_service_campaigns_by_status_handler(
 // GET /api/service-campaigns/mandatory/{is_mandatory} - получить сервисные кампании по обязательности
 pub async fn get_service_campaigns_by_mandatory_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<bool>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let is_mandatory = path.into_inner();
 
     match repo.find_by_mandatory(is_mandatory).await {
         Ok(campaigns) => HttpResponse::Ok().json(campaigns),
         Err(e) => {
-            eprintln!("Error fetching service campaigns by mandatory {}: {}", is_mandatory, e);
+            log_failure("fetching", "service campaigns by mandatory", Some(&is_mandatory), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaigns"
             }))
@@ -157,15 +240,16 @@ pub async fn get_service_campaigns_by_mandatory_handler(
 // GET /api/service-campaigns/completed/{is_completed} - получить сервисные кампании по выполнению
 pub async fn get_service_campaigns_by_completed_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<bool>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let is_completed = path.into_inner();
 
     match repo.find_by_completed(is_completed).await {
         Ok(campaigns) => HttpResponse::Ok().json(campaigns),
         Err(e) => {
-            eprintln!("Error fetching service campaigns by completed {}: {}", is_completed, e);
+            log_failure("fetching", "service campaigns by completed", Some(&is_completed), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaigns"
             }))
@@ -176,15 +260,16 @@ pub async fn get_service_campaigns_by_completed_handler(
 // GET /api/service-campaigns/vin/{vin} - получить сервисные кампании по VIN коду
 pub async fn get_service_campaigns_by_vin_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let vin = path.into_inner();
 
     match repo.find_by_vin(&vin).await {
         Ok(campaigns) => HttpResponse::Ok().json(campaigns),
         Err(e) => {
-            eprintln!("Error fetching service campaigns by VIN {}: {}", vin, e);
+            log_failure("fetching", "service campaigns by VIN", Some(&vin), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch service campaigns"
             }))
@@ -195,9 +280,10 @@ pub async fn get_service_campaigns_by_vin_handler(
 // POST /api/service-campaigns - создать сервисную кампанию
 pub async fn create_service_campaign_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     create_request: web::Json<CreateServiceCampaignRequest>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
 
     if let Err(validation_errors) = create_request.validate() {
         return HttpResponse::BadRequest().json(serde_json::json!({
@@ -205,14 +291,30 @@ pub async fn create_service_campaign_handler(
             "details": validation_errors
         }));
     }
+    if let Err(error) = check_array_fields(&*create_request, config.limits.max_array_len) {
+        return error.error_response();
+    }
+
+    // Кампания без запчастей и работ - скорее всего не заполненное до конца описание
+    let warning = if create_request.is_empty_service() {
+        if config.catalog.reject_empty_campaigns {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": EMPTY_CAMPAIGN_MESSAGE
+            }));
+        }
+        Some(EMPTY_CAMPAIGN_MESSAGE.to_string())
+    } else {
+        None
+    };
+
     match repo.exists_by_article(&create_request.article).await {
         Ok(true) => {
-            return HttpResponse::BadRequest().json(serde_json::json!({
+            return HttpResponse::Conflict().json(serde_json::json!({
                 "error": "Article already exists"
             }));
         }
         Err(e) => {
-            eprintln!("Error checking article: {}", e);
+            log_failure("checking", "article", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to check article"
             }));
@@ -221,9 +323,9 @@ pub async fn create_service_campaign_handler(
     }
 
     match repo.save(&create_request).await {
-        Ok(campaign) => HttpResponse::Created().json(campaign),
+        Ok(campaign) => created("/api/service-campaigns", campaign.id).json(CreatedServiceCampaign { campaign, warning }),
         Err(e) => {
-            eprintln!("Error creating service campaign: {}", e);
+            log_failure("creating", "service campaign", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create service campaign"
             }))
@@ -234,10 +336,11 @@ pub async fn create_service_campaign_handler(
 // PUT /api/service-campaigns/{id} - обновить сервисную кампанию
 pub async fn update_service_campaign_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
     update_request: web::Json<UpdateServiceCampaignRequest>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let id = path.into_inner();
 
     if let Err(validation_errors) = update_request.validate() {
@@ -246,20 +349,24 @@ pub async fn update_service_campaign_handler(
             "details": validation_errors
         }));
     }
+    if let Err(error) = check_array_fields(&*update_request, config.limits.max_array_len) {
+        return error.error_response();
+    }
+    // Артикул сравнивается без учёта регистра; смена регистра собственного артикула - не конфликт
     if let Some(new_article) = &update_request.article {
-        match repo.exists_by_article(new_article).await {
-            Ok(true) => {
-                return HttpResponse::BadRequest().json(serde_json::json!({
+        match repo.find_by_article(new_article).await {
+            Ok(Some(existing)) if existing.id != id => {
+                return HttpResponse::Conflict().json(serde_json::json!({
                     "error": "Article already exists"
                 }));
             }
+            Ok(_) => {}
             Err(e) => {
-                eprintln!("Error checking article: {}", e);
+                log_failure("checking", "article", None, &e);
                 return HttpResponse::InternalServerError().json(serde_json::json!({
                     "error": "Failed to check article"
                 }));
             }
-            _ => {}
         }
     }
 
@@ -269,7 +376,7 @@ pub async fn update_service_campaign_handler(
             "error": "Service campaign not found"
         })),
         Err(e) => {
-            eprintln!("Error updating service campaign {}: {}", id, e);
+            log_failure("updating", "service campaign", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update service campaign"
             }))
@@ -280,18 +387,18 @@ pub async fn update_service_campaign_handler(
 // DELETE /api/service-campaigns/{id} - удалить сервисную кампанию
 pub async fn delete_service_campaign_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
+    query: web::Query<DeleteQuery>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let id = path.into_inner();
 
     match repo.delete(id).await {
         Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Service campaign not found"
-        })),
+        Ok(false) => not_found_or_deleted(query.idempotent, "Service campaign not found"),
         Err(e) => {
-            eprintln!("Error deleting service campaign {}: {}", id, e);
+            log_failure("deleting", "service campaign", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to delete service campaign"
             }))
@@ -302,10 +409,11 @@ pub async fn delete_service_campaign_handler(
 // PATCH /api/service-campaigns/{id}/status - обновить статус сервисной кампании
 pub async fn update_service_campaign_status_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
     status: web::Json<String>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let id = path.into_inner();
     let status_str = status.into_inner();
 
@@ -326,7 +434,7 @@ pub async fn update_service_campaign_status_handler(
             "error": "Service campaign not found"
         })),
         Err(e) => {
-            eprintln!("Error updating service campaign status {}: {}", id, e);
+            log_failure("updating", "service campaign status", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update service campaign status"
             }))
@@ -337,9 +445,10 @@ pub async fn update_service_campaign_status_handler(
 // PATCH /api/service-campaigns/{id}/complete - отметить сервисную кампанию как выполненную
 pub async fn mark_service_campaign_completed_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let id = path.into_inner();
 
     match repo.mark_completed(id).await {
@@ -348,7 +457,7 @@ pub async fn mark_service_campaign_completed_handler(
             "error": "Service campaign not found"
         })),
         Err(e) => {
-            eprintln!("Error marking service campaign as completed {}: {}", id, e);
+            log_failure("marking", "service campaign as completed", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to mark service campaign as completed"
             }))
@@ -359,9 +468,10 @@ pub async fn mark_service_campaign_completed_handler(
 // PATCH /api/service-campaigns/{id}/pending - отметить сервисную кампанию как ожидающую
 pub async fn mark_service_campaign_pending_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
-    let repo = ServiceCampaignRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = campaign_repo(&db_pool, &config);
     let id = path.into_inner();
 
     match repo.mark_pending(id).await {
@@ -370,10 +480,636 @@ pub async fn mark_service_campaign_pending_handler(
             "error": "Service campaign not found"
         })),
         Err(e) => {
-            eprintln!("Error marking service campaign as pending {}: {}", id, e);
+            log_failure("marking", "service campaign as pending", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to mark service campaign as pending"
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+// PATCH /api/service-campaigns/{id}/reactivate - возобновить отменённую сервисную кампанию
+pub async fn reactivate_service_campaign_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = campaign_repo(&db_pool, &config);
+    let id = path.into_inner();
+
+    match repo.reactivate(id).await {
+        Ok(Some(campaign)) => HttpResponse::Ok().json(campaign),
+        Ok(None) => {
+            // Кампания не обновлена: либо её нет, либо она не в статусе cancelled
+            match repo.find_by_id(id).await {
+                Ok(Some(_)) => HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "Only cancelled service campaigns can be reactivated"
+                })),
+                Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Service campaign not found"
+                })),
+                Err(e) => {
+                    log_failure("fetching", "service campaign", Some(&id), &e);
+                    HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": "Failed to reactivate service campaign"
+                    }))
+                }
+            }
+        }
+        Err(e) => {
+            log_failure("reactivating", "service campaign", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reactivate service campaign"
+            }))
+        }
+    }
+}
+
+// POST /api/service-campaigns/{id}/recalculate-completion - пересчитать выполнение кампании по автомобилям
+pub async fn recalculate_campaign_completion_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = campaign_repo(&db_pool, &config);
+    let id = path.into_inner();
+
+    match repo.recalculate_completion(id).await {
+        Ok(Some(completion)) => HttpResponse::Ok().json(completion),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Service campaign not found"
+        })),
+        Err(e) => {
+            log_failure("recalculating", "completion of service campaign", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to recalculate service campaign completion"
+            }))
+        }
+    }
+}
+
+// GET /api/service-campaigns/{id}/target-cars - автомобили, на которых нужно выполнить кампанию
+pub async fn get_campaign_target_cars_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    path: web::Path<Uuid>,
+) -> HttpResponse {
+    let repo = campaign_repo(&db_pool, &config);
+    let car_repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    let campaign = match repo.find_by_id(id).await {
+        Ok(Some(campaign)) => campaign,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Service campaign not found"
+            }));
+        }
+        Err(e) => {
+            log_failure("fetching", "service campaign", Some(&id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch service campaign"
+            }));
+        }
+    };
+
+    // Пустой target_vins означает "вся модель"
+    let whole_model = campaign.target_vins.is_empty();
+    let result = if whole_model {
+        car_repo.find_by_model_id(campaign.car_model_id).await
+            .map(|cars| (cars, Vec::new()))
+    } else {
+        car_repo.find_by_vins(&campaign.target_vins).await.map(|cars| {
+            // Автомобили в порядке target_vins, каждый VIN один раз
+            let mut cars_by_vin: HashMap<String, _> = cars.into_iter().map(|car| (car.vin.clone(), car)).collect();
+            let mut seen = HashSet::new();
+            let mut ordered = Vec::with_capacity(cars_by_vin.len());
+            let mut unmatched_vins = Vec::new();
+            for vin in campaign.target_vins.iter().filter(|vin| seen.insert(vin.as_str())) {
+                match cars_by_vin.remove(vin) {
+                    Some(car) => ordered.push(car),
+                    None => unmatched_vins.push(vin.clone()),
+                }
+            }
+            (ordered, unmatched_vins)
+        })
+    };
+
+    match result {
+        Ok((cars, unmatched_vins)) => {
+            let cars = cars.into_iter()
+                .map(|car| CampaignTargetCar {
+                    campaign_completed: car.completed_service_campaigns.contains(&campaign.id),
+                    car,
+                })
+                .collect();
+
+            HttpResponse::Ok().json(CampaignTargetCars {
+                campaign_id: campaign.id,
+                whole_model,
+                cars,
+                unmatched_vins,
+            })
+        }
+        Err(e) => {
+            log_failure("resolving", "target cars of service campaign", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to resolve campaign target cars"
+            }))
+        }
+    }
+}
+
+// GET /api/service-campaigns/targeted-vins - все VIN под кампаниями со статусом (по умолчанию активные)
+pub async fn get_targeted_vins_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    query: web::Query<TargetedVinsQuery>,
+) -> HttpResponse {
+    let repo = campaign_repo(&db_pool, &config);
+    let TargetedVinsQuery { status, expand_models } = query.into_inner();
+
+    match repo.collect_target_vins(status.unwrap_or(ServiceCampaignStatus::Active), expand_models).await {
+        Ok(vins) => HttpResponse::Ok().json(vins),
+        Err(e) => {
+            log_failure("collecting", "campaign target VINs", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to collect targeted VINs"
+            }))
+        }
+    }
+}
+
+// POST /api/service-campaigns/validate-vins - проверить, что автомобили с указанными VIN существуют
+pub async fn validate_campaign_vins_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    validate_request: web::Json<ValidateVinsRequest>,
+) -> HttpResponse {
+    let car_repo = CarRepositoryImpl::new(db_pool.get_ref().clone());
+
+    if let Err(error) = check_batch_size(validate_request.vins.len(), config.limits.max_batch_size) {
+        return error.error_response();
+    }
+
+    // Убираем дубликаты, сохраняя порядок из запроса
+    let mut seen = HashSet::new();
+    let vins: Vec<String> = validate_request.vins.iter()
+        .map(|vin| vin.trim().to_uppercase())
+        .filter(|vin| !vin.is_empty() && seen.insert(vin.clone()))
+        .collect();
+
+    match car_repo.find_by_vins(&vins).await {
+        Ok(cars) => {
+            let found: HashMap<String, Uuid> = cars.into_iter()
+                .map(|car| (car.vin, car.id))
+                .collect();
+
+            let results: Vec<VinValidationResult> = vins.into_iter()
+                .map(|vin| {
+                    let car_id = found.get(&vin).copied();
+                    VinValidationResult { vin, exists: car_id.is_some(), car_id }
+                })
+                .collect();
+
+            HttpResponse::Ok().json(results)
+        }
+        Err(e) => {
+            log_failure("validating", "campaign VINs", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to validate VINs"
+            }))
+        }
+    }
+}
+
+// POST /api/service-campaigns/bulk - массовый импорт сервисных кампаний (отзывные кампании производителя)
+pub async fn bulk_import_service_campaigns_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    query: web::Query<BulkImportQuery>,
+    create_requests: web::Json<Vec<CreateServiceCampaignRequest>>,
+) -> HttpResponse {
+    let repo = campaign_repo(&db_pool, &config);
+    let brand_repo = BrandRepositoryImpl::new(db_pool.get_ref().clone());
+    let model_repo = CarModelRepositoryImpl::new(db_pool.get_ref().clone());
+
+    if let Err(error) = check_batch_size(create_requests.len(), config.limits.max_batch_size) {
+        return error.error_response();
+    }
+
+    let mut results: Vec<BulkImportRowResult> = Vec::with_capacity(create_requests.len());
+    let mut to_insert: Vec<usize> = Vec::new();
+    let mut seen_articles: HashSet<String> = HashSet::new();
+    // Кэш проверок существования: brand_id -> bool, model_id -> brand_id модели
+    let mut brands: HashMap<Uuid, bool> = HashMap::new();
+    let mut models: HashMap<Uuid, Option<Uuid>> = HashMap::new();
+
+    for (index, create_request) in create_requests.iter().enumerate() {
+        let mut result = BulkImportRowResult {
+            index,
+            article: create_request.article.clone(),
+            status: BulkImportRowStatus::Failed,
+            id: None,
+            error: None,
+            details: None,
+        };
+
+        if let Err(validation_errors) = create_request.validate() {
+            result.error = Some("Validation failed".to_string());
+            result.details = serde_json::to_value(&validation_errors).ok();
+            results.push(result);
+            continue;
+        }
+        if let Err(error) = check_array_fields(create_request, config.limits.max_array_len) {
+            result.error = Some(error.to_string());
+            result.details = Some(serde_json::json!({ "max_array_len": config.limits.max_array_len }));
+            results.push(result);
+            continue;
+        }
+
+        if !seen_articles.insert(create_request.article.to_lowercase()) {
+            result.status = BulkImportRowStatus::Skipped;
+            result.error = Some("Duplicate article in batch".to_string());
+            results.push(result);
+            continue;
+        }
+
+        match repo.exists_by_article(&create_request.article).await {
+            Ok(true) => {
+                result.status = BulkImportRowStatus::Skipped;
+                result.error = Some("Article already exists".to_string());
+                results.push(result);
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                log_failure("checking", "article", None, &e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to check article"
+                }));
+            }
+        }
+
+        if let Entry::Vacant(entry) = brands.entry(create_request.brand_id) {
+            match brand_repo.find_by_id(create_request.brand_id).await {
+                Ok(brand) => { entry.insert(brand.is_some()); }
+                Err(e) => {
+                    log_failure("fetching", "brand", Some(&create_request.brand_id), &e);
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": "Failed to check brand"
+                    }));
+                }
+            }
+        }
+        if !brands[&create_request.brand_id] {
+            result.error = Some("Brand not found".to_string());
+            results.push(result);
+            continue;
+        }
+
+        if let Entry::Vacant(entry) = models.entry(create_request.car_model_id) {
+            match model_repo.find_by_id(create_request.car_model_id).await {
+                Ok(model) => { entry.insert(model.map(|m| m.brand_id)); }
+                Err(e) => {
+                    log_failure("fetching", "car model", Some(&create_request.car_model_id), &e);
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": "Failed to check car model"
+                    }));
+                }
+            }
+        }
+        match models[&create_request.car_model_id] {
+            None => {
+                result.error = Some("Car model not found".to_string());
+                results.push(result);
+                continue;
+            }
+            Some(brand_id) if brand_id != create_request.brand_id => {
+                result.error = Some("Car model does not belong to brand".to_string());
+                results.push(result);
+                continue;
+            }
+            _ => {}
+        }
+
+        result.status = BulkImportRowStatus::NotImported;
+        to_insert.push(index);
+        results.push(result);
+    }
+
+    let failed = results.iter().filter(|r| r.status == BulkImportRowStatus::Failed).count();
+    let skipped = results.iter().filter(|r| r.status == BulkImportRowStatus::Skipped).count();
+
+    // Без continue_on_error любая ошибочная строка отменяет весь импорт
+    if failed > 0 && !query.continue_on_error {
+        return HttpResponse::BadRequest().json(BulkImportResponse {
+            created: 0,
+            skipped,
+            failed,
+            results,
+        });
+    }
+
+    let batch: Vec<&CreateServiceCampaignRequest> = to_insert.iter()
+        .map(|&index| &create_requests[index])
+        .collect();
+
+    match repo.save_many(&batch).await {
+        Ok(campaigns) => {
+            for (&index, campaign) in to_insert.iter().zip(campaigns.iter()) {
+                results[index].status = BulkImportRowStatus::Created;
+                results[index].id = Some(campaign.id);
+            }
+
+            HttpResponse::Ok().json(BulkImportResponse {
+                created: campaigns.len(),
+                skipped,
+                failed,
+                results,
+            })
+        }
+        Err(e) => {
+            log_failure("bulk importing", "service campaigns", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to import service campaigns"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::database::test_pool;
+
+    // Бренд с моделью для кампаний теста; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestBrand {
+        brand_id: Uuid,
+        model_id: Uuid,
+    }
+
+    impl TestBrand {
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xea, 0xea, 0xea, 0xea, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let brand = Self { brand_id: id(1), model_id: id(2) };
+            brand.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(brand.brand_id)
+                .bind(format!("Test Brand {}", brand.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(brand.model_id)
+                .bind(brand.brand_id)
+                .execute(pool).await.unwrap();
+            brand
+        }
+
+        async fn car(&self, pool: &DbPool, vin: &str) -> Uuid {
+            sqlx::query_scalar(
+                "INSERT INTO cars (brand_id, model_id, year, price, mileage, color, fuel_type, transmission, vin)
+                 VALUES ($1, $2, 2020, 1000000, 0, 'White', 'Petrol', 'Manual', $3)
+                 RETURNING id"
+            )
+                .bind(self.brand_id)
+                .bind(self.model_id)
+                .bind(vin)
+                .fetch_one(pool).await.unwrap()
+        }
+
+        async fn campaign(&self, pool: &DbPool, article: &str, target_vins: &[&str]) -> Uuid {
+            ServiceCampaignRepositoryImpl::new(pool.clone())
+                .save(&CreateServiceCampaignRequest {
+                    article: article.to_string(),
+                    name: "Test Campaign".to_string(),
+                    description: None,
+                    brand_id: self.brand_id,
+                    car_model_id: self.model_id,
+                    target_vins: target_vins.iter().map(|vin| vin.to_string()).collect(),
+                    required_parts: Vec::new(),
+                    required_works: Vec::new(),
+                    is_mandatory: false,
+                })
+                .await
+                .unwrap()
+                .id
+        }
+
+        // Автомобили и кампании ссылаются на бренд без каскада, поэтому удаляются первыми
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM service_campaigns WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM cars WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    fn config_with_empty_policy(reject_empty_campaigns: bool) -> web::Data<Config> {
+        let mut config = Config::from_env().expect("test configuration");
+        config.catalog.reject_empty_campaigns = reject_empty_campaigns;
+        web::Data::new(config)
+    }
+
+    fn empty_campaign(article: &str, brand_id: Uuid, car_model_id: Uuid) -> serde_json::Value {
+        serde_json::json!({
+            "article": article,
+            "name": "Test Campaign",
+            "brand_id": brand_id,
+            "car_model_id": car_model_id,
+            "target_vins": [],
+            "required_parts": [],
+            "required_works": [],
+            "is_mandatory": false
+        })
+    }
+
+    async fn post_campaign(pool: DbPool, config: web::Data<Config>, body: serde_json::Value) -> (actix_web::http::StatusCode, serde_json::Value) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(config)
+                .route("/api/service-campaigns", web::post().to(create_service_campaign_handler)),
+        ).await;
+        let req = test::TestRequest::post().uri("/api/service-campaigns").set_json(body).to_request();
+        let resp = test::call_service(&app, req).await;
+        let status = resp.status();
+        (status, test::read_body_json(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn empty_campaign_is_rejected_in_reject_mode() {
+        // Пул без соединения: запрос отклоняется до обращения к базе
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+
+        let (status, body) = post_campaign(
+            pool,
+            config_with_empty_policy(true),
+            empty_campaign("TEST-EMPTY-REJECT", Uuid::new_v4(), Uuid::new_v4()),
+        ).await;
+
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], EMPTY_CAMPAIGN_MESSAGE);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn empty_campaign_is_created_with_warning_in_warn_mode() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 1).await;
+
+        let (status, body) = post_campaign(
+            pool.clone(),
+            config_with_empty_policy(false),
+            empty_campaign("TEST-EMPTY-WARN", brand.brand_id, brand.model_id),
+        ).await;
+        brand.remove(&pool).await;
+
+        assert_eq!(status, actix_web::http::StatusCode::CREATED);
+        assert_eq!(body["warning"], EMPTY_CAMPAIGN_MESSAGE);
+        assert_eq!(body["article"], "TEST-EMPTY-WARN");
+    }
+
+    #[actix_web::test]
+    async fn campaign_with_too_many_target_vins_is_rejected() {
+        let mut config = Config::from_env().expect("test configuration");
+        config.limits.max_array_len = 1;
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+        let mut campaign = empty_campaign("TEST-ARRAY-CAMPAIGN", Uuid::new_v4(), Uuid::new_v4());
+        campaign["target_vins"] = serde_json::json!(["JTDBR32E720012345", "JTDBR32E720012346"]);
+
+        let (status, body) = post_campaign(pool, web::Data::new(config), campaign).await;
+
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["details"]["field"], "target_vins");
+        assert_eq!(body["error"]["details"]["max_array_len"], 1);
+    }
+
+    async fn get_target_cars(pool: DbPool, campaign_id: Uuid) -> serde_json::Value {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(Config::from_env().expect("test configuration")))
+                .route("/api/service-campaigns/{id}/target-cars", web::get().to(get_campaign_target_cars_handler)),
+        ).await;
+        let req = test::TestRequest::get().uri(&format!("/api/service-campaigns/{}/target-cars", campaign_id)).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        test::read_body_json(resp).await
+    }
+
+    fn target_car_ids(body: &serde_json::Value) -> Vec<Uuid> {
+        body["cars"].as_array().unwrap().iter()
+            .map(|car| car["id"].as_str().unwrap().parse().unwrap())
+            .collect()
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn target_cars_follow_target_vins_and_report_unmatched() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 2).await;
+        let first = brand.car(&pool, "EAEAEA00000000201").await;
+        let second = brand.car(&pool, "EAEAEA00000000202").await;
+        brand.car(&pool, "EAEAEA00000000203").await;
+        // Повтор VIN не дублирует автомобиль, неизвестный VIN попадает в unmatched_vins
+        let campaign_id = brand.campaign(
+            &pool,
+            "TEST-TARGET-VINS",
+            &["EAEAEA00000000202", "EAEAEA00000000299", "EAEAEA00000000201", "EAEAEA00000000202"],
+        ).await;
+        sqlx::query("UPDATE cars SET completed_service_campaigns = ARRAY[$1] WHERE id = $2")
+            .bind(campaign_id)
+            .bind(first)
+            .execute(&pool).await.unwrap();
+
+        let body = get_target_cars(pool.clone(), campaign_id).await;
+        brand.remove(&pool).await;
+
+        assert_eq!(body["whole_model"], false);
+        assert_eq!(target_car_ids(&body), vec![second, first]);
+        assert_eq!(body["cars"][0]["campaign_completed"], false);
+        assert_eq!(body["cars"][1]["campaign_completed"], true);
+        assert_eq!(body["unmatched_vins"], serde_json::json!(["EAEAEA00000000299"]));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn target_cars_without_vins_cover_the_whole_model() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 3).await;
+        let first = brand.car(&pool, "EAEAEA00000000301").await;
+        let second = brand.car(&pool, "EAEAEA00000000302").await;
+        let campaign_id = brand.campaign(&pool, "TEST-TARGET-MODEL", &[]).await;
+
+        let body = get_target_cars(pool.clone(), campaign_id).await;
+        brand.remove(&pool).await;
+
+        let mut ids = target_car_ids(&body);
+        ids.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(body["whole_model"], true);
+        assert_eq!(ids, expected);
+        assert_eq!(body["unmatched_vins"], serde_json::json!([]));
+    }
+
+    async fn get_campaigns(pool: DbPool, uri: &str) -> (actix_web::http::StatusCode, serde_json::Value) {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool))
+                .app_data(web::Data::new(Config::from_env().expect("test configuration")))
+                .route("/api/service-campaigns", web::get().to(get_service_campaigns_handler)),
+        ).await;
+        let resp = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        let status = resp.status();
+        (status, test::read_body_json(resp).await)
+    }
+
+    fn campaign_ids(page: &serde_json::Value) -> Vec<String> {
+        page["items"].as_array().unwrap().iter().map(|item| item["id"].as_str().unwrap().to_string()).collect()
+    }
+
+    #[actix_web::test]
+    async fn campaigns_page_with_invalid_limit_is_rejected() {
+        // Пул без соединения: запрос отклоняется до обращения к базе
+        let pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap();
+
+        let (status, _) = get_campaigns(pool, "/api/service-campaigns?limit=0").await;
+
+        assert_eq!(status, actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn campaigns_are_filtered_and_paginated() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 4).await;
+        let targeted = brand.campaign(&pool, "TEST-PAGE-1", &["EAEAEA00000000041"]).await;
+        brand.campaign(&pool, "TEST-PAGE-2", &[]).await;
+        brand.campaign(&pool, "TEST-PAGE-3", &[]).await;
+        let by_brand = format!("/api/service-campaigns?brand_id={}&limit=2", brand.brand_id);
+
+        let (first_status, first_page) = get_campaigns(pool.clone(), &by_brand).await;
+        let (_, second_page) = get_campaigns(pool.clone(), &format!("{}&offset=2", by_brand)).await;
+        // VIN в фильтре приводится к верхнему регистру
+        let (_, by_vin) = get_campaigns(
+            pool.clone(),
+            &format!("/api/service-campaigns?brand_id={}&vin=eaeaea00000000041", brand.brand_id),
+        ).await;
+        brand.remove(&pool).await;
+
+        assert_eq!(first_status, actix_web::http::StatusCode::OK);
+        assert_eq!((first_page["total"].as_i64(), first_page["limit"].as_i64()), (Some(3), Some(2)));
+        assert_eq!(campaign_ids(&first_page).len(), 2);
+        assert_eq!(campaign_ids(&second_page).len(), 1);
+        assert!(campaign_ids(&second_page).iter().all(|id| !campaign_ids(&first_page).contains(id)));
+        assert_eq!(campaign_ids(&by_vin), vec![targeted.to_string()]);
+        assert_eq!(by_vin["total"], 1);
+    }
+}
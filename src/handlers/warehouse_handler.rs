@@ -1,16 +1,51 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
+    handlers::common::{check_batch_size, created, log_failure, not_found_or_deleted},
     models::warehouse::{
-        CreateWarehouseItemRequest, UpdateWarehouseItemRequest,
-        StockMovementRequest, StockMovementType
+        CreateWarehouseItemRequest, UpdateWarehouseItemRequest, UpdateWarehouseLocationRequest,
+        StockMovementRequest, StockUpdateResult, InventorySnapshotComparison,
+        ReservePartsRequest, PartsReservation
     },
+    models::{DeleteQuery, PaginatedResponse},
     repositories::warehouse_repository::WarehouseRepositoryImpl,
 };
 use crate::repositories::warehouse_repository::WarehouseRepository;
+use crate::repositories::StockError;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSnapshotQuery {
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareSnapshotsQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+const DEFAULT_MOVEMENTS_PAGE_LIMIT: i64 = 50;
+const MAX_MOVEMENTS_PAGE_LIMIT: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct MovementsQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+const DEFAULT_FORECAST_DAYS: i32 = 30;
+const MAX_FORECAST_DAYS: i32 = 365;
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    pub days: Option<i32>,
+}
 
 // GET /api/warehouse - получить все складские позиции
 pub async fn get_warehouse_items_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
@@ -18,7 +53,7 @@ pub async fn get_warehouse_items_handler(db_pool: web::Data<DbPool>) -> HttpResp
     match repo.find_all().await {
         Ok(items) => HttpResponse::Ok().json(items),
         Err(e) => {
-            eprintln!("Error fetching warehouse items: {}", e);
+            log_failure("fetching", "warehouse items", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch warehouse items"
             }))
@@ -32,7 +67,7 @@ pub async fn get_low_stock_items_handler(db_pool: web::Data<DbPool>) -> HttpResp
     match repo.find_all_with_low_stock().await {
         Ok(items) => HttpResponse::Ok().json(items),
         Err(e) => {
-            eprintln!("Error fetching low stock items: {}", e);
+            log_failure("fetching", "low stock items", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch low stock items"
             }))
@@ -40,6 +75,51 @@ pub async fn get_low_stock_items_handler(db_pool: web::Data<DbPool>) -> HttpResp
     }
 }
 
+// GET /api/warehouse/orphans - складские позиции, ссылающиеся на удалённые запчасти
+pub async fn get_warehouse_orphans_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    match repo.find_orphans().await {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(e) => {
+            log_failure("fetching", "orphaned warehouse items", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch orphaned warehouse items"
+            }))
+        }
+    }
+}
+
+// DELETE /api/warehouse/orphans - удалить складские позиции, ссылающиеся на удалённые запчасти
+pub async fn delete_warehouse_orphans_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    match repo.delete_orphans().await {
+        Ok(deleted_ids) => HttpResponse::Ok().json(serde_json::json!({
+            "deleted": deleted_ids.len(),
+            "deleted_ids": deleted_ids
+        })),
+        Err(e) => {
+            log_failure("deleting", "orphaned warehouse items", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete orphaned warehouse items"
+            }))
+        }
+    }
+}
+
+// GET /api/warehouse/integrity-check - найти запчасти с несколькими складскими позициями
+pub async fn get_warehouse_integrity_check_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    match repo.find_duplicates().await {
+        Ok(duplicates) => HttpResponse::Ok().json(duplicates),
+        Err(e) => {
+            log_failure("checking", "warehouse integrity", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to check warehouse integrity"
+            }))
+        }
+    }
+}
+
 // GET /api/warehouse/{id} - получить складскую позицию по ID
 pub async fn get_warehouse_item_by_id_handler(
     db_pool: web::Data<DbPool>,
@@ -54,7 +134,7 @@ pub async fn get_warehouse_item_by_id_handler(
             "error": "Warehouse item not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching warehouse item {}: {}", id, e);
+            log_failure("fetching", "warehouse item", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch warehouse item"
             }))
@@ -76,7 +156,7 @@ pub async fn get_warehouse_item_by_part_id_handler(
             "error": "Warehouse item not found for this part"
         })),
         Err(e) => {
-            eprintln!("Error fetching warehouse item by part_id {}: {}", part_id, e);
+            log_failure("fetching", "warehouse item by part_id", Some(&part_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch warehouse item"
             }))
@@ -98,7 +178,7 @@ pub async fn get_warehouse_item_by_article_handler(
             "error": "Warehouse item not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching warehouse item by article {}: {}", article, e);
+            log_failure("fetching", "warehouse item by article", Some(&article), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch warehouse item"
             }))
@@ -117,7 +197,7 @@ pub async fn get_warehouse_items_by_location_handler(
     match repo.find_by_location(&location).await {
         Ok(items) => HttpResponse::Ok().json(items),
         Err(e) => {
-            eprintln!("Error fetching warehouse items by location {}: {}", location, e);
+            log_failure("fetching", "warehouse items by location", Some(&location), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch warehouse items"
             }))
@@ -145,7 +225,7 @@ pub async fn create_warehouse_item_handler(
             }));
         }
         Err(e) => {
-            eprintln!("Error checking existing warehouse item: {}", e);
+            log_failure("checking", "existing warehouse item", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to check existing warehouse item"
             }));
@@ -153,10 +233,36 @@ pub async fn create_warehouse_item_handler(
         _ => {}
     }
 
+    // Уровень из запроса вместе с уровнем бренда по умолчанию тоже должен давать min <= max
+    let (min_stock_level, max_stock_level) = match repo
+        .resolve_stock_levels(create_request.part_id, create_request.min_stock_level, create_request.max_stock_level)
+        .await
+    {
+        Ok(levels) => levels,
+        Err(e) => {
+            log_failure("resolving", "stock levels", Some(&create_request.part_id), &e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create warehouse item"
+            }));
+        }
+    };
+    if min_stock_level > max_stock_level {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!(
+                "'min_stock_level' ({}) must not exceed 'max_stock_level' ({}); levels missing from the request are taken from the part's brand defaults",
+                min_stock_level, max_stock_level
+            ),
+            "details": {
+                "min_stock_level": min_stock_level,
+                "max_stock_level": max_stock_level
+            }
+        }));
+    }
+
     match repo.save(&create_request).await {
-        Ok(item) => HttpResponse::Created().json(item),
+        Ok(item) => created("/api/warehouse", item.id).json(item),
         Err(e) => {
-            eprintln!("Error creating warehouse item: {}", e);
+            log_failure("creating", "warehouse item", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create warehouse item"
             }))
@@ -186,7 +292,7 @@ pub async fn update_warehouse_item_handler(
             "error": "Warehouse item not found"
         })),
         Err(e) => {
-            eprintln!("Error updating warehouse item {}: {}", id, e);
+            log_failure("updating", "warehouse item", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update warehouse item"
             }))
@@ -194,21 +300,101 @@ pub async fn update_warehouse_item_handler(
     }
 }
 
+// PATCH /api/warehouse/{id}/location - переместить складскую позицию (только местоположение)
+pub async fn update_warehouse_location_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    location_request: web::Json<UpdateWarehouseLocationRequest>,
+) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    let id = path.into_inner();
+
+    if let Err(validation_errors) = location_request.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Validation failed",
+            "details": validation_errors
+        }));
+    }
+
+    let location = location_request.location.trim();
+    if location.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Location must not be empty"
+        }));
+    }
+
+    match repo.update_location(id, location).await {
+        Ok(Some(item)) => HttpResponse::Ok().json(item),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Warehouse item not found"
+        })),
+        Err(e) => {
+            log_failure("relocating", "warehouse item", Some(&id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update warehouse location"
+            }))
+        }
+    }
+}
+
+// POST /api/warehouse/reserve - атомарно зарезервировать (списать) запчасти под работу
+pub async fn reserve_parts_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    reserve_request: web::Json<ReservePartsRequest>,
+) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+
+    if let Err(error) = check_batch_size(reserve_request.items.len(), config.limits.max_batch_size) {
+        return error.error_response();
+    }
+    if let Err(validation_errors) = reserve_request.validate() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Validation failed",
+            "details": validation_errors
+        }));
+    }
+
+    let reference = reserve_request.reference.trim();
+    if reference.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Reference must not be empty"
+        }));
+    }
+    let reason = reserve_request.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty());
+
+    match repo.reserve_parts(&reserve_request.items, reference, reason).await {
+        Ok(PartsReservation::Reserved(items)) => HttpResponse::Ok().json(serde_json::json!({
+            "reference": reference,
+            "items": items
+        })),
+        Ok(PartsReservation::Insufficient(parts)) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Insufficient stock",
+            "insufficient": parts
+        })),
+        Err(e) => {
+            log_failure("reserving", "parts for reference", Some(&reference), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to reserve parts"
+            }))
+        }
+    }
+}
+
 // DELETE /api/warehouse/{id} - удалить складскую позицию
 pub async fn delete_warehouse_item_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    query: web::Query<DeleteQuery>,
 ) -> HttpResponse {
     let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
     match repo.delete(id).await {
         Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Warehouse item not found"
-        })),
+        Ok(false) => not_found_or_deleted(query.idempotent, "Warehouse item not found"),
         Err(e) => {
-            eprintln!("Error deleting warehouse item {}: {}", id, e);
+            log_failure("deleting", "warehouse item", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to delete warehouse item"
             }))
@@ -233,20 +419,48 @@ pub async fn update_stock_handler(
     }
 
     match repo.update_stock(part_id, &movement_request).await {
-        Ok(Some(item)) => HttpResponse::Ok().json(item),
-        Ok(None) => {
-            let error_msg = match movement_request.movement_type {
-                StockMovementType::Outgoing => "Warehouse item not found or insufficient stock",
-                _ => "Warehouse item not found"
-            };
-            HttpResponse::NotFound().json(serde_json::json!({
-                "error": error_msg
+        Ok(item) => HttpResponse::Ok().json(StockUpdateResult::from(item)),
+        Err(StockError::NotFound) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Warehouse item not found"
+        })),
+        Err(StockError::InsufficientStock { available, requested }) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Insufficient stock",
+            "available": available,
+            "requested": requested
+        })),
+        Err(StockError::Database(e)) => {
+            log_failure("updating", "stock for part", Some(&part_id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update stock"
             }))
         }
+    }
+}
+
+// GET /api/warehouse/{part_id}/movements - история движений запчасти (сначала новые, постранично)
+pub async fn get_stock_movements_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    query: web::Query<MovementsQuery>,
+) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    let part_id = path.into_inner();
+    let limit = query.limit.unwrap_or(DEFAULT_MOVEMENTS_PAGE_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    if limit <= 0 || offset < 0 {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "'limit' must be greater than 0 and 'offset' must not be negative"
+        }));
+    }
+    let limit = limit.min(MAX_MOVEMENTS_PAGE_LIMIT);
+
+    match repo.find_movements(part_id, limit, offset).await {
+        Ok((movements, total)) => HttpResponse::Ok().json(PaginatedResponse::new(movements, total, limit, offset)),
         Err(e) => {
-            eprintln!("Error updating stock for part {}: {}", part_id, e);
+            log_failure("fetching", "stock movements for part", Some(&part_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to update stock"
+                "error": "Failed to fetch stock movements"
             }))
         }
     }
@@ -260,10 +474,276 @@ pub async fn get_total_inventory_value_handler(db_pool: web::Data<DbPool>) -> Ht
             "total_value": total_value
         })),
         Err(e) => {
-            eprintln!("Error calculating total inventory value: {}", e);
+            log_failure("calculating", "total inventory value", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to calculate total inventory value"
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+// GET /api/warehouse/reorder-cost - сколько стоит дозаказать все позиции с низким запасом до максимума
+pub async fn get_reorder_cost_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    match repo.reorder_cost().await {
+        Ok(reorder_cost) => HttpResponse::Ok().json(reorder_cost),
+        Err(e) => {
+            log_failure("calculating", "reorder cost", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to calculate reorder cost"
+            }))
+        }
+    }
+}
+
+// GET /api/warehouse/stats - сводка по складу: остатки, стоимость, низкий и избыточный запас, места хранения
+pub async fn get_warehouse_stats_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    match repo.stats().await {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(e) => {
+            log_failure("calculating", "warehouse stats", None, &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to calculate warehouse stats"
+            }))
+        }
+    }
+}
+
+// GET /api/warehouse/{part_id}/forecast - прогноз, когда запас запчасти опустится до минимума
+pub async fn get_stock_forecast_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<Uuid>,
+    query: web::Query<ForecastQuery>,
+) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    let part_id = path.into_inner();
+    let days = query.days.unwrap_or(DEFAULT_FORECAST_DAYS);
+
+    if !(1..=MAX_FORECAST_DAYS).contains(&days) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("days must be between 1 and {}", MAX_FORECAST_DAYS)
+        }));
+    }
+
+    match repo.forecast_depletion(part_id, days).await {
+        Ok(Some(forecast)) => HttpResponse::Ok().json(forecast),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Warehouse item not found for this part"
+        })),
+        Err(e) => {
+            log_failure("forecasting", "stock for part", Some(&part_id), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to forecast stock"
+            }))
+        }
+    }
+}
+
+// POST /api/warehouse/snapshots - сохранить снимок текущих остатков (по умолчанию на сегодня)
+pub async fn create_inventory_snapshot_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<CreateSnapshotQuery>,
+) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    let snapshot_date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+
+    match repo.create_snapshot(snapshot_date).await {
+        Ok(Some(summary)) => created("/api/warehouse/snapshots", snapshot_date).json(summary),
+        Ok(None) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Warehouse is empty, nothing to snapshot"
+        })),
+        Err(e) => {
+            log_failure("creating", "inventory snapshot", Some(&snapshot_date), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create inventory snapshot"
+            }))
+        }
+    }
+}
+
+// GET /api/warehouse/snapshots/{date} - итоги сохраненного снимка остатков
+pub async fn get_inventory_snapshot_handler(
+    db_pool: web::Data<DbPool>,
+    path: web::Path<NaiveDate>,
+) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    let snapshot_date = path.into_inner();
+
+    match repo.find_snapshot_summary(snapshot_date).await {
+        Ok(Some(summary)) => HttpResponse::Ok().json(summary),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Inventory snapshot for {} not found", snapshot_date)
+        })),
+        Err(e) => {
+            log_failure("fetching", "inventory snapshot", Some(&snapshot_date), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch inventory snapshot"
+            }))
+        }
+    }
+}
+
+// GET /api/warehouse/snapshots/compare - сравнить два снимка остатков
+pub async fn compare_inventory_snapshots_handler(
+    db_pool: web::Data<DbPool>,
+    query: web::Query<CompareSnapshotsQuery>,
+) -> HttpResponse {
+    let repo = WarehouseRepositoryImpl::new(db_pool.get_ref().clone());
+    let CompareSnapshotsQuery { from, to } = query.into_inner();
+
+    let mut totals = Vec::with_capacity(2);
+    for date in [from, to] {
+        match repo.find_snapshot_summary(date).await {
+            Ok(Some(summary)) => totals.push(summary.total_value),
+            Ok(None) => {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "error": format!("Inventory snapshot for {} not found", date)
+                }));
+            }
+            Err(e) => {
+                log_failure("fetching", "inventory snapshot", Some(&date), &e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to compare inventory snapshots"
+                }));
+            }
+        }
+    }
+
+    match repo.compare_snapshots(from, to).await {
+        Ok(parts) => HttpResponse::Ok().json(InventorySnapshotComparison {
+            from,
+            to,
+            from_total_value: totals[0],
+            to_total_value: totals[1],
+            total_value_change: totals[1] - totals[0],
+            parts,
+        }),
+        Err(e) => {
+            log_failure("comparing", "inventory snapshots", Some(&format!("{} and {}", from, to)), &e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to compare inventory snapshots"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+    use crate::models::warehouse::{ReservePartItem, DEFAULT_MAX_STOCK_LEVEL, DEFAULT_MIN_STOCK_LEVEL};
+
+    // Бренд (с уровнями запаса по умолчанию или без) и его запчасть; у каждого теста свой номер
+    struct TestBrandPart {
+        brand_id: Uuid,
+        part_id: Uuid,
+    }
+
+    impl TestBrandPart {
+        async fn insert(pool: &DbPool, n: u8, stock_defaults: Option<(i32, i32)>) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xde, 0xde, 0xde, 0xde, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let fixture = Self { brand_id: id(1), part_id: id(2) };
+            fixture.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(fixture.brand_id)
+                .bind(format!("Test Brand {}", fixture.brand_id))
+                .execute(pool).await.unwrap();
+            if let Some((min_stock_level, max_stock_level)) = stock_defaults {
+                sqlx::query("INSERT INTO brand_stock_defaults (brand_id, min_stock_level, max_stock_level) VALUES ($1, $2, $3)")
+                    .bind(fixture.brand_id)
+                    .bind(min_stock_level)
+                    .bind(max_stock_level)
+                    .execute(pool).await.unwrap();
+            }
+            sqlx::query("INSERT INTO parts (id, article, name, brand_id, purchase_price, sale_price) VALUES ($1, $2, 'Test Part', $3, 10, 20)")
+                .bind(fixture.part_id)
+                .bind(format!("TEST-LEVELS-{}", n))
+                .bind(fixture.brand_id)
+                .execute(pool).await.unwrap();
+            fixture
+        }
+
+        // Складская позиция удаляется каскадом вместе с запчастью, уровни по умолчанию - вместе с брендом
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM parts WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+
+        async fn create_item(&self, pool: &DbPool, min_stock_level: Option<i32>, max_stock_level: Option<i32>) -> (actix_web::http::StatusCode, serde_json::Value) {
+            let resp = create_warehouse_item_handler(
+                web::Data::new(pool.clone()),
+                web::Json(CreateWarehouseItemRequest {
+                    part_id: self.part_id,
+                    quantity: 10,
+                    min_stock_level,
+                    max_stock_level,
+                    location: None,
+                }),
+            ).await;
+            let status = resp.status();
+            let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+            (status, serde_json::from_slice(&body).unwrap())
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn brand_stock_defaults_fill_missing_levels_and_are_checked() {
+        let pool = test_pool().await;
+        let fixture = TestBrandPart::insert(&pool, 1, Some((5, 20))).await;
+
+        // Минимум из запроса больше максимума бренда - позиция не создаётся
+        let (rejected_status, rejected) = fixture.create_item(&pool, Some(50), None).await;
+        let (status, item) = fixture.create_item(&pool, Some(8), None).await;
+        fixture.remove(&pool).await;
+
+        assert_eq!(rejected_status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(rejected["details"]["min_stock_level"], 50);
+        assert_eq!(rejected["details"]["max_stock_level"], 20);
+        assert_eq!(status, actix_web::http::StatusCode::CREATED);
+        assert_eq!(item["min_stock_level"], 8);
+        assert_eq!(item["max_stock_level"], 20);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn brand_without_stock_defaults_uses_global_levels() {
+        let pool = test_pool().await;
+        let fixture = TestBrandPart::insert(&pool, 2, None).await;
+
+        // Без уровней бренда минимум сравнивается с общим максимумом
+        let (rejected_status, rejected) = fixture.create_item(&pool, Some(DEFAULT_MAX_STOCK_LEVEL + 1), None).await;
+        let (status, item) = fixture.create_item(&pool, None, None).await;
+        fixture.remove(&pool).await;
+
+        assert_eq!(rejected_status, actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(rejected["details"]["max_stock_level"], DEFAULT_MAX_STOCK_LEVEL);
+        assert_eq!(status, actix_web::http::StatusCode::CREATED);
+        assert_eq!(item["min_stock_level"], DEFAULT_MIN_STOCK_LEVEL);
+        assert_eq!(item["max_stock_level"], DEFAULT_MAX_STOCK_LEVEL);
+    }
+
+    #[actix_web::test]
+    async fn reserve_parts_rejects_batch_over_limit() {
+        let mut config = Config::from_env().expect("test configuration");
+        config.limits.max_batch_size = 2;
+        let items = (0..3).map(|_| ReservePartItem { part_id: Uuid::new_v4(), quantity: 1 }).collect();
+
+        // Пул без соединения: до базы запрос не доходит
+        let resp = reserve_parts_handler(
+            web::Data::new(sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap()),
+            web::Data::new(config),
+            web::Json(ReservePartsRequest { reference: "RO-1".to_string(), reason: None, items }),
+        ).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["details"]["max_batch_size"], 2);
+    }
+}
@@ -1,21 +1,58 @@
 use actix_web::{web, HttpResponse};
+use serde::Deserialize;
 use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
+    config::Config,
     database::DbPool,
-    models::{CreateWorkRequest, UpdateWorkRequest},
+    handlers::common::{cacheable_ok, created, log_failure, not_found_or_deleted},
+    models::{CreateWorkRequest, UpdateWorkRequest, DeleteQuery},
     repositories::work_repository::WorkRepositoryImpl,
 };
 use crate::repositories::WorkRepository;
 
-// GET /api/works - получить все работы
-pub async fn get_works_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
+#[derive(Debug, Deserialize)]
+pub struct WorksQuery {
+    pub min_hours: Option<f64>,
+    pub max_hours: Option<f64>,
+    pub brand_id: Option<Uuid>,
+    pub car_model_id: Option<Uuid>,
+}
+
+impl WorksQuery {
+    fn has_filters(&self) -> bool {
+        self.min_hours.is_some() || self.max_hours.is_some()
+            || self.brand_id.is_some() || self.car_model_id.is_some()
+    }
+}
+
+// GET /api/works - получить все работы (с фильтрами по норме часов, бренду и модели)
+pub async fn get_works_handler(
+    db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
+    query: web::Query<WorksQuery>,
+) -> HttpResponse {
     let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
-    match repo.find_all().await {
-        Ok(works) => HttpResponse::Ok().json(works),
+
+    if let (Some(min_hours), Some(max_hours)) = (query.min_hours, query.max_hours) {
+        if min_hours > max_hours {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "'min_hours' must not be greater than 'max_hours'"
+            }));
+        }
+    }
+
+    let result = if query.has_filters() {
+        repo.find_by_hours_range(query.min_hours, query.max_hours, query.brand_id, query.car_model_id).await
+    } else {
+        repo.find_all().await
+    };
+
+    match result {
+        Ok(works) => cacheable_ok(config.cache.works_max_age).json(works),
         Err(e) => {
-            eprintln!("Error fetching works: {}", e);
+            log_failure("fetching", "works", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch works"
             }))
@@ -26,18 +63,19 @@ pub async fn get_works_handler(db_pool: web::Data<DbPool>) -> HttpResponse {
 // GET /api/works/{id} - получить работу по ID
 pub async fn get_work_by_id_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
     let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
     match repo.find_by_id(id).await {
-        Ok(Some(work)) => HttpResponse::Ok().json(work),
+        Ok(Some(work)) => cacheable_ok(config.cache.works_max_age).json(work),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Work not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching work {}: {}", id, e);
+            log_failure("fetching", "work", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch work"
             }))
@@ -48,18 +86,19 @@ pub async fn get_work_by_id_handler(
 // GET /api/works/article/{article} - получить работу по артикулу
 pub async fn get_work_by_article_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
 ) -> HttpResponse {
     let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
     let article = path.into_inner();
 
     match repo.find_by_article(&article).await {
-        Ok(Some(work)) => HttpResponse::Ok().json(work),
+        Ok(Some(work)) => cacheable_ok(config.cache.works_max_age).json(work),
         Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
             "error": "Work not found"
         })),
         Err(e) => {
-            eprintln!("Error fetching work by article {}: {}", article, e);
+            log_failure("fetching", "work by article", Some(&article), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch work"
             }))
@@ -70,15 +109,16 @@ pub async fn get_work_by_article_handler(
 // GET /api/works/brand/{brand_id} - получить работы по бренду
 pub async fn get_works_by_brand_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
     let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
     let brand_id = path.into_inner();
 
     match repo.find_by_brand(brand_id).await {
-        Ok(works) => HttpResponse::Ok().json(works),
+        Ok(works) => cacheable_ok(config.cache.works_max_age).json(works),
         Err(e) => {
-            eprintln!("Error fetching works by brand {}: {}", brand_id, e);
+            log_failure("fetching", "works by brand", Some(&brand_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch works"
             }))
@@ -89,15 +129,16 @@ pub async fn get_works_by_brand_handler(
 // GET /api/works/car-model/{car_model_id} - получить работы по модели автомобиля
 pub async fn get_works_by_car_model_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
 ) -> HttpResponse {
     let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
     let car_model_id = path.into_inner();
 
     match repo.find_by_car_model(car_model_id).await {
-        Ok(works) => HttpResponse::Ok().json(works),
+        Ok(works) => cacheable_ok(config.cache.works_max_age).json(works),
         Err(e) => {
-            eprintln!("Error fetching works by car model {}: {}", car_model_id, e);
+            log_failure("fetching", "works by car model", Some(&car_model_id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch works"
             }))
@@ -108,15 +149,16 @@ pub async fn get_works_by_car_model_handler(
 // GET /api/works/name/{name} - получить работы по названию
 pub async fn get_works_by_name_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<String>,
 ) -> HttpResponse {
     let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
     let name = path.into_inner();
 
     match repo.find_by_name(&name).await {
-        Ok(works) => HttpResponse::Ok().json(works),
+        Ok(works) => cacheable_ok(config.cache.works_max_age).json(works),
         Err(e) => {
-            eprintln!("Error fetching works by name {}: {}", name, e);
+            log_failure("fetching", "works by name", Some(&name), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to fetch works"
             }))
@@ -127,9 +169,11 @@ pub async fn get_works_by_name_handler(
 // POST /api/works - создать работу
 pub async fn create_work_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     create_request: web::Json<CreateWorkRequest>,
 ) -> HttpResponse {
-    let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_article_case(config.catalog.article_case);
 
     if let Err(validation_errors) = create_request.validate() {
         return HttpResponse::BadRequest().json(serde_json::json!({
@@ -141,12 +185,12 @@ pub async fn create_work_handler(
     // Проверка уникальности артикула
     match repo.exists_by_article(&create_request.article).await {
         Ok(true) => {
-            return HttpResponse::BadRequest().json(serde_json::json!({
+            return HttpResponse::Conflict().json(serde_json::json!({
                 "error": "Article already exists"
             }));
         }
         Err(e) => {
-            eprintln!("Error checking article: {}", e);
+            log_failure("checking", "article", None, &e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to check article"
             }));
@@ -155,9 +199,9 @@ pub async fn create_work_handler(
     }
 
     match repo.save(&create_request).await {
-        Ok(work) => HttpResponse::Created().json(work),
+        Ok(work) => created("/api/works", work.id).json(work),
         Err(e) => {
-            eprintln!("Error creating work: {}", e);
+            log_failure("creating", "work", None, &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to create work"
             }))
@@ -168,10 +212,12 @@ pub async fn create_work_handler(
 // PUT /api/works/{id} - обновить работу
 pub async fn update_work_handler(
     db_pool: web::Data<DbPool>,
+    config: web::Data<Config>,
     path: web::Path<Uuid>,
     update_request: web::Json<UpdateWorkRequest>,
 ) -> HttpResponse {
-    let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
+    let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone())
+        .with_article_case(config.catalog.article_case);
     let id = path.into_inner();
 
     if let Err(validation_errors) = update_request.validate() {
@@ -180,20 +226,21 @@ pub async fn update_work_handler(
             "details": validation_errors
         }));
     }
+    // Артикул сравнивается без учёта регистра; смена регистра собственного артикула - не конфликт
     if let Some(new_article) = &update_request.article {
-        match repo.exists_by_article(new_article).await {
-            Ok(true) => {
-                return HttpResponse::BadRequest().json(serde_json::json!({
+        match repo.find_by_article(new_article).await {
+            Ok(Some(existing)) if existing.id != id => {
+                return HttpResponse::Conflict().json(serde_json::json!({
                     "error": "Article already exists"
                 }));
             }
+            Ok(_) => {}
             Err(e) => {
-                eprintln!("Error checking article: {}", e);
+                log_failure("checking", "article", None, &e);
                 return HttpResponse::InternalServerError().json(serde_json::json!({
                     "error": "Failed to check article"
                 }));
             }
-            _ => {}
         }
     }
 
@@ -203,7 +250,7 @@ pub async fn update_work_handler(
             "error": "Work not found"
         })),
         Err(e) => {
-            eprintln!("Error updating work {}: {}", id, e);
+            log_failure("updating", "work", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to update work"
             }))
@@ -215,17 +262,16 @@ pub async fn update_work_handler(
 pub async fn delete_work_handler(
     db_pool: web::Data<DbPool>,
     path: web::Path<Uuid>,
+    query: web::Query<DeleteQuery>,
 ) -> HttpResponse {
     let repo = WorkRepositoryImpl::new(db_pool.get_ref().clone());
     let id = path.into_inner();
 
     match repo.delete(id).await {
         Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Work not found"
-        })),
+        Ok(false) => not_found_or_deleted(query.idempotent, "Work not found"),
         Err(e) => {
-            eprintln!("Error deleting work {}: {}", id, e);
+            log_failure("deleting", "work", Some(&id), &e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": "Failed to delete work"
             }))
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+use crate::config::JobsConfig;
+use crate::database::DbPool;
+use crate::repositories::car_repository::CarRepositoryImpl;
+use crate::repositories::CarRepository;
+
+pub type JobResult = Result<String, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Периодическая фоновая задача; результат выполнения (краткое описание) попадает в лог
+#[async_trait]
+pub trait Job: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn run(&self, pool: &DbPool) -> JobResult;
+}
+
+/// Снимает резерв с автомобилей, которые находятся в статусе Reserved дольше TTL
+pub struct ReleaseExpiredReservationsJob {
+    pub ttl_hours: i32,
+}
+
+#[async_trait]
+impl Job for ReleaseExpiredReservationsJob {
+    fn name(&self) -> &'static str {
+        "release_expired_reservations"
+    }
+
+    async fn run(&self, pool: &DbPool) -> JobResult {
+        let repo = CarRepositoryImpl::new(pool.clone());
+        let released = repo.release_expired_reservations(self.ttl_hours).await?;
+        Ok(format!("released {} reservation(s)", released.len()))
+    }
+}
+
+// Задачи, включённые в конфигурации, с интервалами запуска
+fn registered_jobs(config: &JobsConfig) -> Vec<(Arc<dyn Job>, Duration)> {
+    let mut jobs: Vec<(Arc<dyn Job>, Duration)> = Vec::new();
+
+    if config.release_reservations.enabled {
+        jobs.push((
+            Arc::new(ReleaseExpiredReservationsJob { ttl_hours: config.reservation_ttl_hours }),
+            Duration::from_secs(config.release_reservations.interval_secs),
+        ));
+    }
+
+    jobs
+}
+
+/// Запускает каждую включённую задачу в отдельном цикле по своему интервалу.
+/// Ошибка или паника задачи логируется и не останавливает цикл.
+pub fn spawn_jobs(pool: DbPool, config: &JobsConfig) {
+    if !config.enabled {
+        log::info!("Background jobs are disabled");
+        return;
+    }
+
+    for (job, period) in registered_jobs(config) {
+        let pool = pool.clone();
+        log::info!("Scheduling job {} every {}s", job.name(), period.as_secs());
+
+        tokio::spawn(async move {
+            let mut ticker = interval_at(Instant::now() + period, period);
+            // Если запуск затянулся, пропущенные срабатывания не догоняем
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            loop {
+                ticker.tick().await;
+
+                let run = {
+                    let job = job.clone();
+                    let pool = pool.clone();
+                    tokio::spawn(async move { job.run(&pool).await })
+                };
+                match run.await {
+                    Ok(Ok(summary)) => log::info!("Job {} finished: {}", job.name(), summary),
+                    Ok(Err(e)) => log::error!("Job {} failed: {}", job.name(), e),
+                    Err(e) => log::error!("Job {} panicked: {}", job.name(), e),
+                }
+            }
+        });
+    }
+}
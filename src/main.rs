@@ -3,43 +3,60 @@ mod config;
 mod database;
 mod repositories;
 mod handlers;
+mod middleware;
+mod jobs;
 
+use actix_cors::Cors;
 use actix_web::{get, web, App, HttpServer, Responder, HttpResponse};
+use actix_web::http::header::{self, CacheControl, CacheDirective};
+use actix_web::middleware::{from_fn, DefaultHeaders};
 use config::Config;
-use database::create_db_pool;
+use database::create_db_pool_with_retry;
 
 use handlers::{
     car_handlers::{
         get_cars_handler, get_car_by_id_handler, get_cars_by_status_handler,
-        create_car_handler, update_car_handler, delete_car_handler, update_car_status_handler,
+        create_car_handler, bulk_import_cars_handler, duplicate_car_handler, get_car_facets_handler, batch_get_cars_handler,
+        update_car_handler, delete_car_handler, update_car_status_handler,
         get_car_by_vin_handler,
         add_completed_campaign_handler, remove_completed_campaign_handler,
         clear_completed_campaigns_handler, get_pending_campaigns_handler,
-        get_cars_by_completed_campaign_handler
+        get_cars_by_completed_campaign_handler, get_most_requested_cars_handler,
+        get_car_changes_handler, get_recent_cars_handler, export_cars_handler,
+        get_car_applicable_works_handler, get_car_parts_coverage_handler, add_car_feature_handler, remove_car_feature_handler,
+        get_car_timeline_handler, get_car_detail_handler, restore_car_handler
     },
     customer_handlers::{
         get_customers_handler, get_customer_by_id_handler,
-        create_customer_handler, update_customer_handler, delete_customer_handler
+        create_customer_handler, update_customer_handler, delete_customer_handler,
+        get_customer_pending_campaigns_handler, export_customer_data_handler, anonymize_customer_handler,
+        get_top_customers_handler
     },
     purchase_handlers::{
         get_purchases_handler, get_purchase_by_id_handler,
-        get_purchases_by_customer_handler, get_purchases_by_car_handler,
-        create_purchase_handler, update_purchase_status_handler, delete_purchase_handler
+        get_purchases_by_customer_handler, get_purchases_by_car_handler, get_purchases_by_status_handler,
+        create_purchase_handler, update_purchase_status_handler, delete_purchase_handler,
+        get_stale_purchases_handler, get_oldest_pending_per_car_handler, batch_update_purchase_status_handler,
+        approve_and_sell_handler
     },
     part_handlers::{
         get_parts_handler, get_part_by_id_handler, get_part_by_article_handler,
-        get_parts_by_brand_handler, get_parts_by_car_model_handler, get_parts_by_vin_handler,
-        create_part_handler, update_part_handler, delete_part_handler
+        get_parts_by_brand_handler, get_parts_by_car_model_handler, get_parts_by_vin_handler, get_parts_by_name_handler, get_part_margins_handler,
+        create_part_handler, update_part_handler, delete_part_handler, batch_delete_parts_handler,
+        get_part_compatible_models_handler, get_part_campaign_demand_handler, add_compatible_vins_handler, get_cross_compatible_parts_handler,
+        get_parts_catalog_handler
     },
     brand_handlers::{
         get_brands_handler, get_brand_by_id_handler, get_brand_by_name_handler,
-        get_brands_by_country_handler, create_brand_handler, update_brand_handler,
-        delete_brand_handler
+        get_brands_by_country_handler, get_brand_countries_handler, get_brand_price_floor_handler,
+        set_brand_price_floor_handler, get_brand_stock_defaults_handler, set_brand_stock_defaults_handler,
+        create_brand_handler, update_brand_handler,
+        delete_brand_handler, get_brand_dependents_handler, get_brand_models_with_counts_handler
     },
     car_model_handlers::{
         get_car_models_handler, get_car_model_by_id_handler, get_car_models_by_brand_handler,
         get_car_models_by_name_handler, create_car_model_handler, update_car_model_handler,
-        delete_car_model_handler
+        delete_car_model_handler, get_car_model_low_stock_parts_handler
     },
     work_handlers::{
         get_works_handler, get_work_by_id_handler, get_work_by_article_handler,
@@ -54,15 +71,26 @@ use handlers::{
         get_service_campaigns_by_vin_handler, create_service_campaign_handler,
         update_service_campaign_handler, delete_service_campaign_handler,
         update_service_campaign_status_handler, mark_service_campaign_completed_handler,
-        mark_service_campaign_pending_handler
+        mark_service_campaign_pending_handler, validate_campaign_vins_handler,
+        reactivate_service_campaign_handler, bulk_import_service_campaigns_handler,
+        recalculate_campaign_completion_handler, get_targeted_vins_handler,
+        get_campaign_target_cars_handler
     },
     warehouse_handler::{
         get_warehouse_items_handler, get_low_stock_items_handler, get_warehouse_item_by_id_handler,
         get_warehouse_item_by_part_id_handler, get_warehouse_item_by_article_handler,
         get_warehouse_items_by_location_handler, create_warehouse_item_handler,
-        update_warehouse_item_handler, delete_warehouse_item_handler, update_stock_handler,
-        get_total_inventory_value_handler
-    }
+        update_warehouse_item_handler, delete_warehouse_item_handler, update_stock_handler, get_stock_movements_handler,
+        get_total_inventory_value_handler, get_reorder_cost_handler, get_stock_forecast_handler,
+        get_warehouse_stats_handler,
+        create_inventory_snapshot_handler, get_inventory_snapshot_handler, compare_inventory_snapshots_handler,
+        get_warehouse_integrity_check_handler, get_warehouse_orphans_handler,
+        delete_warehouse_orphans_handler, update_warehouse_location_handler, reserve_parts_handler
+    },
+    report_handlers::{get_avg_price_by_model_handler, get_sale_margins_handler, get_avg_days_to_sale_handler},
+    meta_handlers::{get_enums_handler, get_resource_schema_handler},
+    search_handlers::search_handler,
+    common::{path_error_handler, query_error_handler}
 };
 #[get("/")]
 async fn hello() -> impl Responder {
@@ -79,30 +107,66 @@ async fn health_check() -> impl Responder {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::init();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
 
     println!("🔧 Loading configuration...");
     let config = Config::from_env().expect("Failed to load configuration");
 
-    println!("🗄️ Connecting to database...");
-    let db_pool = create_db_pool(&config.database.url).await
-        .expect("Failed to connect to database");
+    let db_pool = match create_db_pool_with_retry(&config.database).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            log::error!(
+                "❌ Failed to connect to database after {} attempts: {}",
+                config.database.connect_retries + 1,
+                e
+            );
+            std::process::exit(1);
+        }
+    };
 
     println!("✅ Database connected successfully!");
     println!("🚀 Starting AutoDealer API on http://{}:{}", config.server.host, config.server.port);
 
+    jobs::spawn_jobs(db_pool.clone(), &config.jobs);
+
+    let app_config = web::Data::new(config.clone());
+
     HttpServer::new(move || {
+        // Без разрешённых источников Cors::default() отклоняет все кросс-доменные запросы
+        let cors = app_config.server.allowed_origins.iter()
+            .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+            .allow_any_method()
+            .allow_any_header()
+            .expose_headers([header::LOCATION, header::ETAG, middleware::REQUEST_ID_HEADER])
+            .max_age(3600);
+
         App::new()
             .app_data(web::Data::new(db_pool.clone()))
+            .app_data(app_config.clone())
+            .app_data(web::PathConfig::default().error_handler(path_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .wrap(from_fn(middleware::request_id_middleware))
+            .wrap(cors)
             // Базовые routes
             .service(hello)
             .service(health_check)
             // Car API routes
             .service(
                 web::scope("/api/cars")
+                    // Данные меняются постоянно: ответы не кешируются
+                    .wrap(DefaultHeaders::new().add(CacheControl(vec![CacheDirective::NoStore])))
                     .route("", web::get().to(get_cars_handler))
                     .route("", web::post().to(create_car_handler))
+                    .route("/most-requested", web::get().to(get_most_requested_cars_handler))
+                    .route("/changes", web::get().to(get_car_changes_handler))
+                    .route("/recent", web::get().to(get_recent_cars_handler))
+                    .route("/export.json", web::get().to(export_cars_handler))
+                    .route("/facets", web::get().to(get_car_facets_handler))
+                    .route("/batch-get", web::post().to(batch_get_cars_handler))
+                    .route("/bulk", web::post().to(bulk_import_cars_handler))
                     .route("/{id}", web::get().to(get_car_by_id_handler))
+                    .route("/{id}/detail", web::get().to(get_car_detail_handler))
+                    .route("/{id}/restore", web::post().to(restore_car_handler))
                     .route("/{id}", web::put().to(update_car_handler))
                     .route("/{id}", web::delete().to(delete_car_handler))
                     .route("/status/{status}", web::get().to(get_cars_by_status_handler))
@@ -112,7 +176,13 @@ async fn main() -> std::io::Result<()> {
                     .route("/{car_id}/completed-campaigns/{campaign_id}", web::patch().to(add_completed_campaign_handler))
                     .route("/{car_id}/completed-campaigns/{campaign_id}", web::delete().to(remove_completed_campaign_handler))
                     .route("/{car_id}/completed-campaigns", web::delete().to(clear_completed_campaigns_handler))
+                    .route("/{car_id}/features/{feature}", web::patch().to(add_car_feature_handler))
+                    .route("/{car_id}/features/{feature}", web::delete().to(remove_car_feature_handler))
                     .route("/{car_id}/pending-campaigns", web::get().to(get_pending_campaigns_handler))
+                    .route("/{id}/applicable-works", web::get().to(get_car_applicable_works_handler))
+                    .route("/{id}/parts-coverage", web::get().to(get_car_parts_coverage_handler))
+                    .route("/{id}/timeline", web::get().to(get_car_timeline_handler))
+                    .route("/{id}/duplicate", web::post().to(duplicate_car_handler))
                     .route("/completed-campaign/{campaign_id}", web::get().to(get_cars_by_completed_campaign_handler))
             )
             // Customer API routes
@@ -120,18 +190,27 @@ async fn main() -> std::io::Result<()> {
                 web::scope("/api/customers")
                     .route("", web::get().to(get_customers_handler))
                     .route("", web::post().to(create_customer_handler))
+                    .route("/top", web::get().to(get_top_customers_handler))
                     .route("/{id}", web::get().to(get_customer_by_id_handler))
                     .route("/{id}", web::put().to(update_customer_handler))
                     .route("/{id}", web::delete().to(delete_customer_handler))
+                    .route("/{id}/pending-campaigns", web::get().to(get_customer_pending_campaigns_handler))
+                    .route("/{id}/export", web::get().to(export_customer_data_handler))
+                    .route("/{id}/anonymize", web::post().to(anonymize_customer_handler))
             )
             // Purchase API routes
             .service(
                 web::scope("/api/purchases")
                     .route("", web::get().to(get_purchases_handler))
                     .route("", web::post().to(create_purchase_handler))
+                    .route("/stale", web::get().to(get_stale_purchases_handler))
+                    .route("/oldest-pending-per-car", web::get().to(get_oldest_pending_per_car_handler))
+                    .route("/status/batch", web::post().to(batch_update_purchase_status_handler))
+                    .route("/status/{status}", web::get().to(get_purchases_by_status_handler))
                     .route("/{id}", web::get().to(get_purchase_by_id_handler))
                     .route("/{id}", web::delete().to(delete_purchase_handler))
                     .route("/{id}/status", web::patch().to(update_purchase_status_handler))
+                    .route("/{id}/approve-and-sell", web::post().to(approve_and_sell_handler))
                     .route("/customer/{customer_id}", web::get().to(get_purchases_by_customer_handler))
                     .route("/car/{car_id}", web::get().to(get_purchases_by_car_handler))
             )
@@ -140,6 +219,11 @@ async fn main() -> std::io::Result<()> {
                 web::scope("/api/parts")
                     .route("", web::get().to(get_parts_handler))
                     .route("", web::post().to(create_part_handler))
+                    .route("/batch-delete", web::post().to(batch_delete_parts_handler))
+                    .route("/add-compatible-vins", web::post().to(add_compatible_vins_handler))
+                    .route("/cross-compatible", web::get().to(get_cross_compatible_parts_handler))
+                    .route("/catalog", web::get().to(get_parts_catalog_handler))
+                    .route("/margins", web::get().to(get_part_margins_handler))
                     .route("/{id}", web::get().to(get_part_by_id_handler))
                     .route("/{id}", web::put().to(update_part_handler))
                     .route("/{id}", web::delete().to(delete_part_handler))
@@ -147,15 +231,25 @@ async fn main() -> std::io::Result<()> {
                     .route("/brand/{brand_id}", web::get().to(get_parts_by_brand_handler))
                     .route("/car-model/{car_model_id}", web::get().to(get_parts_by_car_model_handler))
                     .route("/vin/{vin}", web::get().to(get_parts_by_vin_handler))
+                    .route("/name/{name}", web::get().to(get_parts_by_name_handler))
+                    .route("/{id}/compatible-models", web::get().to(get_part_compatible_models_handler))
+                    .route("/{id}/campaign-demand", web::get().to(get_part_campaign_demand_handler))
             )
             // Brands API routes
             .service(
                 web::scope("/api/brands")
                     .route("", web::get().to(get_brands_handler))
                     .route("", web::post().to(create_brand_handler))
+                    .route("/countries", web::get().to(get_brand_countries_handler))
                     .route("/{id}", web::get().to(get_brand_by_id_handler))
                     .route("/{id}", web::put().to(update_brand_handler))
                     .route("/{id}", web::delete().to(delete_brand_handler))
+                    .route("/{id}/dependents", web::get().to(get_brand_dependents_handler))
+                    .route("/{id}/models-with-counts", web::get().to(get_brand_models_with_counts_handler))
+                    .route("/{id}/price-floor", web::get().to(get_brand_price_floor_handler))
+                    .route("/{id}/price-floor", web::put().to(set_brand_price_floor_handler))
+                    .route("/{id}/stock-defaults", web::get().to(get_brand_stock_defaults_handler))
+                    .route("/{id}/stock-defaults", web::put().to(set_brand_stock_defaults_handler))
                     .route("/name/{name}", web::get().to(get_brand_by_name_handler))
                     .route("/country/{country}", web::get().to(get_brands_by_country_handler))
             )
@@ -169,6 +263,7 @@ async fn main() -> std::io::Result<()> {
                     .route("/{id}", web::delete().to(delete_car_model_handler))
                     .route("/brand/{brand_id}", web::get().to(get_car_models_by_brand_handler))
                     .route("/name/{name}", web::get().to(get_car_models_by_name_handler))
+                    .route("/{id}/low-stock-parts", web::get().to(get_car_model_low_stock_parts_handler))
             )
             // Works API routes
             .service(
@@ -188,6 +283,9 @@ async fn main() -> std::io::Result<()> {
                 web::scope("/api/service-campaigns")
                     .route("", web::get().to(get_service_campaigns_handler))
                     .route("", web::post().to(create_service_campaign_handler))
+                    .route("/validate-vins", web::post().to(validate_campaign_vins_handler))
+                    .route("/targeted-vins", web::get().to(get_targeted_vins_handler))
+                    .route("/bulk", web::post().to(bulk_import_service_campaigns_handler))
                     .route("/{id}", web::get().to(get_service_campaign_by_id_handler))
                     .route("/{id}", web::put().to(update_service_campaign_handler))
                     .route("/{id}", web::delete().to(delete_service_campaign_handler))
@@ -201,22 +299,54 @@ async fn main() -> std::io::Result<()> {
                     .route("/{id}/status", web::patch().to(update_service_campaign_status_handler))
                     .route("/{id}/complete", web::patch().to(mark_service_campaign_completed_handler))
                     .route("/{id}/pending", web::patch().to(mark_service_campaign_pending_handler))
+                    .route("/{id}/reactivate", web::patch().to(reactivate_service_campaign_handler))
+                    .route("/{id}/recalculate-completion", web::post().to(recalculate_campaign_completion_handler))
+                    .route("/{id}/target-cars", web::get().to(get_campaign_target_cars_handler))
             )
             // Warehouse API routes
             .service(
                 web::scope("/api/warehouse")
+                    // Данные меняются постоянно: ответы не кешируются
+                    .wrap(DefaultHeaders::new().add(CacheControl(vec![CacheDirective::NoStore])))
                     .route("", web::get().to(get_warehouse_items_handler))
                     .route("", web::post().to(create_warehouse_item_handler))
                     .route("/low-stock", web::get().to(get_low_stock_items_handler))
                     .route("/total-value", web::get().to(get_total_inventory_value_handler))
+                    .route("/reorder-cost", web::get().to(get_reorder_cost_handler))
+                    .route("/stats", web::get().to(get_warehouse_stats_handler))
+                    .route("/integrity-check", web::get().to(get_warehouse_integrity_check_handler))
+                    .route("/orphans", web::get().to(get_warehouse_orphans_handler))
+                    .route("/orphans", web::delete().to(delete_warehouse_orphans_handler))
+                    .route("/snapshots", web::post().to(create_inventory_snapshot_handler))
+                    .route("/snapshots/compare", web::get().to(compare_inventory_snapshots_handler))
+                    .route("/snapshots/{date}", web::get().to(get_inventory_snapshot_handler))
+                    .route("/reserve", web::post().to(reserve_parts_handler))
                     .route("/{id}", web::get().to(get_warehouse_item_by_id_handler))
                     .route("/{id}", web::put().to(update_warehouse_item_handler))
                     .route("/{id}", web::delete().to(delete_warehouse_item_handler))
+                    .route("/{id}/location", web::patch().to(update_warehouse_location_handler))
                     .route("/part/{part_id}", web::get().to(get_warehouse_item_by_part_id_handler))
                     .route("/article/{article}", web::get().to(get_warehouse_item_by_article_handler))
                     .route("/location/{location}", web::get().to(get_warehouse_items_by_location_handler))
                     .route("/{part_id}/stock", web::put().to(update_stock_handler))
+                    .route("/{part_id}/movements", web::get().to(get_stock_movements_handler))
+                    .route("/{part_id}/forecast", web::get().to(get_stock_forecast_handler))
+            )
+            // Reports API routes
+            .service(
+                web::scope("/api/reports")
+                    .route("/avg-price-by-model", web::get().to(get_avg_price_by_model_handler))
+                    .route("/sale-margins", web::get().to(get_sale_margins_handler))
+                    .route("/avg-days-to-sale", web::get().to(get_avg_days_to_sale_handler))
+            )
+            // Meta API routes
+            .service(
+                web::scope("/api/meta")
+                    .route("/enums", web::get().to(get_enums_handler))
+                    .route("/schema/{resource}", web::get().to(get_resource_schema_handler))
             )
+            // Search API routes
+            .route("/api/search", web::get().to(search_handler))
     })
         .bind((config.server.host.as_str(), config.server.port))?
         .run()
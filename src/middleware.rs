@@ -0,0 +1,133 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    web, Error,
+};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// Ограничение длины входящего идентификатора, чтобы не тянуть в логи произвольные данные
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+// Входящий X-Request-Id используется, если ему разрешено доверять и он корректен; иначе генерируется новый
+pub fn resolve_request_id(inbound: Option<&str>, trust_inbound: bool) -> String {
+    inbound
+        .filter(|_| trust_inbound)
+        .map(str::trim)
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LENGTH
+                && id.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+pub async fn request_id_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let trust_inbound = req
+        .app_data::<web::Data<Config>>()
+        .map(|config| config.server.trust_request_id)
+        .unwrap_or(true);
+    let inbound = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let request_id = resolve_request_id(inbound, trust_inbound);
+
+    let method = req.method().clone();
+    let path = req.path().to_string();
+
+    let mut res = next.call(req).await?;
+
+    log::info!("[{}] {} {} -> {}", request_id, method, path, res.status().as_u16());
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware::from_fn, App, HttpResponse};
+
+    fn is_generated(id: &str) -> bool {
+        Uuid::parse_str(id).is_ok()
+    }
+
+    #[test]
+    fn resolve_request_id_keeps_trusted_inbound_id() {
+        assert_eq!(resolve_request_id(Some("req-42"), true), "req-42");
+        assert_eq!(resolve_request_id(Some("  req-42  "), true), "req-42");
+    }
+
+    #[test]
+    fn resolve_request_id_replaces_untrusted_inbound_id() {
+        let id = resolve_request_id(Some("req-42"), false);
+        assert_ne!(id, "req-42");
+        assert!(is_generated(&id));
+    }
+
+    #[test]
+    fn resolve_request_id_replaces_too_long_id() {
+        let max = "a".repeat(MAX_REQUEST_ID_LENGTH);
+        assert_eq!(resolve_request_id(Some(&max), true), max);
+
+        let too_long = "a".repeat(MAX_REQUEST_ID_LENGTH + 1);
+        assert!(is_generated(&resolve_request_id(Some(&too_long), true)));
+    }
+
+    #[test]
+    fn resolve_request_id_replaces_non_graphic_characters() {
+        assert!(is_generated(&resolve_request_id(Some("req 42"), true)));
+        assert!(is_generated(&resolve_request_id(Some("req\t42"), true)));
+        assert!(is_generated(&resolve_request_id(Some("запрос-42"), true)));
+    }
+
+    #[test]
+    fn resolve_request_id_generates_when_missing_or_empty() {
+        assert!(is_generated(&resolve_request_id(None, true)));
+        assert!(is_generated(&resolve_request_id(Some("   "), true)));
+    }
+
+    async fn call_with_request_id(trust_request_id: bool, inbound: Option<&str>) -> Option<String> {
+        let mut config = Config::from_env().expect("test configuration");
+        config.server.trust_request_id = trust_request_id;
+        let app = actix_web::test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .wrap(from_fn(request_id_middleware))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        ).await;
+        let mut req = actix_web::test::TestRequest::get().uri("/");
+        if let Some(inbound) = inbound {
+            req = req.insert_header((REQUEST_ID_HEADER, inbound));
+        }
+
+        let resp = actix_web::test::call_service(&app, req.to_request()).await;
+        resp.headers().get(REQUEST_ID_HEADER).map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[actix_web::test]
+    async fn middleware_echoes_trusted_request_id() {
+        assert_eq!(call_with_request_id(true, Some("req-42")).await.as_deref(), Some("req-42"));
+    }
+
+    #[actix_web::test]
+    async fn middleware_sets_generated_request_id() {
+        let missing = call_with_request_id(true, None).await.unwrap();
+        assert!(is_generated(&missing));
+
+        let untrusted = call_with_request_id(false, Some("req-42")).await.unwrap();
+        assert!(is_generated(&untrusted));
+    }
+}
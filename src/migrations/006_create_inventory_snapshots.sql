@@ -0,0 +1,17 @@
+-- Снимки складских остатков на дату (для сравнения стоимости запасов между периодами)
+CREATE TABLE IF NOT EXISTS inventory_snapshots (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    snapshot_date DATE NOT NULL,
+    part_id UUID NOT NULL,
+    part_article VARCHAR(100) NOT NULL,
+    part_name VARCHAR(200) NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_cost FLOAT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (snapshot_date, part_id)
+);
+
+-- part_id без внешнего ключа: снимок должен переживать удаление запчасти
+CREATE INDEX IF NOT EXISTS idx_inventory_snapshots_date ON inventory_snapshots(snapshot_date);
+
+COMMENT ON COLUMN inventory_snapshots.unit_cost IS 'Закупочная цена запчасти на момент снимка';
@@ -0,0 +1,14 @@
+-- Журнал складских движений (перемещения между местами хранения и т.п.)
+CREATE TABLE IF NOT EXISTS stock_movements (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    warehouse_item_id UUID NOT NULL REFERENCES warehouse(id) ON DELETE CASCADE,
+    part_id UUID NOT NULL,
+    movement_type VARCHAR(20) NOT NULL CHECK (movement_type IN ('incoming', 'outgoing', 'adjustment', 'relocation')),
+    quantity INTEGER NOT NULL DEFAULT 0,
+    from_location VARCHAR(100),
+    to_location VARCHAR(100),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_item ON stock_movements(warehouse_item_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_stock_movements_part ON stock_movements(part_id);
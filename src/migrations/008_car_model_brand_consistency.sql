@@ -0,0 +1,11 @@
+-- Модель автомобиля должна принадлежать бренду автомобиля
+ALTER TABLE car_models
+    ADD CONSTRAINT car_models_id_brand_id_key UNIQUE (id, brand_id);
+
+-- NOT VALID: существующие строки не проверяются, ограничение действует для новых вставок и обновлений.
+-- После исправления старых данных: ALTER TABLE cars VALIDATE CONSTRAINT cars_model_brand_fkey;
+ALTER TABLE cars
+    ADD CONSTRAINT cars_model_brand_fkey
+    FOREIGN KEY (model_id, brand_id) REFERENCES car_models(id, brand_id)
+    ON UPDATE CASCADE
+    NOT VALID;
@@ -0,0 +1,6 @@
+ALTER TABLE cars ADD COLUMN features TEXT[] DEFAULT '{}' NOT NULL;
+
+-- Индекс для фильтрации по опциям
+CREATE INDEX idx_cars_features ON cars USING GIN (features);
+
+COMMENT ON COLUMN cars.features IS 'Опции и комплектация автомобиля (нормализованные: нижний регистр, пробелы заменены на _)';
@@ -0,0 +1,5 @@
+-- Основание складского движения: ссылка на документ (заказ-наряд и т.п.) и причина
+ALTER TABLE stock_movements ADD COLUMN IF NOT EXISTS reference VARCHAR(100);
+ALTER TABLE stock_movements ADD COLUMN IF NOT EXISTS reason VARCHAR(255);
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_reference ON stock_movements(reference) WHERE reference IS NOT NULL;
@@ -0,0 +1,42 @@
+-- История изменений автомобиля: цена, статус, выполненные сервисные кампании.
+-- Заполняется триггером, поэтому фиксируются изменения из любых мест (API, фоновые задачи, ручные правки).
+CREATE TABLE IF NOT EXISTS car_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    car_id UUID NOT NULL REFERENCES cars(id) ON DELETE CASCADE,
+    event_type VARCHAR(30) NOT NULL CHECK (event_type IN ('price_changed', 'status_changed', 'campaign_completed')),
+    old_price DOUBLE PRECISION,
+    new_price DOUBLE PRECISION,
+    old_status VARCHAR(20),
+    new_status VARCHAR(20),
+    campaign_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_car_history_car ON car_history(car_id, created_at);
+
+CREATE OR REPLACE FUNCTION record_car_history() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.price IS DISTINCT FROM OLD.price THEN
+        INSERT INTO car_history (car_id, event_type, old_price, new_price)
+        VALUES (NEW.id, 'price_changed', OLD.price, NEW.price);
+    END IF;
+
+    IF NEW.status IS DISTINCT FROM OLD.status THEN
+        INSERT INTO car_history (car_id, event_type, old_status, new_status)
+        VALUES (NEW.id, 'status_changed', OLD.status, NEW.status);
+    END IF;
+
+    INSERT INTO car_history (car_id, event_type, campaign_id)
+    SELECT NEW.id, 'campaign_completed', added.campaign_id
+    FROM unnest(NEW.completed_service_campaigns) AS added(campaign_id)
+    WHERE NOT added.campaign_id = ANY(OLD.completed_service_campaigns);
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_car_history ON cars;
+CREATE TRIGGER trg_car_history
+    AFTER UPDATE ON cars
+    FOR EACH ROW
+    EXECUTE FUNCTION record_car_history();
@@ -0,0 +1,8 @@
+-- Минимальные цены продажи по брендам: премиальные марки нельзя выставлять ниже порога.
+-- Пороги для автомобилей и запчастей задаются отдельно, NULL - без ограничения.
+CREATE TABLE IF NOT EXISTS brand_price_floors (
+    brand_id UUID PRIMARY KEY REFERENCES brands(id) ON DELETE CASCADE,
+    min_car_price DOUBLE PRECISION CHECK (min_car_price >= 0),
+    min_part_price DOUBLE PRECISION CHECK (min_part_price >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,9 @@
+-- Уровни запаса по умолчанию для запчастей бренда: подставляются при создании складской
+-- позиции без явных min/max. NULL - используется общий уровень по умолчанию (0 и 100).
+CREATE TABLE IF NOT EXISTS brand_stock_defaults (
+    brand_id UUID PRIMARY KEY REFERENCES brands(id) ON DELETE CASCADE,
+    min_stock_level INTEGER CHECK (min_stock_level >= 0),
+    max_stock_level INTEGER CHECK (max_stock_level >= 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (min_stock_level IS NULL OR max_stock_level IS NULL OR min_stock_level <= max_stock_level)
+);
@@ -0,0 +1,8 @@
+-- Артикулы уникальны без учёта регистра: "ABC-1" и "abc-1" - один и тот же артикул.
+-- Индексы по LOWER(article) также используются поиском по артикулу (WHERE LOWER(article) = LOWER($1)).
+-- Если в таблице уже есть артикулы, отличающиеся только регистром, создание индекса завершится ошибкой;
+-- найти их перед миграцией:
+--   SELECT LOWER(article), array_agg(article) FROM parts GROUP BY LOWER(article) HAVING COUNT(*) > 1;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_parts_article_lower ON parts (LOWER(article));
+CREATE UNIQUE INDEX IF NOT EXISTS idx_works_article_lower ON works (LOWER(article));
+CREATE UNIQUE INDEX IF NOT EXISTS idx_service_campaigns_article_lower ON service_campaigns (LOWER(article));
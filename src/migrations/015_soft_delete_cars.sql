@@ -0,0 +1,3 @@
+-- Мягкое удаление автомобилей: строка остаётся (заявки на покупку и история не теряются),
+-- репозиторий скрывает автомобили с заполненным deleted_at. VIN удалённого автомобиля остаётся занятым.
+ALTER TABLE cars ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
@@ -0,0 +1,4 @@
+-- Остаток позиции после движения; у записей, сделанных до миграции, остаётся NULL
+ALTER TABLE stock_movements ADD COLUMN IF NOT EXISTS resulting_quantity INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_part_created ON stock_movements(part_id, created_at DESC);
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use super::country::normalize_country;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct Brand {
@@ -17,11 +19,99 @@ pub struct Brand {
 pub struct CreateBrandRequest {
     #[validate(length(min = 1))]
     pub name: String,
+    #[validate(custom = "validate_country")]
     pub country: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct UpdateBrandRequest {
     pub name: Option<String>,
+    #[validate(custom = "validate_country")]
     pub country: Option<String>,
-}
\ No newline at end of file
+}
+
+/// Страна бренда должна быть кодом или названием страны по ISO 3166-1
+fn validate_country(country: &str) -> Result<(), ValidationError> {
+    if normalize_country(country).is_none() {
+        let mut error = ValidationError::new("country");
+        error.message = Some("Неизвестная страна: укажите код ISO 3166-1 или название".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Режим поиска брендов по стране: точное совпадение (по умолчанию) или подстрока
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountryMatch {
+    #[default]
+    Exact,
+    Contains,
+}
+
+/// Страна и количество брендов из неё (для фильтра в интерфейсе)
+#[derive(Debug, Serialize, Clone)]
+pub struct BrandCountryCount {
+    pub country: String,
+    pub code: Option<String>,
+    pub brand_count: i64,
+}
+
+/// Минимальные цены продажи бренда; `None` - порог не задан
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BrandPriceFloor {
+    pub brand_id: Uuid,
+    pub min_car_price: Option<f64>,
+    pub min_part_price: Option<f64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SetBrandPriceFloorRequest {
+    #[validate(range(min = 0.0))]
+    pub min_car_price: Option<f64>,
+    #[validate(range(min = 0.0))]
+    pub min_part_price: Option<f64>,
+}
+
+/// Уровни запаса по умолчанию для запчастей бренда; `None` - общий уровень по умолчанию
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BrandStockDefaults {
+    pub brand_id: Uuid,
+    pub min_stock_level: Option<i32>,
+    pub max_stock_level: Option<i32>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SetBrandStockDefaultsRequest {
+    #[validate(range(min = 0))]
+    pub min_stock_level: Option<i32>,
+    #[validate(range(min = 0))]
+    pub max_stock_level: Option<i32>,
+}
+
+impl SetBrandStockDefaultsRequest {
+    pub fn levels_consistent(&self) -> bool {
+        match (self.min_stock_level, self.max_stock_level) {
+            (Some(min), Some(max)) => min <= max,
+            _ => true,
+        }
+    }
+}
+
+/// Что продаётся: для автомобилей и запчастей пороги разные
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PricedItem {
+    Car,
+    Part,
+}
+
+impl BrandPriceFloor {
+    pub fn min_price(&self, item: PricedItem) -> Option<f64> {
+        match item {
+            PricedItem::Car => self.min_car_price,
+            PricedItem::Part => self.min_part_price,
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-use super::enums::{FuelType, Transmission, CarStatus};
+use super::csv::CsvRecord;
+use super::enums::{EnumVariants, FuelType, Transmission, CarStatus};
+use super::money::{round_money, serialize_money, serialize_optional_money};
+use super::ArrayFields;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct Car {
@@ -12,19 +15,47 @@ pub struct Car {
     pub model_id: Uuid,
     pub year: i32,
     #[validate(range(min = 0.0))]
+    #[serde(serialize_with = "serialize_money")]
     pub price: f64,
     pub mileage: i32,
     pub color: String,
-    #[validate(length(min = 17, max = 17, message = "VIN код должен содержать 17 символов"))]
+    #[validate(custom = "validate_vin")]
     pub vin: String,
     pub fuel_type: FuelType,
     pub transmission: Transmission,
     pub status: CarStatus,
     pub completed_service_campaigns: Vec<Uuid>, // ← ДОБАВЛЯЕМ
+    pub features: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+// Списки (выполненные кампании, опции) выгружаются одним полем через `;`
+impl CsvRecord for Car {
+    const CSV_HEADER: &'static str = "id,brand_id,model_id,year,price,mileage,color,vin,fuel_type,transmission,status,\
+        completed_service_campaigns,features,created_at,updated_at";
+
+    fn csv_fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.brand_id.to_string(),
+            self.model_id.to_string(),
+            self.year.to_string(),
+            round_money(self.price).to_string(),
+            self.mileage.to_string(),
+            self.color.clone(),
+            self.vin.clone(),
+            self.fuel_type.value().to_string(),
+            self.transmission.value().to_string(),
+            self.status.value().to_string(),
+            self.completed_service_campaigns.iter().map(Uuid::to_string).collect::<Vec<_>>().join(";"),
+            self.features.join(";"),
+            self.created_at.to_rfc3339(),
+            self.updated_at.to_rfc3339(),
+        ]
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateCarRequest {
     pub brand_id: Uuid,
@@ -35,7 +66,7 @@ pub struct CreateCarRequest {
     pub price: f64,
     pub mileage: i32,
     pub color: String,
-    #[validate(length(min = 17, max = 17))]
+    #[validate(custom = "validate_vin")]
     pub vin: String,
     pub fuel_type: FuelType,
     pub transmission: Transmission,
@@ -51,10 +82,288 @@ pub struct UpdateCarRequest {
     pub price: Option<f64>,
     pub mileage: Option<i32>,
     pub color: Option<String>,
-    #[validate(length(min = 17, max = 17))]
+    #[validate(custom = "validate_vin")]
     pub vin: Option<String>,
     pub fuel_type: Option<FuelType>,
     pub transmission: Option<Transmission>,
     pub status: Option<CarStatus>,
     pub completed_service_campaigns: Option<Vec<Uuid>>,
-}
\ No newline at end of file
+}
+
+impl ArrayFields for UpdateCarRequest {
+    fn array_fields(&self) -> Vec<(&'static str, usize)> {
+        vec![("completed_service_campaigns", self.completed_service_campaigns.as_ref().map_or(0, Vec::len))]
+    }
+}
+
+/// Запрос на создание копии автомобиля: характеристики берутся из исходного,
+/// VIN и пробег задаются заново.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct DuplicateCarRequest {
+    pub vin: String,
+    #[validate(range(min = 0))]
+    pub mileage: Option<i32>,
+}
+
+/// Размер пакета проверяется в обработчике по MAX_BATCH_SIZE
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchGetCarsRequest {
+    pub ids: Vec<Uuid>,
+}
+
+/// Автомобили в порядке запроса (без повторов) и ID, для которых автомобиль не найден
+#[derive(Debug, Serialize)]
+pub struct BatchGetCarsResponse {
+    pub items: Vec<Car>,
+    pub not_found: Vec<Uuid>,
+}
+
+/// Итог массового импорта автомобилей. Импорт атомарный: при ошибке ничего не сохраняется,
+/// а ответ - AppError с details.failed_index (с нуля) первой невалидной записи
+#[derive(Debug, Serialize)]
+pub struct BulkCarImportResponse {
+    pub inserted: usize,
+    pub cars: Vec<Car>,
+}
+
+impl BulkCarImportResponse {
+    pub fn inserted(cars: Vec<Car>) -> Self {
+        Self { inserted: cars.len(), cars }
+    }
+}
+
+pub const MAX_FEATURE_LENGTH: usize = 50;
+
+/// Приводит опцию автомобиля к каноническому виду: обрезает пробелы по краям,
+/// переводит в нижний регистр и заменяет внутренние пробелы на `_`.
+/// Возвращает `None`, если опция пустая, слишком длинная или содержит
+/// символы кроме букв, цифр, `_` и `-`.
+pub fn normalize_feature(raw: &str) -> Option<String> {
+    let normalized = raw
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase();
+
+    let valid = !normalized.is_empty()
+        && normalized.chars().count() <= MAX_FEATURE_LENGTH
+        && normalized.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+    valid.then_some(normalized)
+}
+
+/// VIN по ISO 3779: 17 символов, латинские буквы (кроме I, O, Q) и цифры.
+/// Ожидает уже нормализованное значение (без пробелов, в верхнем регистре).
+pub fn is_valid_vin(vin: &str) -> bool {
+    vin.len() == 17
+        && vin.chars().all(|c| c.is_ascii_digit() || (c.is_ascii_uppercase() && !matches!(c, 'I' | 'O' | 'Q')))
+}
+
+/// VIN при создании и изменении автомобиля не нормализуется, поэтому строчные буквы тоже отклоняются
+fn validate_vin(vin: &str) -> Result<(), ValidationError> {
+    if !is_valid_vin(vin) {
+        let mut error = ValidationError::new("vin");
+        error.message = Some("VIN должен содержать 17 символов: цифры и заглавные латинские буквы, кроме I, O и Q".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Позиция в ленте изменений автомобилей: пара (updated_at, id) последней отданной записи.
+/// Клиенту передаётся как непрозрачная строка.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CarChangesCursor {
+    pub updated_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl CarChangesCursor {
+    pub fn from_car(car: &Car) -> Self {
+        Self { updated_at: car.updated_at, id: car.id }
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}.{}", self.updated_at.timestamp_micros(), self.id.simple())
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let (micros, id) = token.split_once('.')?;
+        let updated_at = DateTime::<Utc>::from_timestamp_micros(micros.parse().ok()?)?;
+        let id = Uuid::parse_str(id).ok()?;
+        Some(Self { updated_at, id })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CarChangesResponse {
+    pub items: Vec<CarRecord>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+/// Автомобиль вместе с отметкой мягкого удаления; deleted_at = None - автомобиль не удалён
+#[derive(Debug, Serialize, Clone)]
+pub struct CarRecord {
+    #[serde(flatten)]
+    pub car: Car,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Автомобиль с названиями бренда и модели и страной бренда - для страницы автомобиля за один запрос
+#[derive(Debug, Serialize, Clone)]
+pub struct CarDetail {
+    pub id: Uuid,
+    pub brand_id: Uuid,
+    pub brand_name: String,
+    pub country: String,
+    pub model_id: Uuid,
+    pub model_name: String,
+    pub year: i32,
+    #[serde(serialize_with = "serialize_money")]
+    pub price: f64,
+    pub mileage: i32,
+    pub color: String,
+    pub vin: String,
+    pub fuel_type: FuelType,
+    pub transmission: Transmission,
+    pub status: CarStatus,
+    pub completed_service_campaigns: Vec<Uuid>,
+    pub features: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Строка выгрузки автомобилей: поля автомобиля и названия бренда и модели
+#[derive(Debug, Serialize, Clone)]
+pub struct CarExportRow {
+    pub id: Uuid,
+    pub brand_id: Uuid,
+    pub model_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brand_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_name: Option<String>,
+    pub year: i32,
+    #[serde(serialize_with = "serialize_money")]
+    pub price: f64,
+    pub mileage: i32,
+    pub color: String,
+    pub vin: String,
+    pub fuel_type: FuelType,
+    pub transmission: Transmission,
+    pub status: CarStatus,
+    pub completed_service_campaigns: Vec<Uuid>,
+    pub features: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Значение фасета и количество автомобилей с ним
+#[derive(Debug, Serialize, Clone)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PriceFacet {
+    #[serde(serialize_with = "serialize_money")]
+    pub min: f64,
+    #[serde(serialize_with = "serialize_money")]
+    pub max: f64,
+    pub count: i64,
+}
+
+/// Фасеты для фильтров каталога: только значения, которые реально встречаются среди автомобилей.
+/// Для пустого склада списки пустые, а `price` - null
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct CarFacets {
+    pub total: i64,
+    pub fuel_types: Vec<FacetCount>,
+    pub transmissions: Vec<FacetCount>,
+    pub statuses: Vec<FacetCount>,
+    pub price: Option<PriceFacet>,
+}
+
+/// Событие в истории автомобиля; тип передаётся в поле `type`
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CarTimelineEvent {
+    Created {
+        #[serde(serialize_with = "serialize_money")]
+        price: f64,
+    },
+    PriceChanged {
+        #[serde(serialize_with = "serialize_optional_money")]
+        old_price: Option<f64>,
+        #[serde(serialize_with = "serialize_optional_money")]
+        new_price: Option<f64>,
+    },
+    StatusChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    CampaignCompleted {
+        campaign_id: Option<Uuid>,
+        campaign_name: Option<String>,
+    },
+    OfferReceived {
+        purchase_id: Uuid,
+        customer_id: Uuid,
+        #[serde(serialize_with = "serialize_optional_money")]
+        offer_price: Option<f64>,
+    },
+    Sold {
+        purchase_id: Uuid,
+        customer_id: Uuid,
+        #[serde(serialize_with = "serialize_optional_money")]
+        offer_price: Option<f64>,
+    },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CarTimelineEntry {
+    pub occurred_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: CarTimelineEvent,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_vin_accepts_valid_vins() {
+        assert!(validate_vin("1HGCM82633A004352").is_ok());
+        assert!(validate_vin("WVWZZZ1JZXW000001").is_ok());
+        assert!(validate_vin("00000000000000000").is_ok());
+    }
+
+    #[test]
+    fn validate_vin_rejects_wrong_length() {
+        assert!(validate_vin("").is_err());
+        assert!(validate_vin("1HGCM82633A00435").is_err());
+        assert!(validate_vin("1HGCM82633A0043521").is_err());
+    }
+
+    #[test]
+    fn validate_vin_rejects_forbidden_letters() {
+        assert!(validate_vin("1HGCM82633A00435I").is_err());
+        assert!(validate_vin("1HGCM82633A00435O").is_err());
+        assert!(validate_vin("1HGCM82633A00435Q").is_err());
+    }
+
+    #[test]
+    fn validate_vin_rejects_lowercase_and_other_characters() {
+        assert!(validate_vin("1hgcm82633a004352").is_err());
+        assert!(validate_vin("1HGCM82633A-04352").is_err());
+        assert!(validate_vin("1HGCM82633A 04352").is_err());
+        assert!(validate_vin("1HGCM82633AЖ04352").is_err());
+    }
+
+    #[test]
+    fn validate_vin_error_has_vin_code() {
+        let error = validate_vin("short").unwrap_err();
+        assert_eq!(error.code, "vin");
+    }
+}
@@ -13,6 +13,34 @@ pub struct CarModel {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CarModelWithBrand {
+    pub id: Uuid,
+    pub name: String,
+    pub brand_id: Uuid,
+    pub brand_name: String,
+}
+
+/// Модель вместе с брендом: одноимённые модели разных брендов различимы в поиске
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CarModelDetailed {
+    pub id: Uuid,
+    pub name: String,
+    pub brand_id: Uuid,
+    pub brand_name: String,
+    pub country: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Модель бренда с количеством автомобилей в наличии (для навигации по каталогу)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CarModelWithCount {
+    pub id: Uuid,
+    pub name: String,
+    pub available_cars: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateCarModelRequest {
     #[validate(length(min = 1))]
@@ -0,0 +1,366 @@
+/// Страна по ISO 3166-1. `name` - каноническое название, которое хранится в базе.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Country {
+    pub alpha2: &'static str,
+    pub alpha3: &'static str,
+    pub name: &'static str,
+    pub alt_names: &'static [&'static str],
+}
+
+/// Распространённые названия, которых нет в ISO 3166-1 (в том числе русские), с кодом alpha-2
+const ALIASES: &[(&str, &str)] = &[
+    ("Russia", "RU"),
+    ("UK", "GB"),
+    ("Great Britain", "GB"),
+    ("England", "GB"),
+    ("USA", "US"),
+    ("United States of America", "US"),
+    ("America", "US"),
+    ("Korea", "KR"),
+    ("Czech Republic", "CZ"),
+    ("Россия", "RU"),
+    ("РФ", "RU"),
+    ("Германия", "DE"),
+    ("Япония", "JP"),
+    ("США", "US"),
+    ("Корея", "KR"),
+    ("Южная Корея", "KR"),
+    ("Китай", "CN"),
+    ("Франция", "FR"),
+    ("Италия", "IT"),
+    ("Швеция", "SE"),
+    ("Великобритания", "GB"),
+    ("Англия", "GB"),
+    ("Чехия", "CZ"),
+    ("Испания", "ES"),
+    ("Румыния", "RO"),
+    ("Беларусь", "BY"),
+    ("Белоруссия", "BY"),
+    ("Индия", "IN"),
+    ("Узбекистан", "UZ"),
+];
+
+pub static COUNTRIES: &[Country] = &[
+    Country { alpha2: "AW", alpha3: "ABW", name: "Aruba", alt_names: &[] },
+    Country { alpha2: "AF", alpha3: "AFG", name: "Afghanistan", alt_names: &["Islamic Republic of Afghanistan"] },
+    Country { alpha2: "AO", alpha3: "AGO", name: "Angola", alt_names: &["Republic of Angola"] },
+    Country { alpha2: "AI", alpha3: "AIA", name: "Anguilla", alt_names: &[] },
+    Country { alpha2: "AX", alpha3: "ALA", name: "Åland Islands", alt_names: &[] },
+    Country { alpha2: "AL", alpha3: "ALB", name: "Albania", alt_names: &["Republic of Albania"] },
+    Country { alpha2: "AD", alpha3: "AND", name: "Andorra", alt_names: &["Principality of Andorra"] },
+    Country { alpha2: "AE", alpha3: "ARE", name: "United Arab Emirates", alt_names: &[] },
+    Country { alpha2: "AR", alpha3: "ARG", name: "Argentina", alt_names: &["Argentine Republic"] },
+    Country { alpha2: "AM", alpha3: "ARM", name: "Armenia", alt_names: &["Republic of Armenia"] },
+    Country { alpha2: "AS", alpha3: "ASM", name: "American Samoa", alt_names: &[] },
+    Country { alpha2: "AQ", alpha3: "ATA", name: "Antarctica", alt_names: &[] },
+    Country { alpha2: "TF", alpha3: "ATF", name: "French Southern Territories", alt_names: &[] },
+    Country { alpha2: "AG", alpha3: "ATG", name: "Antigua and Barbuda", alt_names: &[] },
+    Country { alpha2: "AU", alpha3: "AUS", name: "Australia", alt_names: &[] },
+    Country { alpha2: "AT", alpha3: "AUT", name: "Austria", alt_names: &["Republic of Austria"] },
+    Country { alpha2: "AZ", alpha3: "AZE", name: "Azerbaijan", alt_names: &["Republic of Azerbaijan"] },
+    Country { alpha2: "BI", alpha3: "BDI", name: "Burundi", alt_names: &["Republic of Burundi"] },
+    Country { alpha2: "BE", alpha3: "BEL", name: "Belgium", alt_names: &["Kingdom of Belgium"] },
+    Country { alpha2: "BJ", alpha3: "BEN", name: "Benin", alt_names: &["Republic of Benin"] },
+    Country { alpha2: "BQ", alpha3: "BES", name: "Bonaire, Sint Eustatius and Saba", alt_names: &[] },
+    Country { alpha2: "BF", alpha3: "BFA", name: "Burkina Faso", alt_names: &[] },
+    Country { alpha2: "BD", alpha3: "BGD", name: "Bangladesh", alt_names: &["People's Republic of Bangladesh"] },
+    Country { alpha2: "BG", alpha3: "BGR", name: "Bulgaria", alt_names: &["Republic of Bulgaria"] },
+    Country { alpha2: "BH", alpha3: "BHR", name: "Bahrain", alt_names: &["Kingdom of Bahrain"] },
+    Country { alpha2: "BS", alpha3: "BHS", name: "Bahamas", alt_names: &["Commonwealth of the Bahamas"] },
+    Country { alpha2: "BA", alpha3: "BIH", name: "Bosnia and Herzegovina", alt_names: &["Republic of Bosnia and Herzegovina"] },
+    Country { alpha2: "BL", alpha3: "BLM", name: "Saint Barthélemy", alt_names: &[] },
+    Country { alpha2: "BY", alpha3: "BLR", name: "Belarus", alt_names: &["Republic of Belarus"] },
+    Country { alpha2: "BZ", alpha3: "BLZ", name: "Belize", alt_names: &[] },
+    Country { alpha2: "BM", alpha3: "BMU", name: "Bermuda", alt_names: &[] },
+    Country { alpha2: "BO", alpha3: "BOL", name: "Bolivia", alt_names: &["Bolivia, Plurinational State of", "Plurinational State of Bolivia"] },
+    Country { alpha2: "BR", alpha3: "BRA", name: "Brazil", alt_names: &["Federative Republic of Brazil"] },
+    Country { alpha2: "BB", alpha3: "BRB", name: "Barbados", alt_names: &[] },
+    Country { alpha2: "BN", alpha3: "BRN", name: "Brunei Darussalam", alt_names: &[] },
+    Country { alpha2: "BT", alpha3: "BTN", name: "Bhutan", alt_names: &["Kingdom of Bhutan"] },
+    Country { alpha2: "BV", alpha3: "BVT", name: "Bouvet Island", alt_names: &[] },
+    Country { alpha2: "BW", alpha3: "BWA", name: "Botswana", alt_names: &["Republic of Botswana"] },
+    Country { alpha2: "CF", alpha3: "CAF", name: "Central African Republic", alt_names: &[] },
+    Country { alpha2: "CA", alpha3: "CAN", name: "Canada", alt_names: &[] },
+    Country { alpha2: "CC", alpha3: "CCK", name: "Cocos (Keeling) Islands", alt_names: &[] },
+    Country { alpha2: "CH", alpha3: "CHE", name: "Switzerland", alt_names: &["Swiss Confederation"] },
+    Country { alpha2: "CL", alpha3: "CHL", name: "Chile", alt_names: &["Republic of Chile"] },
+    Country { alpha2: "CN", alpha3: "CHN", name: "China", alt_names: &["People's Republic of China"] },
+    Country { alpha2: "CI", alpha3: "CIV", name: "Côte d'Ivoire", alt_names: &["Republic of Côte d'Ivoire"] },
+    Country { alpha2: "CM", alpha3: "CMR", name: "Cameroon", alt_names: &["Republic of Cameroon"] },
+    Country { alpha2: "CD", alpha3: "COD", name: "Congo, The Democratic Republic of the", alt_names: &[] },
+    Country { alpha2: "CG", alpha3: "COG", name: "Congo", alt_names: &["Republic of the Congo"] },
+    Country { alpha2: "CK", alpha3: "COK", name: "Cook Islands", alt_names: &[] },
+    Country { alpha2: "CO", alpha3: "COL", name: "Colombia", alt_names: &["Republic of Colombia"] },
+    Country { alpha2: "KM", alpha3: "COM", name: "Comoros", alt_names: &["Union of the Comoros"] },
+    Country { alpha2: "CV", alpha3: "CPV", name: "Cabo Verde", alt_names: &["Republic of Cabo Verde"] },
+    Country { alpha2: "CR", alpha3: "CRI", name: "Costa Rica", alt_names: &["Republic of Costa Rica"] },
+    Country { alpha2: "CU", alpha3: "CUB", name: "Cuba", alt_names: &["Republic of Cuba"] },
+    Country { alpha2: "CW", alpha3: "CUW", name: "Curaçao", alt_names: &[] },
+    Country { alpha2: "CX", alpha3: "CXR", name: "Christmas Island", alt_names: &[] },
+    Country { alpha2: "KY", alpha3: "CYM", name: "Cayman Islands", alt_names: &[] },
+    Country { alpha2: "CY", alpha3: "CYP", name: "Cyprus", alt_names: &["Republic of Cyprus"] },
+    Country { alpha2: "CZ", alpha3: "CZE", name: "Czechia", alt_names: &["Czech Republic"] },
+    Country { alpha2: "DE", alpha3: "DEU", name: "Germany", alt_names: &["Federal Republic of Germany"] },
+    Country { alpha2: "DJ", alpha3: "DJI", name: "Djibouti", alt_names: &["Republic of Djibouti"] },
+    Country { alpha2: "DM", alpha3: "DMA", name: "Dominica", alt_names: &["Commonwealth of Dominica"] },
+    Country { alpha2: "DK", alpha3: "DNK", name: "Denmark", alt_names: &["Kingdom of Denmark"] },
+    Country { alpha2: "DO", alpha3: "DOM", name: "Dominican Republic", alt_names: &[] },
+    Country { alpha2: "DZ", alpha3: "DZA", name: "Algeria", alt_names: &["People's Democratic Republic of Algeria"] },
+    Country { alpha2: "EC", alpha3: "ECU", name: "Ecuador", alt_names: &["Republic of Ecuador"] },
+    Country { alpha2: "EG", alpha3: "EGY", name: "Egypt", alt_names: &["Arab Republic of Egypt"] },
+    Country { alpha2: "ER", alpha3: "ERI", name: "Eritrea", alt_names: &["the State of Eritrea"] },
+    Country { alpha2: "EH", alpha3: "ESH", name: "Western Sahara", alt_names: &[] },
+    Country { alpha2: "ES", alpha3: "ESP", name: "Spain", alt_names: &["Kingdom of Spain"] },
+    Country { alpha2: "EE", alpha3: "EST", name: "Estonia", alt_names: &["Republic of Estonia"] },
+    Country { alpha2: "ET", alpha3: "ETH", name: "Ethiopia", alt_names: &["Federal Democratic Republic of Ethiopia"] },
+    Country { alpha2: "FI", alpha3: "FIN", name: "Finland", alt_names: &["Republic of Finland"] },
+    Country { alpha2: "FJ", alpha3: "FJI", name: "Fiji", alt_names: &["Republic of Fiji"] },
+    Country { alpha2: "FK", alpha3: "FLK", name: "Falkland Islands (Malvinas)", alt_names: &[] },
+    Country { alpha2: "FR", alpha3: "FRA", name: "France", alt_names: &["French Republic"] },
+    Country { alpha2: "FO", alpha3: "FRO", name: "Faroe Islands", alt_names: &[] },
+    Country { alpha2: "FM", alpha3: "FSM", name: "Micronesia, Federated States of", alt_names: &["Federated States of Micronesia"] },
+    Country { alpha2: "GA", alpha3: "GAB", name: "Gabon", alt_names: &["Gabonese Republic"] },
+    Country { alpha2: "GB", alpha3: "GBR", name: "United Kingdom", alt_names: &["United Kingdom of Great Britain and Northern Ireland"] },
+    Country { alpha2: "GE", alpha3: "GEO", name: "Georgia", alt_names: &[] },
+    Country { alpha2: "GG", alpha3: "GGY", name: "Guernsey", alt_names: &[] },
+    Country { alpha2: "GH", alpha3: "GHA", name: "Ghana", alt_names: &["Republic of Ghana"] },
+    Country { alpha2: "GI", alpha3: "GIB", name: "Gibraltar", alt_names: &[] },
+    Country { alpha2: "GN", alpha3: "GIN", name: "Guinea", alt_names: &["Republic of Guinea"] },
+    Country { alpha2: "GP", alpha3: "GLP", name: "Guadeloupe", alt_names: &[] },
+    Country { alpha2: "GM", alpha3: "GMB", name: "Gambia", alt_names: &["Republic of the Gambia"] },
+    Country { alpha2: "GW", alpha3: "GNB", name: "Guinea-Bissau", alt_names: &["Republic of Guinea-Bissau"] },
+    Country { alpha2: "GQ", alpha3: "GNQ", name: "Equatorial Guinea", alt_names: &["Republic of Equatorial Guinea"] },
+    Country { alpha2: "GR", alpha3: "GRC", name: "Greece", alt_names: &["Hellenic Republic"] },
+    Country { alpha2: "GD", alpha3: "GRD", name: "Grenada", alt_names: &[] },
+    Country { alpha2: "GL", alpha3: "GRL", name: "Greenland", alt_names: &[] },
+    Country { alpha2: "GT", alpha3: "GTM", name: "Guatemala", alt_names: &["Republic of Guatemala"] },
+    Country { alpha2: "GF", alpha3: "GUF", name: "French Guiana", alt_names: &[] },
+    Country { alpha2: "GU", alpha3: "GUM", name: "Guam", alt_names: &[] },
+    Country { alpha2: "GY", alpha3: "GUY", name: "Guyana", alt_names: &["Republic of Guyana"] },
+    Country { alpha2: "HK", alpha3: "HKG", name: "Hong Kong", alt_names: &["Hong Kong Special Administrative Region of China"] },
+    Country { alpha2: "HM", alpha3: "HMD", name: "Heard Island and McDonald Islands", alt_names: &[] },
+    Country { alpha2: "HN", alpha3: "HND", name: "Honduras", alt_names: &["Republic of Honduras"] },
+    Country { alpha2: "HR", alpha3: "HRV", name: "Croatia", alt_names: &["Republic of Croatia"] },
+    Country { alpha2: "HT", alpha3: "HTI", name: "Haiti", alt_names: &["Republic of Haiti"] },
+    Country { alpha2: "HU", alpha3: "HUN", name: "Hungary", alt_names: &[] },
+    Country { alpha2: "ID", alpha3: "IDN", name: "Indonesia", alt_names: &["Republic of Indonesia"] },
+    Country { alpha2: "IM", alpha3: "IMN", name: "Isle of Man", alt_names: &[] },
+    Country { alpha2: "IN", alpha3: "IND", name: "India", alt_names: &["Republic of India"] },
+    Country { alpha2: "IO", alpha3: "IOT", name: "British Indian Ocean Territory", alt_names: &[] },
+    Country { alpha2: "IE", alpha3: "IRL", name: "Ireland", alt_names: &[] },
+    Country { alpha2: "IR", alpha3: "IRN", name: "Iran", alt_names: &["Iran, Islamic Republic of", "Islamic Republic of Iran"] },
+    Country { alpha2: "IQ", alpha3: "IRQ", name: "Iraq", alt_names: &["Republic of Iraq"] },
+    Country { alpha2: "IS", alpha3: "ISL", name: "Iceland", alt_names: &["Republic of Iceland"] },
+    Country { alpha2: "IL", alpha3: "ISR", name: "Israel", alt_names: &["State of Israel"] },
+    Country { alpha2: "IT", alpha3: "ITA", name: "Italy", alt_names: &["Italian Republic"] },
+    Country { alpha2: "JM", alpha3: "JAM", name: "Jamaica", alt_names: &[] },
+    Country { alpha2: "JE", alpha3: "JEY", name: "Jersey", alt_names: &[] },
+    Country { alpha2: "JO", alpha3: "JOR", name: "Jordan", alt_names: &["Hashemite Kingdom of Jordan"] },
+    Country { alpha2: "JP", alpha3: "JPN", name: "Japan", alt_names: &[] },
+    Country { alpha2: "KZ", alpha3: "KAZ", name: "Kazakhstan", alt_names: &["Republic of Kazakhstan"] },
+    Country { alpha2: "KE", alpha3: "KEN", name: "Kenya", alt_names: &["Republic of Kenya"] },
+    Country { alpha2: "KG", alpha3: "KGZ", name: "Kyrgyzstan", alt_names: &["Kyrgyz Republic"] },
+    Country { alpha2: "KH", alpha3: "KHM", name: "Cambodia", alt_names: &["Kingdom of Cambodia"] },
+    Country { alpha2: "KI", alpha3: "KIR", name: "Kiribati", alt_names: &["Republic of Kiribati"] },
+    Country { alpha2: "KN", alpha3: "KNA", name: "Saint Kitts and Nevis", alt_names: &[] },
+    Country { alpha2: "KR", alpha3: "KOR", name: "South Korea", alt_names: &["Korea, Republic of"] },
+    Country { alpha2: "KW", alpha3: "KWT", name: "Kuwait", alt_names: &["State of Kuwait"] },
+    Country { alpha2: "LA", alpha3: "LAO", name: "Laos", alt_names: &["Lao People's Democratic Republic"] },
+    Country { alpha2: "LB", alpha3: "LBN", name: "Lebanon", alt_names: &["Lebanese Republic"] },
+    Country { alpha2: "LR", alpha3: "LBR", name: "Liberia", alt_names: &["Republic of Liberia"] },
+    Country { alpha2: "LY", alpha3: "LBY", name: "Libya", alt_names: &[] },
+    Country { alpha2: "LC", alpha3: "LCA", name: "Saint Lucia", alt_names: &[] },
+    Country { alpha2: "LI", alpha3: "LIE", name: "Liechtenstein", alt_names: &["Principality of Liechtenstein"] },
+    Country { alpha2: "LK", alpha3: "LKA", name: "Sri Lanka", alt_names: &["Democratic Socialist Republic of Sri Lanka"] },
+    Country { alpha2: "LS", alpha3: "LSO", name: "Lesotho", alt_names: &["Kingdom of Lesotho"] },
+    Country { alpha2: "LT", alpha3: "LTU", name: "Lithuania", alt_names: &["Republic of Lithuania"] },
+    Country { alpha2: "LU", alpha3: "LUX", name: "Luxembourg", alt_names: &["Grand Duchy of Luxembourg"] },
+    Country { alpha2: "LV", alpha3: "LVA", name: "Latvia", alt_names: &["Republic of Latvia"] },
+    Country { alpha2: "MO", alpha3: "MAC", name: "Macao", alt_names: &["Macao Special Administrative Region of China"] },
+    Country { alpha2: "MF", alpha3: "MAF", name: "Saint Martin (French part)", alt_names: &[] },
+    Country { alpha2: "MA", alpha3: "MAR", name: "Morocco", alt_names: &["Kingdom of Morocco"] },
+    Country { alpha2: "MC", alpha3: "MCO", name: "Monaco", alt_names: &["Principality of Monaco"] },
+    Country { alpha2: "MD", alpha3: "MDA", name: "Moldova", alt_names: &["Moldova, Republic of", "Republic of Moldova"] },
+    Country { alpha2: "MG", alpha3: "MDG", name: "Madagascar", alt_names: &["Republic of Madagascar"] },
+    Country { alpha2: "MV", alpha3: "MDV", name: "Maldives", alt_names: &["Republic of Maldives"] },
+    Country { alpha2: "MX", alpha3: "MEX", name: "Mexico", alt_names: &["United Mexican States"] },
+    Country { alpha2: "MH", alpha3: "MHL", name: "Marshall Islands", alt_names: &["Republic of the Marshall Islands"] },
+    Country { alpha2: "MK", alpha3: "MKD", name: "North Macedonia", alt_names: &["Republic of North Macedonia"] },
+    Country { alpha2: "ML", alpha3: "MLI", name: "Mali", alt_names: &["Republic of Mali"] },
+    Country { alpha2: "MT", alpha3: "MLT", name: "Malta", alt_names: &["Republic of Malta"] },
+    Country { alpha2: "MM", alpha3: "MMR", name: "Myanmar", alt_names: &["Republic of Myanmar"] },
+    Country { alpha2: "ME", alpha3: "MNE", name: "Montenegro", alt_names: &[] },
+    Country { alpha2: "MN", alpha3: "MNG", name: "Mongolia", alt_names: &[] },
+    Country { alpha2: "MP", alpha3: "MNP", name: "Northern Mariana Islands", alt_names: &["Commonwealth of the Northern Mariana Islands"] },
+    Country { alpha2: "MZ", alpha3: "MOZ", name: "Mozambique", alt_names: &["Republic of Mozambique"] },
+    Country { alpha2: "MR", alpha3: "MRT", name: "Mauritania", alt_names: &["Islamic Republic of Mauritania"] },
+    Country { alpha2: "MS", alpha3: "MSR", name: "Montserrat", alt_names: &[] },
+    Country { alpha2: "MQ", alpha3: "MTQ", name: "Martinique", alt_names: &[] },
+    Country { alpha2: "MU", alpha3: "MUS", name: "Mauritius", alt_names: &["Republic of Mauritius"] },
+    Country { alpha2: "MW", alpha3: "MWI", name: "Malawi", alt_names: &["Republic of Malawi"] },
+    Country { alpha2: "MY", alpha3: "MYS", name: "Malaysia", alt_names: &[] },
+    Country { alpha2: "YT", alpha3: "MYT", name: "Mayotte", alt_names: &[] },
+    Country { alpha2: "NA", alpha3: "NAM", name: "Namibia", alt_names: &["Republic of Namibia"] },
+    Country { alpha2: "NC", alpha3: "NCL", name: "New Caledonia", alt_names: &[] },
+    Country { alpha2: "NE", alpha3: "NER", name: "Niger", alt_names: &["Republic of the Niger"] },
+    Country { alpha2: "NF", alpha3: "NFK", name: "Norfolk Island", alt_names: &[] },
+    Country { alpha2: "NG", alpha3: "NGA", name: "Nigeria", alt_names: &["Federal Republic of Nigeria"] },
+    Country { alpha2: "NI", alpha3: "NIC", name: "Nicaragua", alt_names: &["Republic of Nicaragua"] },
+    Country { alpha2: "NU", alpha3: "NIU", name: "Niue", alt_names: &[] },
+    Country { alpha2: "NL", alpha3: "NLD", name: "Netherlands", alt_names: &["Kingdom of the Netherlands"] },
+    Country { alpha2: "NO", alpha3: "NOR", name: "Norway", alt_names: &["Kingdom of Norway"] },
+    Country { alpha2: "NP", alpha3: "NPL", name: "Nepal", alt_names: &["Federal Democratic Republic of Nepal"] },
+    Country { alpha2: "NR", alpha3: "NRU", name: "Nauru", alt_names: &["Republic of Nauru"] },
+    Country { alpha2: "NZ", alpha3: "NZL", name: "New Zealand", alt_names: &[] },
+    Country { alpha2: "OM", alpha3: "OMN", name: "Oman", alt_names: &["Sultanate of Oman"] },
+    Country { alpha2: "PK", alpha3: "PAK", name: "Pakistan", alt_names: &["Islamic Republic of Pakistan"] },
+    Country { alpha2: "PA", alpha3: "PAN", name: "Panama", alt_names: &["Republic of Panama"] },
+    Country { alpha2: "PN", alpha3: "PCN", name: "Pitcairn", alt_names: &[] },
+    Country { alpha2: "PE", alpha3: "PER", name: "Peru", alt_names: &["Republic of Peru"] },
+    Country { alpha2: "PH", alpha3: "PHL", name: "Philippines", alt_names: &["Republic of the Philippines"] },
+    Country { alpha2: "PW", alpha3: "PLW", name: "Palau", alt_names: &["Republic of Palau"] },
+    Country { alpha2: "PG", alpha3: "PNG", name: "Papua New Guinea", alt_names: &["Independent State of Papua New Guinea"] },
+    Country { alpha2: "PL", alpha3: "POL", name: "Poland", alt_names: &["Republic of Poland"] },
+    Country { alpha2: "PR", alpha3: "PRI", name: "Puerto Rico", alt_names: &[] },
+    Country { alpha2: "KP", alpha3: "PRK", name: "North Korea", alt_names: &["Korea, Democratic People's Republic of", "Democratic People's Republic of Korea"] },
+    Country { alpha2: "PT", alpha3: "PRT", name: "Portugal", alt_names: &["Portuguese Republic"] },
+    Country { alpha2: "PY", alpha3: "PRY", name: "Paraguay", alt_names: &["Republic of Paraguay"] },
+    Country { alpha2: "PS", alpha3: "PSE", name: "Palestine, State of", alt_names: &["the State of Palestine"] },
+    Country { alpha2: "PF", alpha3: "PYF", name: "French Polynesia", alt_names: &[] },
+    Country { alpha2: "QA", alpha3: "QAT", name: "Qatar", alt_names: &["State of Qatar"] },
+    Country { alpha2: "RE", alpha3: "REU", name: "Réunion", alt_names: &[] },
+    Country { alpha2: "RO", alpha3: "ROU", name: "Romania", alt_names: &[] },
+    Country { alpha2: "RU", alpha3: "RUS", name: "Russian Federation", alt_names: &[] },
+    Country { alpha2: "RW", alpha3: "RWA", name: "Rwanda", alt_names: &["Rwandese Republic"] },
+    Country { alpha2: "SA", alpha3: "SAU", name: "Saudi Arabia", alt_names: &["Kingdom of Saudi Arabia"] },
+    Country { alpha2: "SD", alpha3: "SDN", name: "Sudan", alt_names: &["Republic of the Sudan"] },
+    Country { alpha2: "SN", alpha3: "SEN", name: "Senegal", alt_names: &["Republic of Senegal"] },
+    Country { alpha2: "SG", alpha3: "SGP", name: "Singapore", alt_names: &["Republic of Singapore"] },
+    Country { alpha2: "GS", alpha3: "SGS", name: "South Georgia and the South Sandwich Islands", alt_names: &[] },
+    Country { alpha2: "SH", alpha3: "SHN", name: "Saint Helena, Ascension and Tristan da Cunha", alt_names: &[] },
+    Country { alpha2: "SJ", alpha3: "SJM", name: "Svalbard and Jan Mayen", alt_names: &[] },
+    Country { alpha2: "SB", alpha3: "SLB", name: "Solomon Islands", alt_names: &[] },
+    Country { alpha2: "SL", alpha3: "SLE", name: "Sierra Leone", alt_names: &["Republic of Sierra Leone"] },
+    Country { alpha2: "SV", alpha3: "SLV", name: "El Salvador", alt_names: &["Republic of El Salvador"] },
+    Country { alpha2: "SM", alpha3: "SMR", name: "San Marino", alt_names: &["Republic of San Marino"] },
+    Country { alpha2: "SO", alpha3: "SOM", name: "Somalia", alt_names: &["Federal Republic of Somalia"] },
+    Country { alpha2: "PM", alpha3: "SPM", name: "Saint Pierre and Miquelon", alt_names: &[] },
+    Country { alpha2: "RS", alpha3: "SRB", name: "Serbia", alt_names: &["Republic of Serbia"] },
+    Country { alpha2: "SS", alpha3: "SSD", name: "South Sudan", alt_names: &["Republic of South Sudan"] },
+    Country { alpha2: "ST", alpha3: "STP", name: "Sao Tome and Principe", alt_names: &["Democratic Republic of Sao Tome and Principe"] },
+    Country { alpha2: "SR", alpha3: "SUR", name: "Suriname", alt_names: &["Republic of Suriname"] },
+    Country { alpha2: "SK", alpha3: "SVK", name: "Slovakia", alt_names: &["Slovak Republic"] },
+    Country { alpha2: "SI", alpha3: "SVN", name: "Slovenia", alt_names: &["Republic of Slovenia"] },
+    Country { alpha2: "SE", alpha3: "SWE", name: "Sweden", alt_names: &["Kingdom of Sweden"] },
+    Country { alpha2: "SZ", alpha3: "SWZ", name: "Eswatini", alt_names: &["Kingdom of Eswatini"] },
+    Country { alpha2: "SX", alpha3: "SXM", name: "Sint Maarten (Dutch part)", alt_names: &[] },
+    Country { alpha2: "SC", alpha3: "SYC", name: "Seychelles", alt_names: &["Republic of Seychelles"] },
+    Country { alpha2: "SY", alpha3: "SYR", name: "Syria", alt_names: &["Syrian Arab Republic"] },
+    Country { alpha2: "TC", alpha3: "TCA", name: "Turks and Caicos Islands", alt_names: &[] },
+    Country { alpha2: "TD", alpha3: "TCD", name: "Chad", alt_names: &["Republic of Chad"] },
+    Country { alpha2: "TG", alpha3: "TGO", name: "Togo", alt_names: &["Togolese Republic"] },
+    Country { alpha2: "TH", alpha3: "THA", name: "Thailand", alt_names: &["Kingdom of Thailand"] },
+    Country { alpha2: "TJ", alpha3: "TJK", name: "Tajikistan", alt_names: &["Republic of Tajikistan"] },
+    Country { alpha2: "TK", alpha3: "TKL", name: "Tokelau", alt_names: &[] },
+    Country { alpha2: "TM", alpha3: "TKM", name: "Turkmenistan", alt_names: &[] },
+    Country { alpha2: "TL", alpha3: "TLS", name: "Timor-Leste", alt_names: &["Democratic Republic of Timor-Leste"] },
+    Country { alpha2: "TO", alpha3: "TON", name: "Tonga", alt_names: &["Kingdom of Tonga"] },
+    Country { alpha2: "TT", alpha3: "TTO", name: "Trinidad and Tobago", alt_names: &["Republic of Trinidad and Tobago"] },
+    Country { alpha2: "TN", alpha3: "TUN", name: "Tunisia", alt_names: &["Republic of Tunisia"] },
+    Country { alpha2: "TR", alpha3: "TUR", name: "Türkiye", alt_names: &["Republic of Türkiye"] },
+    Country { alpha2: "TV", alpha3: "TUV", name: "Tuvalu", alt_names: &[] },
+    Country { alpha2: "TW", alpha3: "TWN", name: "Taiwan", alt_names: &["Taiwan, Province of China"] },
+    Country { alpha2: "TZ", alpha3: "TZA", name: "Tanzania", alt_names: &["Tanzania, United Republic of", "United Republic of Tanzania"] },
+    Country { alpha2: "UG", alpha3: "UGA", name: "Uganda", alt_names: &["Republic of Uganda"] },
+    Country { alpha2: "UA", alpha3: "UKR", name: "Ukraine", alt_names: &[] },
+    Country { alpha2: "UM", alpha3: "UMI", name: "United States Minor Outlying Islands", alt_names: &[] },
+    Country { alpha2: "UY", alpha3: "URY", name: "Uruguay", alt_names: &["Eastern Republic of Uruguay"] },
+    Country { alpha2: "US", alpha3: "USA", name: "United States", alt_names: &["United States of America"] },
+    Country { alpha2: "UZ", alpha3: "UZB", name: "Uzbekistan", alt_names: &["Republic of Uzbekistan"] },
+    Country { alpha2: "VA", alpha3: "VAT", name: "Holy See (Vatican City State)", alt_names: &[] },
+    Country { alpha2: "VC", alpha3: "VCT", name: "Saint Vincent and the Grenadines", alt_names: &[] },
+    Country { alpha2: "VE", alpha3: "VEN", name: "Venezuela", alt_names: &["Venezuela, Bolivarian Republic of", "Bolivarian Republic of Venezuela"] },
+    Country { alpha2: "VG", alpha3: "VGB", name: "Virgin Islands, British", alt_names: &["British Virgin Islands"] },
+    Country { alpha2: "VI", alpha3: "VIR", name: "Virgin Islands, U.S.", alt_names: &["Virgin Islands of the United States"] },
+    Country { alpha2: "VN", alpha3: "VNM", name: "Vietnam", alt_names: &["Viet Nam", "Socialist Republic of Viet Nam"] },
+    Country { alpha2: "VU", alpha3: "VUT", name: "Vanuatu", alt_names: &["Republic of Vanuatu"] },
+    Country { alpha2: "WF", alpha3: "WLF", name: "Wallis and Futuna", alt_names: &[] },
+    Country { alpha2: "WS", alpha3: "WSM", name: "Samoa", alt_names: &["Independent State of Samoa"] },
+    Country { alpha2: "YE", alpha3: "YEM", name: "Yemen", alt_names: &["Republic of Yemen"] },
+    Country { alpha2: "ZA", alpha3: "ZAF", name: "South Africa", alt_names: &["Republic of South Africa"] },
+    Country { alpha2: "ZM", alpha3: "ZMB", name: "Zambia", alt_names: &["Republic of Zambia"] },
+    Country { alpha2: "ZW", alpha3: "ZWE", name: "Zimbabwe", alt_names: &["Republic of Zimbabwe"] },
+];
+
+impl Country {
+    pub fn by_alpha2(code: &str) -> Option<&'static Country> {
+        COUNTRIES.iter().find(|country| country.alpha2.eq_ignore_ascii_case(code))
+    }
+
+    /// Ищет страну по коду alpha-2/alpha-3, названию ISO или известному синониму
+    /// без учёта регистра и лишних пробелов
+    pub fn find(input: &str) -> Option<&'static Country> {
+        let key = input.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if key.is_empty() {
+            return None;
+        }
+
+        COUNTRIES
+            .iter()
+            .find(|country| {
+                country.alpha2.eq_ignore_ascii_case(&key)
+                    || country.alpha3.eq_ignore_ascii_case(&key)
+                    || country.name.to_lowercase() == key
+                    || country.alt_names.iter().any(|name| name.to_lowercase() == key)
+            })
+            .or_else(|| {
+                ALIASES
+                    .iter()
+                    .find(|(alias, _)| alias.to_lowercase() == key)
+                    .and_then(|(_, code)| Self::by_alpha2(code))
+            })
+    }
+}
+
+/// Приводит страну к каноническому названию ISO 3166-1 (например, "de", "DEU" и "germany" -> "Germany")
+pub fn normalize_country(input: &str) -> Option<&'static str> {
+    Country::find(input).map(|country| country.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_map_to_canonical_names() {
+        assert_eq!(normalize_country("DE"), Some("Germany"));
+        assert_eq!(normalize_country("DEU"), Some("Germany"));
+        assert_eq!(normalize_country("JP"), Some("Japan"));
+        assert_eq!(normalize_country("KOR"), Some("South Korea"));
+        assert_eq!(Country::by_alpha2("us").map(|country| country.alpha3), Some("USA"));
+    }
+
+    #[test]
+    fn names_are_normalized_regardless_of_case_and_spacing() {
+        assert_eq!(normalize_country("germany"), Some("Germany"));
+        assert_eq!(normalize_country("  GERMANY "), Some("Germany"));
+        assert_eq!(normalize_country("de"), Some("Germany"));
+        assert_eq!(normalize_country("united   states"), Some("United States"));
+        assert_eq!(normalize_country("Federal Republic of Germany"), Some("Germany"));
+    }
+
+    #[test]
+    fn aliases_resolve_to_canonical_names() {
+        assert_eq!(normalize_country("Russia"), Some("Russian Federation"));
+        assert_eq!(normalize_country("uk"), Some("United Kingdom"));
+        assert_eq!(normalize_country("Германия"), Some("Germany"));
+        assert_eq!(normalize_country("сша"), Some("United States"));
+    }
+
+    #[test]
+    fn unknown_or_blank_input_is_not_normalized() {
+        assert_eq!(normalize_country("Atlantis"), None);
+        assert_eq!(normalize_country("XX"), None);
+        assert_eq!(normalize_country("   "), None);
+    }
+}
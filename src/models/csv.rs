@@ -0,0 +1,34 @@
+use std::borrow::Cow;
+
+/// Запись, которую можно выгрузить строкой CSV
+pub trait CsvRecord {
+    /// Заголовок: имена колонок через запятую
+    const CSV_HEADER: &'static str;
+
+    /// Значения колонок в порядке заголовка, без экранирования
+    fn csv_fields(&self) -> Vec<String>;
+}
+
+// Поле с запятой, кавычкой или переводом строки берётся в кавычки, кавычки внутри удваиваются (RFC 4180)
+fn escape_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// CSV-документ с заголовком; строки разделяются CRLF
+pub fn to_csv<T: CsvRecord>(records: &[T]) -> String {
+    let mut csv = String::from(T::CSV_HEADER);
+    csv.push_str("\r\n");
+    for record in records {
+        let fields: Vec<String> = record.csv_fields()
+            .iter()
+            .map(|field| escape_field(field).into_owned())
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
@@ -3,6 +3,9 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use validator::Validate;
 
+use super::car::Car;
+use super::purchase::PurchaseRequest;
+
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct Customer {
     pub id: Uuid,
@@ -25,4 +28,32 @@ pub struct CreateCustomerRequest {
     #[validate(email)]
     pub email: String,
     pub phone: String,
-}
\ No newline at end of file
+}
+
+/// Частичное обновление клиента: незаданные поля остаются прежними
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateCustomerRequest {
+    #[validate(length(min = 2))]
+    pub first_name: Option<String>,
+    #[validate(length(min = 2))]
+    pub last_name: Option<String>,
+    #[validate(email)]
+    pub email: Option<String>,
+    pub phone: Option<String>,
+}
+
+/// Заявка клиента с данными автомобиля; car = None, если автомобиль уже не найден
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportedPurchaseRequest {
+    #[serde(flatten)]
+    pub purchase: PurchaseRequest,
+    pub car: Option<Car>,
+}
+
+/// Все данные, хранимые о клиенте, одним документом (ответ на запрос субъекта данных)
+#[derive(Debug, Serialize, Clone)]
+pub struct CustomerDataExport {
+    pub exported_at: DateTime<Utc>,
+    pub customer: Customer,
+    pub purchase_requests: Vec<ExportedPurchaseRequest>,
+}
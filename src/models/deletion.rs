@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct DryRunQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// `?idempotent=true`: повторное удаление уже отсутствующей записи возвращает 204 вместо 404
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuery {
+    #[serde(default)]
+    pub idempotent: bool,
+}
+
+/// `?force=true`: удалить объект вместе с незавершёнными зависимыми записями
+#[derive(Debug, Deserialize)]
+pub struct ForceQuery {
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Результат (или прогноз при dry_run) каскадного удаления
+#[derive(Debug, Serialize, Clone)]
+pub struct DeletionReport {
+    pub dry_run: bool,
+    pub deleted: u64,
+    /// Записи, которые будут удалены каскадно вместе с объектом
+    pub cascaded: BTreeMap<String, i64>,
+    /// Записи, которые ссылаются на объект и не дают его удалить
+    pub blocked_by: BTreeMap<String, i64>,
+}
+
+impl DeletionReport {
+    pub fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            deleted: 0,
+            cascaded: BTreeMap::new(),
+            blocked_by: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_cascaded(&mut self, table: &str, count: i64) {
+        if count > 0 {
+            self.cascaded.insert(table.to_string(), count);
+        }
+    }
+
+    pub fn add_blocker(&mut self, table: &str, count: i64) {
+        if count > 0 {
+            self.blocked_by.insert(table.to_string(), count);
+        }
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        !self.blocked_by.is_empty()
+    }
+}
+
+/// Размер пакета проверяется в обработчике по MAX_BATCH_SIZE
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchDeleteRequest {
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BatchDeletionReport {
+    pub dry_run: bool,
+    pub deleted: Vec<Uuid>,
+    pub not_found: Vec<Uuid>,
+    /// ID, которые нельзя удалить, с причиной
+    pub blocked: BTreeMap<Uuid, String>,
+    pub cascaded: BTreeMap<String, i64>,
+}
+
+/// Количество зависимых записей и несколько их идентификаторов для примера
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct DependentRecords {
+    pub count: i64,
+    pub sample_ids: Vec<Uuid>,
+}
+
+/// Всё, что затронет удаление бренда (только чтение, без блокировок)
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct BrandDependents {
+    pub brand_id: Uuid,
+    pub car_models: DependentRecords,
+    pub cars: DependentRecords,
+    pub parts: DependentRecords,
+    pub works: DependentRecords,
+    pub service_campaigns: DependentRecords,
+}
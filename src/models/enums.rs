@@ -49,4 +49,140 @@ pub enum RequestStatus {
     Rejected,
     #[sqlx(rename = "Completed")]
     Completed,
-}
\ No newline at end of file
+}
+
+impl RequestStatus {
+    // Pending -> Approved/Rejected, Approved -> Completed/Rejected; Rejected и Completed - конечные
+    pub fn can_transition_to(&self, next: &RequestStatus) -> bool {
+        matches!(
+            (self, next),
+            (RequestStatus::Pending, RequestStatus::Approved)
+                | (RequestStatus::Pending, RequestStatus::Rejected)
+                | (RequestStatus::Approved, RequestStatus::Completed)
+                | (RequestStatus::Approved, RequestStatus::Rejected)
+        )
+    }
+}
+
+/// Допустимое значение перечисления и подпись для выпадающих списков в UI
+#[derive(Debug, Serialize, Clone)]
+pub struct EnumOption {
+    pub value: &'static str,
+    pub label: &'static str,
+}
+
+pub trait EnumVariants: Sized + 'static {
+    fn variants() -> &'static [Self];
+    /// Значение в том виде, в котором оно приходит и уходит через API
+    fn value(&self) -> &'static str;
+    fn label(&self) -> &'static str;
+
+    /// Вариант по значению без учёта регистра (например, из пути запроса)
+    fn from_value_ignore_case(value: &str) -> Option<&'static Self> {
+        Self::variants().iter().find(|variant| variant.value().eq_ignore_ascii_case(value))
+    }
+
+    fn options() -> Vec<EnumOption> {
+        Self::variants()
+            .iter()
+            .map(|variant| EnumOption {
+                value: variant.value(),
+                label: variant.label(),
+            })
+            .collect()
+    }
+}
+
+impl EnumVariants for FuelType {
+    fn variants() -> &'static [Self] {
+        &[FuelType::Petrol, FuelType::Diesel, FuelType::Electric, FuelType::Hybrid]
+    }
+
+    fn value(&self) -> &'static str {
+        match self {
+            FuelType::Petrol => "Petrol",
+            FuelType::Diesel => "Diesel",
+            FuelType::Electric => "Electric",
+            FuelType::Hybrid => "Hybrid",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            FuelType::Petrol => "Бензин",
+            FuelType::Diesel => "Дизель",
+            FuelType::Electric => "Электро",
+            FuelType::Hybrid => "Гибрид",
+        }
+    }
+}
+
+impl EnumVariants for Transmission {
+    fn variants() -> &'static [Self] {
+        &[Transmission::Manual, Transmission::Automatic, Transmission::CVT]
+    }
+
+    fn value(&self) -> &'static str {
+        match self {
+            Transmission::Manual => "Manual",
+            Transmission::Automatic => "Automatic",
+            Transmission::CVT => "CVT",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Transmission::Manual => "Механическая",
+            Transmission::Automatic => "Автоматическая",
+            Transmission::CVT => "Вариатор",
+        }
+    }
+}
+
+impl EnumVariants for CarStatus {
+    fn variants() -> &'static [Self] {
+        &[CarStatus::Available, CarStatus::Reserved, CarStatus::Sold, CarStatus::Maintenance]
+    }
+
+    fn value(&self) -> &'static str {
+        match self {
+            CarStatus::Available => "Available",
+            CarStatus::Reserved => "Reserved",
+            CarStatus::Sold => "Sold",
+            CarStatus::Maintenance => "Maintenance",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            CarStatus::Available => "В наличии",
+            CarStatus::Reserved => "Зарезервирован",
+            CarStatus::Sold => "Продан",
+            CarStatus::Maintenance => "На обслуживании",
+        }
+    }
+}
+
+impl EnumVariants for RequestStatus {
+    fn variants() -> &'static [Self] {
+        &[RequestStatus::Pending, RequestStatus::Approved, RequestStatus::Rejected, RequestStatus::Completed]
+    }
+
+    fn value(&self) -> &'static str {
+        match self {
+            RequestStatus::Pending => "Pending",
+            RequestStatus::Approved => "Approved",
+            RequestStatus::Rejected => "Rejected",
+            RequestStatus::Completed => "Completed",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RequestStatus::Pending => "Ожидает",
+            RequestStatus::Approved => "Одобрена",
+            RequestStatus::Rejected => "Отклонена",
+            RequestStatus::Completed => "Завершена",
+        }
+    }
+}
@@ -3,18 +3,54 @@ pub mod customer;
 pub mod purchase;
 pub mod part;
 pub mod brand;
+pub mod country;
 pub mod car_model;
 pub mod work;
 pub mod enums;
 mod service_campaigns;
 pub mod warehouse;
+pub mod deletion;
+pub mod pagination;
+pub mod money;
+pub mod search;
+pub mod csv;
+pub mod schema;
 
-pub use car::{Car, CreateCarRequest, UpdateCarRequest};
-pub use customer::{Customer, CreateCustomerRequest};
-pub use purchase::{PurchaseRequest, CreatePurchaseRequest};
-pub use part::{Part, CreatePartRequest, UpdatePartRequest};
-pub use brand::{Brand, CreateBrandRequest, UpdateBrandRequest};
-pub use car_model::{CarModel, CreateCarModelRequest, UpdateCarModelRequest};
-pub use enums::{FuelType, Transmission, CarStatus, RequestStatus};
-pub use work::{Work, CreateWorkRequest, UpdateWorkRequest};
-pub use service_campaigns::{ServiceCampaign, ServiceCampaignStatus, UpdateServiceCampaignRequest, CreateServiceCampaignRequest};
\ No newline at end of file
+/// Массивные поля запроса с их длиной; проверяются в обработчике по limits.max_array_len
+pub trait ArrayFields {
+    fn array_fields(&self) -> Vec<(&'static str, usize)>;
+}
+
+pub use car::{Car, CreateCarRequest, UpdateCarRequest, DuplicateCarRequest, BatchGetCarsRequest, BatchGetCarsResponse, BulkCarImportResponse, CarChangesCursor, CarChangesResponse, CarExportRow, CarDetail, CarRecord, normalize_feature, is_valid_vin,
+    CarTimelineEvent, CarTimelineEntry, CarFacets, FacetCount, PriceFacet};
+pub use customer::{Customer, CreateCustomerRequest, UpdateCustomerRequest, CustomerDataExport, ExportedPurchaseRequest};
+pub use purchase::{
+    PurchaseRequest, CreatePurchaseRequest, MostRequestedCar, AvgPriceByModel, StalePurchaseRequest, OldestPendingPurchase,
+    SaleMargin, SaleMarginsReport, BatchStatusUpdateRequest, StatusTransitionResult, BatchStatusUpdateResponse,
+    OfferPriceWarning, CreatedPurchaseRequest, PurchaseFilter,
+    BrandDaysToSale, AvgDaysToSaleReport, TopCustomer
+};
+pub use part::{
+    Part, CreatePartRequest, UpdatePartRequest, CompatibleModel, PartCompatibleModels, CrossCompatiblePart, AddCompatibleVinsRequest,
+    CompatibleVinsStatus, CompatibleVinsResult, CampaignPartDemand, PartCampaignDemand,
+    PartStock, PartsCoverage, PartCatalogFilter, PartCatalogSort, SortOrder, PartCatalogRow, PartWithMargin, validate_sale_price
+};
+pub use brand::{
+    Brand, CreateBrandRequest, UpdateBrandRequest, BrandCountryCount, CountryMatch, BrandPriceFloor, SetBrandPriceFloorRequest, PricedItem,
+    BrandStockDefaults, SetBrandStockDefaultsRequest
+};
+pub use country::{Country, normalize_country};
+pub use car_model::{CarModel, CarModelWithBrand, CarModelDetailed, CarModelWithCount, CreateCarModelRequest, UpdateCarModelRequest};
+pub use enums::{FuelType, Transmission, CarStatus, RequestStatus, EnumVariants};
+pub use work::{Work, CreateWorkRequest, UpdateWorkRequest, ApplicableWorks};
+pub use service_campaigns::{
+    ServiceCampaign, ServiceCampaignStatus, UpdateServiceCampaignRequest, CreateServiceCampaignRequest,
+    ValidateVinsRequest, VinValidationResult, CustomerPendingCampaign, ServiceCampaignFilter,
+    BulkImportQuery, BulkImportRowStatus, BulkImportRowResult, BulkImportResponse, TargetedVin,
+    CampaignCompletion, CampaignTargetCar, CampaignTargetCars, CreatedServiceCampaign
+};
+pub use deletion::{DryRunQuery, DeleteQuery, ForceQuery, DeletionReport, BatchDeleteRequest, BatchDeletionReport, DependentRecords, BrandDependents};
+pub use pagination::PaginatedResponse;
+pub use search::{SearchType, SearchResults};
+pub use csv::to_csv;
+pub use schema::ResourceSchema;
@@ -0,0 +1,66 @@
+use serde::Serializer;
+
+// Денежные суммы хранятся в f64; в ответах округляем до копеек, чтобы не отдавать 19999.999999998
+pub fn round_money(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+pub fn serialize_money<S>(value: &f64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_f64(round_money(*value))
+}
+
+pub fn serialize_optional_money<S>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(value) => serializer.serialize_some(&round_money(*value)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Priced {
+        #[serde(serialize_with = "serialize_money")]
+        price: f64,
+        #[serde(serialize_with = "serialize_optional_money")]
+        discount: Option<f64>,
+    }
+
+    fn round_trip(price: f64, discount: Option<f64>) -> (String, Priced) {
+        let json = serde_json::to_string(&Priced { price, discount }).unwrap();
+        let parsed = serde_json::from_str(&json).unwrap();
+        (json, parsed)
+    }
+
+    #[test]
+    fn round_money_rounds_to_cents() {
+        assert_eq!(round_money(1999.999), 2000.0);
+        assert_eq!(round_money(0.1 + 0.2), 0.3);
+        assert_eq!(round_money(10.004), 10.0);
+        assert_eq!(round_money(-5.556), -5.56);
+    }
+
+    #[test]
+    fn serialize_money_round_trip() {
+        let (json, parsed) = round_trip(1999.999, Some(0.1 + 0.2));
+        assert_eq!(json, r#"{"price":2000.0,"discount":0.3}"#);
+        assert_eq!(parsed.price, 2000.0);
+        assert_eq!(parsed.discount, Some(0.3));
+    }
+
+    #[test]
+    fn serialize_optional_money_keeps_null() {
+        let (json, parsed) = round_trip(19999.999999998, None);
+        assert_eq!(json, r#"{"price":20000.0,"discount":null}"#);
+        assert_eq!(parsed.discount, None);
+    }
+}
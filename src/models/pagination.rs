@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+/// Страница списка вместе с общим количеством записей, подходящих под фильтр
+#[derive(Debug, Serialize, Clone)]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl<T> PaginatedResponse<T> {
+    pub fn new(items: Vec<T>, total: i64, limit: i64, offset: i64) -> Self {
+        Self { items, total, limit, offset }
+    }
+}
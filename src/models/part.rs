@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use super::money::serialize_money;
+use super::ArrayFields;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct Part {
@@ -13,8 +16,10 @@ pub struct Part {
     pub brand_id: Uuid,
     pub car_model_id: Uuid,
     #[validate(range(min = 0.0))]
+    #[serde(serialize_with = "serialize_money")]
     pub purchase_price: f64,
     #[validate(range(min = 0.0))]
+    #[serde(serialize_with = "serialize_money")]
     pub sale_price: f64,
     pub compatible_vins: Vec<String>,
     pub created_at: DateTime<Utc>,
@@ -22,6 +27,7 @@ pub struct Part {
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_create_prices"))]
 pub struct CreatePartRequest {
     #[validate(length(min = 1))]
     pub article: String,
@@ -36,7 +42,15 @@ pub struct CreatePartRequest {
     pub compatible_vins: Vec<String>,
 }
 
+impl ArrayFields for CreatePartRequest {
+    fn array_fields(&self) -> Vec<(&'static str, usize)> {
+        vec![("compatible_vins", self.compatible_vins.len())]
+    }
+}
+
+/// Соотношение цен проверяется здесь, только если переданы обе; иначе - в обработчике с текущей ценой запчасти
 #[derive(Debug, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_update_prices"))]
 pub struct UpdatePartRequest {
     pub article: Option<String>,
     pub name: Option<String>,
@@ -47,4 +61,349 @@ pub struct UpdatePartRequest {
     #[validate(range(min = 0.0))]
     pub sale_price: Option<f64>,
     pub compatible_vins: Option<Vec<String>>,
-}
\ No newline at end of file
+}
+
+impl ArrayFields for UpdatePartRequest {
+    fn array_fields(&self) -> Vec<(&'static str, usize)> {
+        vec![("compatible_vins", self.compatible_vins.as_ref().map_or(0, Vec::len))]
+    }
+}
+
+/// Цена продажи не может быть ниже закупочной, иначе в отчётах появляется отрицательная маржа
+pub fn validate_sale_price(purchase_price: f64, sale_price: f64) -> Result<(), ValidationError> {
+    if sale_price < purchase_price {
+        let mut error = ValidationError::new("sale_price");
+        error.message = Some("Цена продажи не может быть ниже закупочной цены".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn validate_create_prices(request: &CreatePartRequest) -> Result<(), ValidationError> {
+    validate_sale_price(request.purchase_price, request.sale_price)
+}
+
+fn validate_update_prices(request: &UpdatePartRequest) -> Result<(), ValidationError> {
+    match (request.purchase_price, request.sale_price) {
+        (Some(purchase_price), Some(sale_price)) => validate_sale_price(purchase_price, sale_price),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompatibleModel {
+    pub model_id: Uuid,
+    pub model_name: String,
+    pub brand_id: Uuid,
+    pub brand_name: String,
+    pub vins: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartCompatibleModels {
+    pub part_id: Uuid,
+    pub models: Vec<CompatibleModel>,
+    /// VIN из списка совместимости, для которых нет автомобиля в базе
+    pub unmatched_vins: Vec<String>,
+}
+
+/// Запчасть, совместимая с несколькими моделями. Модели определяются по автомобилям
+/// с VIN из списка совместимости; собственная car_model_id запчасти не учитывается,
+/// поэтому запчасть с пустым списком VIN считается одномодельной.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CrossCompatiblePart {
+    pub part_id: Uuid,
+    pub article: String,
+    pub name: String,
+    pub brand_id: Uuid,
+    pub car_model_id: Uuid,
+    pub model_count: i64,
+    pub model_ids: Vec<Uuid>,
+}
+
+/// Потребность в запчасти по одной активной кампании: количество на автомобиль
+/// (сколько раз запчасть указана в required_parts) на число невыполненных автомобилей
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignPartDemand {
+    pub campaign_id: Uuid,
+    pub article: String,
+    pub name: String,
+    pub units_per_car: i64,
+    pub outstanding_cars: i64,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartCampaignDemand {
+    pub part_id: Uuid,
+    pub total_quantity: i64,
+    pub campaigns: Vec<CampaignPartDemand>,
+}
+
+impl PartCampaignDemand {
+    pub fn new(part_id: Uuid, campaigns: Vec<CampaignPartDemand>) -> Self {
+        Self {
+            part_id,
+            total_quantity: campaigns.iter().map(|campaign| campaign.quantity).sum(),
+            campaigns,
+        }
+    }
+}
+
+/// Запчасть модели с остатком на складе; quantity = 0 и stocked = false, если позиции на складе нет
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartStock {
+    pub part_id: Uuid,
+    pub article: String,
+    pub name: String,
+    pub stocked: bool,
+    pub quantity: i32,
+    pub in_stock: bool,
+}
+
+/// Запчасти модели автомобиля и их наличие: serviceable, если хотя бы одна есть на складе
+#[derive(Debug, Serialize, Clone)]
+pub struct PartsCoverage {
+    pub car_id: Uuid,
+    pub car_model_id: Uuid,
+    pub serviceable: bool,
+    pub parts_total: usize,
+    pub parts_in_stock: usize,
+    pub parts: Vec<PartStock>,
+}
+
+impl PartsCoverage {
+    pub fn new(car_id: Uuid, car_model_id: Uuid, parts: Vec<PartStock>) -> Self {
+        let parts_in_stock = parts.iter().filter(|part| part.in_stock).count();
+        Self {
+            car_id,
+            car_model_id,
+            serviceable: parts_in_stock > 0,
+            parts_total: parts.len(),
+            parts_in_stock,
+            parts,
+        }
+    }
+}
+
+/// Фильтры каталога запчастей; None - фильтр не применяется. Цена фильтруется по sale_price
+#[derive(Debug, Default, Clone)]
+pub struct PartCatalogFilter {
+    pub brand_id: Option<Uuid>,
+    pub car_model_id: Option<Uuid>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PartCatalogSort {
+    #[default]
+    Article,
+    Name,
+    SalePrice,
+    Quantity,
+    MarginPercent,
+}
+
+impl PartCatalogSort {
+    /// Выражение ORDER BY; подставляется в SQL, поэтому только из фиксированного набора
+    pub fn column(&self) -> &'static str {
+        match self {
+            PartCatalogSort::Article => "p.article",
+            PartCatalogSort::Name => "p.name",
+            PartCatalogSort::SalePrice => "p.sale_price",
+            PartCatalogSort::Quantity => "quantity",
+            PartCatalogSort::MarginPercent => "margin_percent",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Строка каталога: запчасть, остаток на складе (0, если позиции нет) и маржа в процентах от цены продажи;
+/// margin_percent = None при нулевой цене продажи
+#[derive(Debug, Serialize, Clone)]
+pub struct PartCatalogRow {
+    #[serde(flatten)]
+    pub part: Part,
+    pub quantity: i32,
+    pub margin_percent: Option<f64>,
+}
+
+/// Запчасть с маржой: margin = sale_price - purchase_price, margin_percent - наценка в процентах
+/// от закупочной цены; при нулевой закупочной цене margin_percent = None
+#[derive(Debug, Serialize, Clone)]
+pub struct PartWithMargin {
+    #[serde(flatten)]
+    pub part: Part,
+    #[serde(serialize_with = "serialize_money")]
+    pub margin: f64,
+    pub margin_percent: Option<f64>,
+}
+
+impl From<Part> for PartWithMargin {
+    fn from(part: Part) -> Self {
+        let margin = part.sale_price - part.purchase_price;
+        let margin_percent = (part.purchase_price != 0.0)
+            .then(|| (margin / part.purchase_price * 10000.0).round() / 100.0);
+
+        Self { part, margin, margin_percent }
+    }
+}
+
+/// Добавление одних и тех же VIN в список совместимости нескольких запчастей
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AddCompatibleVinsRequest {
+    /// Размер пакета проверяется в обработчике по MAX_BATCH_SIZE
+    pub part_ids: Vec<Uuid>,
+    #[validate(length(min = 1))]
+    pub vins: Vec<String>,
+}
+
+impl ArrayFields for AddCompatibleVinsRequest {
+    fn array_fields(&self) -> Vec<(&'static str, usize)> {
+        vec![("vins", self.vins.len())]
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub enum CompatibleVinsStatus {
+    #[serde(rename = "updated")]
+    Updated,
+    /// Все VIN уже были в списке совместимости
+    #[serde(rename = "unchanged")]
+    Unchanged,
+    #[serde(rename = "not_found")]
+    NotFound,
+    /// Список совместимости превысил бы limits.max_array_len, запчасть не изменена
+    #[serde(rename = "limit_exceeded")]
+    LimitExceeded,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CompatibleVinsResult {
+    pub part_id: Uuid,
+    pub status: CompatibleVinsStatus,
+    /// VIN, которых раньше не было у запчасти
+    pub added_vins: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_request(purchase_price: f64, sale_price: f64) -> CreatePartRequest {
+        CreatePartRequest {
+            article: "A1".to_string(),
+            name: "Filter".to_string(),
+            brand_id: Uuid::nil(),
+            car_model_id: Uuid::nil(),
+            purchase_price,
+            sale_price,
+            compatible_vins: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn validate_sale_price_rejects_sale_below_purchase() {
+        let error = validate_sale_price(100.0, 99.99).unwrap_err();
+        assert_eq!(error.code, "sale_price");
+    }
+
+    #[test]
+    fn validate_sale_price_accepts_sale_equal_to_purchase() {
+        assert!(validate_sale_price(100.0, 100.0).is_ok());
+    }
+
+    #[test]
+    fn validate_sale_price_accepts_sale_above_purchase() {
+        assert!(validate_sale_price(100.0, 150.0).is_ok());
+    }
+
+    #[test]
+    fn create_request_validation_checks_price_ratio() {
+        let errors = create_request(100.0, 50.0).validate().unwrap_err();
+        assert!(errors.errors().contains_key("__all__"));
+        assert!(create_request(100.0, 100.0).validate().is_ok());
+    }
+
+    #[test]
+    fn update_request_checks_price_ratio_only_when_both_prices_given() {
+        let mut request = UpdatePartRequest {
+            article: None,
+            name: None,
+            brand_id: None,
+            car_model_id: None,
+            purchase_price: Some(100.0),
+            sale_price: Some(50.0),
+            compatible_vins: None,
+        };
+        assert!(request.validate().is_err());
+
+        request.purchase_price = None;
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn catalog_sort_columns_are_whitelisted() {
+        // Колонки и псевдонимы SELECT каталога, которые можно подставлять в ORDER BY
+        let whitelist = ["p.article", "p.name", "p.sale_price", "quantity", "margin_percent"];
+        let sorts = ["article", "name", "sale_price", "quantity", "margin_percent"];
+
+        for sort in sorts {
+            let sort: PartCatalogSort = serde_json::from_value(serde_json::json!(sort)).unwrap();
+            assert!(whitelist.contains(&sort.column()), "{:?} sorts by {}", sort, sort.column());
+        }
+        assert!(serde_json::from_value::<PartCatalogSort>(serde_json::json!("p.id; DROP TABLE parts")).is_err());
+    }
+
+    fn part_stock(article: &str, quantity: Option<i32>) -> PartStock {
+        PartStock {
+            part_id: Uuid::new_v4(),
+            article: article.to_string(),
+            name: "Filter".to_string(),
+            stocked: quantity.is_some(),
+            quantity: quantity.unwrap_or(0),
+            in_stock: quantity.is_some_and(|quantity| quantity > 0),
+        }
+    }
+
+    #[test]
+    fn coverage_is_serviceable_when_any_part_is_in_stock() {
+        // Без складской позиции, с нулевым остатком и в наличии
+        let parts = vec![part_stock("A1", None), part_stock("A2", Some(0)), part_stock("A3", Some(4))];
+
+        let coverage = PartsCoverage::new(Uuid::nil(), Uuid::nil(), parts);
+
+        assert!(coverage.serviceable);
+        assert_eq!(coverage.parts_total, 3);
+        assert_eq!(coverage.parts_in_stock, 1);
+    }
+
+    #[test]
+    fn coverage_without_parts_in_stock_is_not_serviceable() {
+        let coverage = PartsCoverage::new(Uuid::nil(), Uuid::nil(), vec![part_stock("A1", None), part_stock("A2", Some(0))]);
+        assert!(!coverage.serviceable);
+        assert_eq!(coverage.parts_in_stock, 0);
+
+        let empty = PartsCoverage::new(Uuid::nil(), Uuid::nil(), Vec::new());
+        assert!(!empty.serviceable);
+        assert_eq!(empty.parts_total, 0);
+    }
+}
@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use validator::Validate;
 
 use super::enums::RequestStatus;
+use super::money::{serialize_money, serialize_optional_money};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PurchaseRequest {
@@ -11,12 +12,21 @@ pub struct PurchaseRequest {
     pub car_id: Uuid,
     pub customer_id: Uuid,
     pub status: RequestStatus,
+    #[serde(serialize_with = "serialize_optional_money")]
     pub offer_price: Option<f64>,
     pub notes: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Комбинируемые фильтры списка заявок; None - фильтр не применяется
+#[derive(Debug, Default, Clone)]
+pub struct PurchaseFilter {
+    pub status: Option<RequestStatus>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreatePurchaseRequest {
     pub car_id: Uuid,
@@ -24,4 +34,276 @@ pub struct CreatePurchaseRequest {
     #[validate(range(min = 0.0))]
     pub offer_price: Option<f64>,
     pub notes: Option<String>,
-}
\ No newline at end of file
+}
+
+/// Предложенная цена выходит за допустимый процент отклонения от цены автомобиля (вероятно, опечатка)
+#[derive(Debug, Serialize, Clone)]
+pub struct OfferPriceWarning {
+    pub message: String,
+    #[serde(serialize_with = "serialize_money")]
+    pub offer_price: f64,
+    #[serde(serialize_with = "serialize_money")]
+    pub car_price: f64,
+    pub band_percent: f64,
+}
+
+impl OfferPriceWarning {
+    /// `None`, если предложение в пределах `car_price ± band_percent%`
+    pub fn check(offer_price: f64, car_price: f64, band_percent: f64) -> Option<Self> {
+        let deviation = car_price * band_percent / 100.0;
+        if (car_price - deviation..=car_price + deviation).contains(&offer_price) {
+            return None;
+        }
+
+        Some(Self {
+            message: format!("Offer price deviates from the car price by more than {}%", band_percent),
+            offer_price,
+            car_price,
+            band_percent,
+        })
+    }
+}
+
+/// Созданная заявка; `warning` присутствует, если предложенная цена подозрительна
+#[derive(Debug, Serialize, Clone)]
+pub struct CreatedPurchaseRequest {
+    #[serde(flatten)]
+    pub purchase: PurchaseRequest,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<OfferPriceWarning>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MostRequestedCar {
+    pub car_id: Uuid,
+    pub brand_name: String,
+    pub model_name: String,
+    pub year: i32,
+    pub vin: String,
+    #[serde(serialize_with = "serialize_money")]
+    pub price: f64,
+    pub request_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AvgPriceByModel {
+    pub model_id: Uuid,
+    pub model_name: String,
+    pub brand_id: Uuid,
+    pub brand_name: String,
+    #[serde(serialize_with = "serialize_optional_money")]
+    pub avg_price: Option<f64>,
+    pub sales_count: i64,
+}
+
+/// Клиент с суммой завершённых покупок; заявки без offer_price учитываются в количестве, но не в сумме
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopCustomer {
+    pub customer_id: Uuid,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub purchases_count: i64,
+    #[serde(serialize_with = "serialize_money")]
+    pub total_spent: f64,
+}
+
+/// Заявка в статусе Pending старше порога - с данными клиента и автомобиля для обзвона
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StalePurchaseRequest {
+    pub id: Uuid,
+    #[serde(serialize_with = "serialize_optional_money")]
+    pub offer_price: Option<f64>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub days_pending: i32,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub customer_phone: String,
+    pub car_id: Uuid,
+    pub vin: String,
+    pub brand_name: String,
+    pub model_name: String,
+    pub year: i32,
+    #[serde(serialize_with = "serialize_money")]
+    pub price: f64,
+}
+
+/// Самая ранняя заявка в статусе Pending по автомобилю - очередь ответов в порядке поступления
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OldestPendingPurchase {
+    pub id: Uuid,
+    pub car_id: Uuid,
+    pub vin: String,
+    #[serde(serialize_with = "serialize_optional_money")]
+    pub offer_price: Option<f64>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Сколько всего заявок в статусе Pending по этому автомобилю
+    pub pending_count: i64,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_email: String,
+    pub customer_phone: String,
+}
+
+/// Маржа по одной завершённой продаже: цена предложения минус цена автомобиля в карточке
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaleMargin {
+    pub purchase_id: Uuid,
+    pub car_id: Uuid,
+    pub vin: String,
+    pub brand_name: String,
+    pub model_name: String,
+    pub sold_at: DateTime<Utc>,
+    #[serde(serialize_with = "serialize_optional_money")]
+    pub offer_price: Option<f64>,
+    #[serde(serialize_with = "serialize_money")]
+    pub cost_price: f64,
+    /// None, если в заявке не указана цена предложения
+    #[serde(serialize_with = "serialize_optional_money")]
+    pub margin: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SaleMarginsReport {
+    pub sales_count: usize,
+    #[serde(serialize_with = "serialize_money")]
+    pub total_margin: f64,
+    #[serde(serialize_with = "serialize_optional_money")]
+    pub avg_margin: Option<f64>,
+    /// Продажи без offer_price - не участвуют в сумме и среднем
+    pub missing_offer_price_count: usize,
+    pub sales: Vec<SaleMargin>,
+}
+
+impl SaleMarginsReport {
+    pub fn from_sales(sales: Vec<SaleMargin>) -> Self {
+        let margins: Vec<f64> = sales.iter().filter_map(|sale| sale.margin).collect();
+        let total_margin = margins.iter().fold(0.0, |total, margin| total + margin);
+        let avg_margin = if margins.is_empty() {
+            None
+        } else {
+            Some(total_margin / margins.len() as f64)
+        };
+
+        Self {
+            sales_count: margins.len(),
+            total_margin,
+            avg_margin,
+            missing_offer_price_count: sales.len() - margins.len(),
+            sales,
+        }
+    }
+}
+
+/// Размер пакета проверяется в обработчике по MAX_BATCH_SIZE
+/// Средний срок продажи автомобилей бренда: дни от поступления автомобиля (cars.created_at)
+/// до завершения заявки (purchase_requests.updated_at)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BrandDaysToSale {
+    pub brand_id: Uuid,
+    pub brand_name: String,
+    pub sales_count: i64,
+    pub avg_days: Option<f64>,
+    /// Продажи раньше поступления автомобиля (отрицательный срок) - не участвуют в среднем
+    pub excluded_count: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AvgDaysToSaleReport {
+    pub sales_count: i64,
+    pub avg_days: Option<f64>,
+    pub excluded_count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub by_brand: Option<Vec<BrandDaysToSale>>,
+}
+
+impl AvgDaysToSaleReport {
+    pub fn from_brands(brands: Vec<BrandDaysToSale>, by_brand: bool) -> Self {
+        let sales_count = brands.iter().map(|brand| brand.sales_count).sum::<i64>();
+        // Среднее по всем продажам - среднее по брендам, взвешенное числом продаж
+        let total_days = brands.iter()
+            .filter_map(|brand| brand.avg_days.map(|avg| avg * brand.sales_count as f64))
+            .sum::<f64>();
+        let avg_days = if sales_count > 0 {
+            Some(total_days / sales_count as f64)
+        } else {
+            None
+        };
+
+        Self {
+            sales_count,
+            avg_days,
+            excluded_count: brands.iter().map(|brand| brand.excluded_count).sum(),
+            by_brand: by_brand.then_some(brands),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchStatusUpdateRequest {
+    pub ids: Vec<Uuid>,
+    pub status: RequestStatus,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StatusTransitionResult {
+    pub id: Uuid,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_status: Option<RequestStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BatchStatusUpdateResponse {
+    pub status: RequestStatus,
+    pub updated: usize,
+    pub rejected: usize,
+    pub results: Vec<StatusTransitionResult>,
+}
+
+impl BatchStatusUpdateResponse {
+    pub fn new(status: RequestStatus, results: Vec<StatusTransitionResult>) -> Self {
+        let updated = results.iter().filter(|result| result.success).count();
+        Self {
+            status,
+            updated,
+            rejected: results.len() - updated,
+            results,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offer_within_band_has_no_warning() {
+        assert!(OfferPriceWarning::check(1_000_000.0, 1_000_000.0, 20.0).is_none());
+        assert!(OfferPriceWarning::check(850_000.0, 1_000_000.0, 20.0).is_none());
+        assert!(OfferPriceWarning::check(1_150_000.0, 1_000_000.0, 20.0).is_none());
+    }
+
+    #[test]
+    fn offer_on_band_edge_has_no_warning() {
+        // Границы car_price ± band_percent% включаются
+        assert!(OfferPriceWarning::check(800_000.0, 1_000_000.0, 20.0).is_none());
+        assert!(OfferPriceWarning::check(1_200_000.0, 1_000_000.0, 20.0).is_none());
+    }
+
+    #[test]
+    fn offer_outside_band_is_warned_in_both_directions() {
+        let below = OfferPriceWarning::check(799_999.99, 1_000_000.0, 20.0).unwrap();
+        assert_eq!(below.offer_price, 799_999.99);
+        assert_eq!(below.car_price, 1_000_000.0);
+        assert_eq!(below.band_percent, 20.0);
+        assert!(below.message.contains("20%"));
+
+        assert!(OfferPriceWarning::check(1_200_000.01, 1_000_000.0, 20.0).is_some());
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Serialize;
+
+use super::enums::{EnumOption, EnumVariants, FuelType, Transmission};
+
+/// Описание поля формы создания ресурса: тип и ограничения из `#[validate(...)]` модели запроса
+#[derive(Debug, Serialize, Clone)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub field_type: &'static str,
+    pub required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// Для строк - длина, для массивов - число элементов
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Регулярное выражение для строковых полей
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<Vec<EnumOption>>,
+}
+
+impl FieldSchema {
+    fn new(name: &'static str, field_type: &'static str, required: bool) -> Self {
+        Self {
+            name,
+            field_type,
+            required,
+            min: None,
+            max: None,
+            min_length: None,
+            max_length: None,
+            pattern: None,
+            options: None,
+        }
+    }
+
+    fn range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    fn length(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_length = min;
+        self.max_length = max;
+        self
+    }
+
+    fn pattern(mut self, pattern: &'static str) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    fn options<T: EnumVariants>(mut self) -> Self {
+        self.options = Some(T::options());
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ResourceSchema {
+    pub resource: &'static str,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl ResourceSchema {
+    /// Схема запроса на создание ресурса; None - для ресурса схема не описана.
+    /// Описания поддерживаются вручную и должны совпадать с CreateCarRequest и CreatePartRequest;
+    /// max_array_len - настроенный лимит массивных полей (limits.max_array_len).
+    pub fn for_resource(resource: &str, max_array_len: usize) -> Option<Self> {
+        match resource {
+            "cars" => Some(Self::cars()),
+            "parts" => Some(Self::parts(max_array_len)),
+            _ => None,
+        }
+    }
+
+    fn cars() -> Self {
+        Self {
+            resource: "cars",
+            fields: vec![
+                FieldSchema::new("brand_id", "uuid", true),
+                FieldSchema::new("model_id", "uuid", true),
+                FieldSchema::new("year", "integer", true).range(Some(1990.0), Some(2024.0)),
+                FieldSchema::new("price", "number", true).range(Some(0.0), None),
+                FieldSchema::new("mileage", "integer", true),
+                FieldSchema::new("color", "string", true),
+                FieldSchema::new("vin", "string", true).length(Some(17), Some(17)).pattern("^[A-HJ-NPR-Z0-9]{17}$"),
+                FieldSchema::new("fuel_type", "enum", true).options::<FuelType>(),
+                FieldSchema::new("transmission", "enum", true).options::<Transmission>(),
+            ],
+        }
+    }
+
+    fn parts(max_array_len: usize) -> Self {
+        Self {
+            resource: "parts",
+            fields: vec![
+                FieldSchema::new("article", "string", true).length(Some(1), None),
+                FieldSchema::new("name", "string", true).length(Some(1), None),
+                FieldSchema::new("brand_id", "uuid", true),
+                FieldSchema::new("car_model_id", "uuid", true),
+                FieldSchema::new("purchase_price", "number", true).range(Some(0.0), None),
+                FieldSchema::new("sale_price", "number", true).range(Some(0.0), None),
+                FieldSchema::new("compatible_vins", "array", true).length(None, Some(max_array_len)),
+            ],
+        }
+    }
+}
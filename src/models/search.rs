@@ -0,0 +1,65 @@
+use serde::Serialize;
+
+use super::{Car, Customer, Part, ServiceCampaign, Work};
+
+/// Тип ресурса, участвующего в общем поиске
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchType {
+    Cars,
+    Parts,
+    Works,
+    Customers,
+    Campaigns,
+}
+
+impl SearchType {
+    pub const ALL: [SearchType; 5] = [
+        SearchType::Cars,
+        SearchType::Parts,
+        SearchType::Works,
+        SearchType::Customers,
+        SearchType::Campaigns,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "cars" => Some(SearchType::Cars),
+            "parts" => Some(SearchType::Parts),
+            "works" => Some(SearchType::Works),
+            "customers" => Some(SearchType::Customers),
+            "campaigns" => Some(SearchType::Campaigns),
+            _ => None,
+        }
+    }
+
+    /// Разбирает список типов через запятую; пустые элементы пропускаются,
+    /// неизвестный тип возвращается как ошибка
+    pub fn parse_list(value: &str) -> Result<Vec<Self>, String> {
+        let mut types = Vec::new();
+        for item in value.split(',').filter(|item| !item.trim().is_empty()) {
+            let search_type = Self::parse(item).ok_or_else(|| item.trim().to_string())?;
+            if !types.contains(&search_type) {
+                types.push(search_type);
+            }
+        }
+        Ok(types)
+    }
+}
+
+/// Результаты общего поиска, сгруппированные по типам ресурсов.
+/// Невыбранные типы в ответ не попадают.
+#[derive(Debug, Serialize, Default)]
+pub struct SearchResults {
+    pub query: String,
+    pub limit_per_type: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cars: Option<Vec<Car>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parts: Option<Vec<Part>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub works: Option<Vec<Work>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customers: Option<Vec<Customer>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub campaigns: Option<Vec<ServiceCampaign>>,
+}
@@ -3,6 +3,10 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use validator::Validate;
 
+use crate::models::car::Car;
+use crate::models::enums::EnumVariants;
+use crate::models::ArrayFields;
+
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct ServiceCampaign {
     pub id: Uuid,
@@ -33,6 +37,55 @@ pub enum ServiceCampaignStatus {
     Cancelled,
 }
 
+impl ServiceCampaignStatus {
+    /// Статус из строки в БД. Неизвестное значение в строгом режиме (`strict`) - ошибка,
+    /// иначе (по умолчанию, для совместимости) - Active
+    pub fn from_db(value: &str, strict: bool) -> Result<Self, sqlx::Error> {
+        match value.to_lowercase().as_str() {
+            "active" => Ok(ServiceCampaignStatus::Active),
+            "completed" => Ok(ServiceCampaignStatus::Completed),
+            "cancelled" => Ok(ServiceCampaignStatus::Cancelled),
+            _ if strict => Err(sqlx::Error::Decode(
+                format!("unknown service campaign status {:?}", value).into(),
+            )),
+            _ => Ok(ServiceCampaignStatus::Active),
+        }
+    }
+}
+
+impl EnumVariants for ServiceCampaignStatus {
+    fn variants() -> &'static [Self] {
+        &[ServiceCampaignStatus::Active, ServiceCampaignStatus::Completed, ServiceCampaignStatus::Cancelled]
+    }
+
+    fn value(&self) -> &'static str {
+        match self {
+            ServiceCampaignStatus::Active => "active",
+            ServiceCampaignStatus::Completed => "completed",
+            ServiceCampaignStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ServiceCampaignStatus::Active => "Активна",
+            ServiceCampaignStatus::Completed => "Завершена",
+            ServiceCampaignStatus::Cancelled => "Отменена",
+        }
+    }
+}
+
+/// Комбинируемые фильтры списка кампаний; None - фильтр не применяется
+#[derive(Debug, Default, Clone)]
+pub struct ServiceCampaignFilter {
+    pub brand_id: Option<Uuid>,
+    pub car_model_id: Option<Uuid>,
+    pub status: Option<ServiceCampaignStatus>,
+    pub is_mandatory: Option<bool>,
+    pub is_completed: Option<bool>,
+    pub vin: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateServiceCampaignRequest {
     #[validate(length(min = 1))]
@@ -48,6 +101,32 @@ pub struct CreateServiceCampaignRequest {
     pub is_mandatory: bool,
 }
 
+impl ArrayFields for CreateServiceCampaignRequest {
+    fn array_fields(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("target_vins", self.target_vins.len()),
+            ("required_parts", self.required_parts.len()),
+            ("required_works", self.required_works.len()),
+        ]
+    }
+}
+
+impl CreateServiceCampaignRequest {
+    /// Ни запчастей, ни работ - кампания не предполагает никакого обслуживания
+    pub fn is_empty_service(&self) -> bool {
+        self.required_parts.is_empty() && self.required_works.is_empty()
+    }
+}
+
+/// Созданная кампания; `warning` присутствует, если кампания не содержит ни запчастей, ни работ
+#[derive(Debug, Serialize, Clone)]
+pub struct CreatedServiceCampaign {
+    #[serde(flatten)]
+    pub campaign: ServiceCampaign,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct UpdateServiceCampaignRequest {
     pub article: Option<String>,
@@ -61,4 +140,141 @@ pub struct UpdateServiceCampaignRequest {
     pub is_mandatory: Option<bool>,
     pub is_completed: Option<bool>,
     pub status: Option<ServiceCampaignStatus>,
-}
\ No newline at end of file
+}
+
+impl ArrayFields for UpdateServiceCampaignRequest {
+    fn array_fields(&self) -> Vec<(&'static str, usize)> {
+        vec![
+            ("target_vins", self.target_vins.as_ref().map_or(0, Vec::len)),
+            ("required_parts", self.required_parts.as_ref().map_or(0, Vec::len)),
+            ("required_works", self.required_works.as_ref().map_or(0, Vec::len)),
+        ]
+    }
+}
+
+/// Размер пакета проверяется в обработчике по MAX_BATCH_SIZE
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateVinsRequest {
+    pub vins: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VinValidationResult {
+    pub vin: String,
+    pub exists: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub car_id: Option<Uuid>,
+}
+
+/// VIN, попадающий под кампании; `car_id` - автомобиль с этим VIN, если он есть в базе
+#[derive(Debug, Serialize, Clone)]
+pub struct TargetedVin {
+    pub vin: String,
+    pub car_id: Option<Uuid>,
+    pub campaign_ids: Vec<Uuid>,
+}
+
+/// Автомобиль из рабочего списка кампании с признаком, выполнена ли уже кампания на нём
+#[derive(Debug, Serialize, Clone)]
+pub struct CampaignTargetCar {
+    #[serde(flatten)]
+    pub car: Car,
+    pub campaign_completed: bool,
+}
+
+/// Рабочий список кампании: автомобили по target_vins (или вся модель при пустом списке)
+/// и VIN из списка, для которых автомобиль не найден
+#[derive(Debug, Serialize, Clone)]
+pub struct CampaignTargetCars {
+    pub campaign_id: Uuid,
+    pub whole_model: bool,
+    pub cars: Vec<CampaignTargetCar>,
+    pub unmatched_vins: Vec<String>,
+}
+
+/// Результат пересчёта выполнения кампании. Автомобиль подпадает под кампанию, если совпадают
+/// бренд и модель и VIN входит в target_vins (пустой список - вся модель). Кампания без
+/// оставшихся автомобилей считается выполненной, если под неё подпадал хотя бы один автомобиль;
+/// кампания без автомобилей остаётся активной, отменённая не меняется.
+#[derive(Debug, Serialize, Clone)]
+pub struct CampaignCompletion {
+    pub campaign_id: Uuid,
+    pub status: ServiceCampaignStatus,
+    pub is_completed: bool,
+    pub applicable_cars: i64,
+    pub remaining_cars: i64,
+}
+
+/// Невыполненная кампания клиента вместе с автомобилями, к которым она относится
+#[derive(Debug, Serialize, Clone)]
+pub struct CustomerPendingCampaign {
+    #[serde(flatten)]
+    pub campaign: ServiceCampaign,
+    pub car_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkImportQuery {
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub enum BulkImportRowStatus {
+    #[serde(rename = "created")]
+    Created,
+    #[serde(rename = "skipped")]
+    Skipped,
+    #[serde(rename = "failed")]
+    Failed,
+    #[serde(rename = "not_imported")]
+    NotImported,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BulkImportRowResult {
+    pub index: usize,
+    pub article: String,
+    pub status: BulkImportRowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BulkImportResponse {
+    pub created: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub results: Vec<BulkImportRowResult>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_statuses_are_parsed_case_insensitively_in_both_modes() {
+        for strict in [false, true] {
+            assert_eq!(ServiceCampaignStatus::from_db("active", strict).unwrap(), ServiceCampaignStatus::Active);
+            assert_eq!(ServiceCampaignStatus::from_db("Completed", strict).unwrap(), ServiceCampaignStatus::Completed);
+            assert_eq!(ServiceCampaignStatus::from_db("CANCELLED", strict).unwrap(), ServiceCampaignStatus::Cancelled);
+        }
+    }
+
+    #[test]
+    fn unknown_status_falls_back_to_active_in_lenient_mode() {
+        assert_eq!(ServiceCampaignStatus::from_db("archived", false).unwrap(), ServiceCampaignStatus::Active);
+        assert_eq!(ServiceCampaignStatus::from_db("", false).unwrap(), ServiceCampaignStatus::Active);
+    }
+
+    #[test]
+    fn unknown_status_is_a_decode_error_in_strict_mode() {
+        let error = ServiceCampaignStatus::from_db("archived", true).unwrap_err();
+        assert!(matches!(error, sqlx::Error::Decode(_)));
+        assert!(error.to_string().contains("archived"));
+    }
+}
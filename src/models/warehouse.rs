@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use validator::Validate;
 
+use super::money::serialize_money;
+
+/// Уровни запаса новой позиции, если они не заданы ни в запросе, ни для бренда запчасти
+pub const DEFAULT_MIN_STOCK_LEVEL: i32 = 0;
+pub const DEFAULT_MAX_STOCK_LEVEL: i32 = 100;
+
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct WarehouseItem {
     pub id: Uuid,
@@ -32,6 +38,66 @@ pub struct WarehouseItemWithPart {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LowStockPart {
+    pub part_id: Uuid,
+    pub part_article: String,
+    pub part_name: String,
+    pub quantity: i32,
+    pub min_stock_level: i32,
+    pub max_stock_level: i32,
+    pub location: Option<String>,
+    /// Сколько заказать, чтобы довести запас до максимального уровня
+    pub suggested_reorder: i32,
+}
+
+/// Стоимость дозаказа позиции с низким запасом до максимального уровня
+#[derive(Debug, Serialize, Clone)]
+pub struct ReorderCostItem {
+    pub part_id: Uuid,
+    pub part_article: String,
+    pub part_name: String,
+    pub quantity: i32,
+    pub min_stock_level: i32,
+    pub max_stock_level: i32,
+    pub suggested_reorder: i32,
+    #[serde(serialize_with = "serialize_money")]
+    pub purchase_price: f64,
+    #[serde(serialize_with = "serialize_money")]
+    pub reorder_cost: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReorderCost {
+    pub items: Vec<ReorderCostItem>,
+    #[serde(serialize_with = "serialize_money")]
+    pub total_cost: f64,
+}
+
+impl ReorderCost {
+    pub fn new(items: Vec<ReorderCostItem>) -> Self {
+        let total_cost = items.iter().fold(0.0, |total, item| total + item.reorder_cost);
+        Self { items, total_cost }
+    }
+}
+
+/// Сводка по складу для дашборда; для пустого склада все значения нулевые
+#[derive(Debug, Serialize, Clone)]
+pub struct WarehouseStats {
+    /// Позиции с ненулевым остатком
+    pub parts_stocked: i64,
+    pub total_units: i64,
+    #[serde(serialize_with = "serialize_money")]
+    pub value_at_cost: f64,
+    #[serde(serialize_with = "serialize_money")]
+    pub value_at_retail: f64,
+    /// Остаток не выше минимального уровня
+    pub low_stock_items: i64,
+    /// Остаток выше максимального уровня
+    pub overstock_items: i64,
+    pub locations: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct CreateWarehouseItemRequest {
     pub part_id: Uuid,
@@ -55,6 +121,12 @@ pub struct UpdateWarehouseItemRequest {
     pub location: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct UpdateWarehouseLocationRequest {
+    #[validate(length(min = 1, max = 100, message = "Местоположение должно содержать от 1 до 100 символов"))]
+    pub location: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct StockMovementRequest {
     #[validate(range(min = 1, message = "Количество должно быть положительным"))]
@@ -62,6 +134,62 @@ pub struct StockMovementRequest {
     pub movement_type: StockMovementType,
 }
 
+/// Позиция после движения запаса; is_now_low_stock - остаток не выше минимального,
+/// чтобы клиент мог сразу показать предупреждение без отдельного запроса /low-stock
+#[derive(Debug, Serialize, Clone)]
+pub struct StockUpdateResult {
+    #[serde(flatten)]
+    pub item: WarehouseItem,
+    pub is_now_low_stock: bool,
+}
+
+impl From<WarehouseItem> for StockUpdateResult {
+    fn from(item: WarehouseItem) -> Self {
+        let is_now_low_stock = item.quantity <= item.min_stock_level;
+        Self { item, is_now_low_stock }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReservePartItem {
+    pub part_id: Uuid,
+    #[validate(range(min = 1, message = "Количество должно быть положительным"))]
+    pub quantity: i32,
+}
+
+/// Резервирование запчастей под работу: списывается всё или ничего
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReservePartsRequest {
+    #[validate(length(min = 1, max = 100, message = "Ссылка на документ должна содержать от 1 до 100 символов"))]
+    pub reference: String,
+    #[validate(length(max = 255, message = "Причина не должна превышать 255 символов"))]
+    pub reason: Option<String>,
+    /// Число позиций ограничено MAX_BATCH_SIZE, как и в других пакетных операциях (проверка в обработчике)
+    #[validate]
+    pub items: Vec<ReservePartItem>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReservedPart {
+    pub part_id: Uuid,
+    pub quantity: i64,
+    pub remaining: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InsufficientPart {
+    pub part_id: Uuid,
+    pub requested: i64,
+    pub available: i64,
+}
+
+/// Итог резервирования: либо списаны все позиции, либо ничего и список нехватки
+#[derive(Debug, Clone)]
+pub enum PartsReservation {
+    Reserved(Vec<ReservedPart>),
+    Insufficient(Vec<InsufficientPart>),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum StockMovementType {
     #[serde(rename = "incoming")]
@@ -70,4 +198,102 @@ pub enum StockMovementType {
     Outgoing,
     #[serde(rename = "adjustment")]
     Adjustment,
-}
\ No newline at end of file
+}
+
+/// Запись журнала складских движений. movement_type - строка, так как в журнале есть и перемещения
+/// (relocation), которых нет среди типов StockMovementType; resulting_quantity - остаток позиции
+/// после движения (None у записей, сделанных до его учёта)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StockMovement {
+    pub id: Uuid,
+    pub warehouse_item_id: Uuid,
+    pub part_id: Uuid,
+    pub movement_type: String,
+    pub quantity: i32,
+    pub resulting_quantity: Option<i32>,
+    pub from_location: Option<String>,
+    pub to_location: Option<String>,
+    pub reference: Option<String>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarehouseDuplicate {
+    pub part_id: Uuid,
+    pub item_count: i64,
+    pub warehouse_item_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InventorySnapshotSummary {
+    pub snapshot_date: NaiveDate,
+    pub items_count: i64,
+    pub total_value: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SnapshotPartDelta {
+    pub part_id: Uuid,
+    pub part_article: String,
+    pub part_name: String,
+    pub from_quantity: i32,
+    pub to_quantity: i32,
+    pub quantity_change: i32,
+    pub from_value: f64,
+    pub to_value: f64,
+    pub value_change: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InventorySnapshotComparison {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub from_total_value: f64,
+    pub to_total_value: f64,
+    pub total_value_change: f64,
+    pub parts: Vec<SnapshotPartDelta>,
+}
+
+/// Прогноз снижения запасов по средней скорости расхода (исходящие движения) за последние `window_days` дней
+#[derive(Debug, Serialize, Clone)]
+pub struct StockForecast {
+    pub part_id: Uuid,
+    pub quantity: i32,
+    pub min_stock_level: i32,
+    pub window_days: i32,
+    pub outgoing_in_window: i64,
+    pub daily_outgoing_rate: f64,
+    /// Через сколько полных дней запас опустится до минимального уровня; `None` - расхода нет
+    pub days_until_min: Option<i64>,
+    /// Ожидаемый остаток через `window_days` дней при той же скорости расхода
+    pub projected_quantity: i64,
+}
+
+impl StockForecast {
+    pub fn new(part_id: Uuid, quantity: i32, min_stock_level: i32, window_days: i32, outgoing_in_window: i64) -> Self {
+        let daily_outgoing_rate = outgoing_in_window as f64 / window_days as f64;
+
+        let days_until_min = if quantity <= min_stock_level {
+            Some(0)
+        } else if daily_outgoing_rate > 0.0 {
+            Some(((quantity - min_stock_level) as f64 / daily_outgoing_rate).floor() as i64)
+        } else {
+            None
+        };
+
+        let projected = quantity as f64 - daily_outgoing_rate * window_days as f64;
+        let projected_quantity = projected.max(0.0).round() as i64;
+
+        Self {
+            part_id,
+            quantity,
+            min_stock_level,
+            window_days,
+            outgoing_in_window,
+            daily_outgoing_rate,
+            days_until_min,
+            projected_quantity,
+        }
+    }
+}
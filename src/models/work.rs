@@ -3,6 +3,8 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use validator::Validate;
 
+use super::money::serialize_money;
+
 #[derive(Debug, Serialize, Deserialize, Clone, Validate)]
 pub struct Work {
     pub id: Uuid,
@@ -38,4 +40,47 @@ pub struct UpdateWorkRequest {
     pub norm_hours: Option<f64>,
     pub brand_id: Option<Uuid>,
     pub car_model_id: Option<Uuid>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkEstimate {
+    #[serde(flatten)]
+    pub work: Work,
+    #[serde(serialize_with = "serialize_money")]
+    pub estimated_cost: f64,
+}
+
+/// Работы, предусмотренные для модели автомобиля, с оценкой стоимости по ставке нормо-часа
+#[derive(Debug, Serialize, Clone)]
+pub struct ApplicableWorks {
+    pub car_id: Uuid,
+    pub car_model_id: Uuid,
+    #[serde(serialize_with = "serialize_money")]
+    pub labor_hour_rate: f64,
+    pub works: Vec<WorkEstimate>,
+    pub total_norm_hours: f64,
+    #[serde(serialize_with = "serialize_money")]
+    pub total_estimated_cost: f64,
+}
+
+impl ApplicableWorks {
+    pub fn new(car_id: Uuid, car_model_id: Uuid, labor_hour_rate: f64, works: Vec<Work>) -> Self {
+        let works: Vec<WorkEstimate> = works
+            .into_iter()
+            .map(|work| WorkEstimate {
+                estimated_cost: work.norm_hours * labor_hour_rate,
+                work,
+            })
+            .collect();
+        let total_norm_hours = works.iter().fold(0.0, |total, estimate| total + estimate.work.norm_hours);
+
+        Self {
+            car_id,
+            car_model_id,
+            labor_hour_rate,
+            total_estimated_cost: total_norm_hours * labor_hour_rate,
+            total_norm_hours,
+            works,
+        }
+    }
+}
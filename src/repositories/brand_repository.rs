@@ -2,7 +2,11 @@ use async_trait::async_trait;
 use sqlx::Error;
 use uuid::Uuid;
 
-use crate::models::{Brand, CreateBrandRequest, UpdateBrandRequest};
+use crate::models::{
+    Brand, CreateBrandRequest, UpdateBrandRequest, DeletionReport, BrandDependents, DependentRecords,
+    BrandCountryCount, CountryMatch, BrandPriceFloor, SetBrandPriceFloorRequest,
+    BrandStockDefaults, SetBrandStockDefaultsRequest, Country, normalize_country
+};
 use crate::database::DbPool;
 
 #[async_trait]
@@ -10,11 +14,17 @@ pub trait BrandRepository: Send + Sync {
     async fn find_all(&self) -> Result<Vec<Brand>, Error>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Brand>, Error>;
     async fn find_by_name(&self, name: &str) -> Result<Option<Brand>, Error>;
-    async fn find_by_country(&self, country: &str) -> Result<Vec<Brand>, Error>;
+    async fn find_by_country(&self, country: &str, match_mode: CountryMatch) -> Result<Vec<Brand>, Error>;
     async fn exists_by_name(&self, name: &str) -> Result<bool, Error>;
     async fn save(&self, create_request: &CreateBrandRequest) -> Result<Brand, Error>;
     async fn update(&self, id: Uuid, update_request: &UpdateBrandRequest) -> Result<Option<Brand>, Error>;
-    async fn delete(&self, id: Uuid) -> Result<bool, Error>;
+    async fn delete_cascade(&self, id: Uuid, dry_run: bool) -> Result<Option<DeletionReport>, Error>;
+    async fn dependency_report(&self, id: Uuid, sample_size: i64) -> Result<BrandDependents, Error>;
+    async fn country_counts(&self) -> Result<Vec<BrandCountryCount>, Error>;
+    async fn find_price_floor(&self, brand_id: Uuid) -> Result<Option<BrandPriceFloor>, Error>;
+    async fn set_price_floor(&self, brand_id: Uuid, request: &SetBrandPriceFloorRequest) -> Result<Option<BrandPriceFloor>, Error>;
+    async fn find_stock_defaults(&self, brand_id: Uuid) -> Result<Option<BrandStockDefaults>, Error>;
+    async fn set_stock_defaults(&self, brand_id: Uuid, request: &SetBrandStockDefaultsRequest) -> Result<Option<BrandStockDefaults>, Error>;
 }
 
 #[derive(Clone)]
@@ -71,16 +81,50 @@ impl BrandRepository for BrandRepositoryImpl {
             .await
     }
 
-    async fn find_by_country(&self, country: &str) -> Result<Vec<Brand>, Error> {
+    async fn find_by_country(&self, country: &str, match_mode: CountryMatch) -> Result<Vec<Brand>, Error> {
+        if match_mode == CountryMatch::Contains {
+            return sqlx::query_as!(
+                Brand,
+                r#"
+                SELECT id, name, country, created_at, updated_at
+                FROM brands
+                WHERE country ILIKE $1
+                ORDER BY name
+                "#,
+                format!("%{}%", country)
+            )
+                .fetch_all(&self.pool)
+                .await;
+        }
+
+        // Известную страну ищем по каноническому названию, а также по кодам,
+        // которыми могли быть сохранены записи до нормализации
+        if let Some(known) = Country::find(country) {
+            return sqlx::query_as!(
+                Brand,
+                r#"
+                SELECT id, name, country, created_at, updated_at
+                FROM brands
+                WHERE lower(country) = lower($1) OR upper(country) IN ($2, $3)
+                ORDER BY name
+                "#,
+                known.name,
+                known.alpha2,
+                known.alpha3
+            )
+                .fetch_all(&self.pool)
+                .await;
+        }
+
         sqlx::query_as!(
             Brand,
             r#"
             SELECT id, name, country, created_at, updated_at
             FROM brands
-            WHERE country ILIKE $1
+            WHERE lower(country) = lower($1)
             ORDER BY name
             "#,
-            format!("%{}%", country)
+            country.trim()
         )
             .fetch_all(&self.pool)
             .await
@@ -109,7 +153,7 @@ impl BrandRepository for BrandRepositoryImpl {
             "#,
             Uuid::new_v4(),
             create_request.name,
-            create_request.country,
+            normalize_country(&create_request.country).unwrap_or(create_request.country.trim()),
             now,
             now
         )
@@ -130,7 +174,9 @@ impl BrandRepository for BrandRepositoryImpl {
                 RETURNING id, name, country, created_at, updated_at
                 "#,
                 update_request.name.as_ref().unwrap_or(&brand.name),
-                update_request.country.as_ref().unwrap_or(&brand.country),
+                update_request.country.as_deref()
+                    .map(|country| normalize_country(country).unwrap_or(country.trim()))
+                    .unwrap_or(&brand.country),
                 now,
                 id
             )
@@ -143,14 +189,251 @@ impl BrandRepository for BrandRepositoryImpl {
         }
     }
 
-    async fn delete(&self, id: Uuid) -> Result<bool, Error> {
-        let result = sqlx::query(
-            "DELETE FROM brands WHERE id = $1"
-        )
+    async fn delete_cascade(&self, id: Uuid, dry_run: bool) -> Result<Option<DeletionReport>, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists = sqlx::query_scalar!("SELECT id FROM brands WHERE id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Ok(None);
+        }
+
+        let mut report = DeletionReport::new(dry_run);
+
+        // Модели удаляются каскадно (ON DELETE CASCADE), остальные ссылки блокируют удаление
+        report.add_cascaded("car_models", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM car_models WHERE brand_id = $1"#, id
+        ).fetch_one(&mut *tx).await?);
+        report.add_blocker("cars", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM cars
+               WHERE brand_id = $1 OR model_id IN (SELECT id FROM car_models WHERE brand_id = $1)"#, id
+        ).fetch_one(&mut *tx).await?);
+        report.add_blocker("parts", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM parts
+               WHERE brand_id = $1 OR car_model_id IN (SELECT id FROM car_models WHERE brand_id = $1)"#, id
+        ).fetch_one(&mut *tx).await?);
+        report.add_blocker("works", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM works
+               WHERE brand_id = $1 OR car_model_id IN (SELECT id FROM car_models WHERE brand_id = $1)"#, id
+        ).fetch_one(&mut *tx).await?);
+        report.add_blocker("service_campaigns", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM service_campaigns
+               WHERE brand_id = $1 OR car_model_id IN (SELECT id FROM car_models WHERE brand_id = $1)"#, id
+        ).fetch_one(&mut *tx).await?);
+
+        if report.is_blocked() {
+            return Ok(Some(report));
+        }
+
+        let result = sqlx::query("DELETE FROM brands WHERE id = $1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        report.deleted = result.rows_affected();
 
-        Ok(result.rows_affected() > 0)
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+
+        Ok(Some(report))
+    }
+
+    async fn dependency_report(&self, id: Uuid, sample_size: i64) -> Result<BrandDependents, Error> {
+        // Те же условия, что и при каскадном удалении: ссылки через бренд или через его модели
+        let rows = sqlx::query!(
+            r#"
+            WITH models AS (SELECT id FROM car_models WHERE brand_id = $1)
+            SELECT 'car_models' as "entity!", COUNT(*) as "count!",
+                   COALESCE((array_agg(id ORDER BY id))[1:$2], '{}') as "sample_ids!"
+            FROM models
+            UNION ALL
+            SELECT 'cars', COUNT(*), COALESCE((array_agg(id ORDER BY id))[1:$2], '{}')
+            FROM cars WHERE brand_id = $1 OR model_id IN (SELECT id FROM models)
+            UNION ALL
+            SELECT 'parts', COUNT(*), COALESCE((array_agg(id ORDER BY id))[1:$2], '{}')
+            FROM parts WHERE brand_id = $1 OR car_model_id IN (SELECT id FROM models)
+            UNION ALL
+            SELECT 'works', COUNT(*), COALESCE((array_agg(id ORDER BY id))[1:$2], '{}')
+            FROM works WHERE brand_id = $1 OR car_model_id IN (SELECT id FROM models)
+            UNION ALL
+            SELECT 'service_campaigns', COUNT(*), COALESCE((array_agg(id ORDER BY id))[1:$2], '{}')
+            FROM service_campaigns WHERE brand_id = $1 OR car_model_id IN (SELECT id FROM models)
+            "#,
+            id,
+            sample_size as i32
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut report = BrandDependents {
+            brand_id: id,
+            ..Default::default()
+        };
+        for row in rows {
+            let records = DependentRecords {
+                count: row.count,
+                sample_ids: row.sample_ids,
+            };
+            match row.entity.as_str() {
+                "car_models" => report.car_models = records,
+                "cars" => report.cars = records,
+                "parts" => report.parts = records,
+                "works" => report.works = records,
+                "service_campaigns" => report.service_campaigns = records,
+                _ => {}
+            }
+        }
+
+        Ok(report)
     }
-}
\ No newline at end of file
+
+    async fn country_counts(&self) -> Result<Vec<BrandCountryCount>, Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT country, COUNT(*) as "brand_count!"
+            FROM brands
+            GROUP BY country
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        // Записи, созданные до нормализации ("DE", "germany"), сводятся к одной стране
+        let mut counts: Vec<BrandCountryCount> = Vec::new();
+        for row in rows {
+            let known = Country::find(&row.country);
+            let country = known.map(|country| country.name.to_string()).unwrap_or(row.country);
+            match counts.iter_mut().find(|entry| entry.country == country) {
+                Some(entry) => entry.brand_count += row.brand_count,
+                None => counts.push(BrandCountryCount {
+                    country,
+                    code: known.map(|country| country.alpha2.to_string()),
+                    brand_count: row.brand_count,
+                }),
+            }
+        }
+        counts.sort_by(|a, b| a.country.cmp(&b.country));
+
+        Ok(counts)
+    }
+
+    async fn find_price_floor(&self, brand_id: Uuid) -> Result<Option<BrandPriceFloor>, Error> {
+        sqlx::query_as!(
+            BrandPriceFloor,
+            r#"
+            SELECT brand_id, min_car_price, min_part_price, updated_at
+            FROM brand_price_floors
+            WHERE brand_id = $1
+            "#,
+            brand_id
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn set_price_floor(&self, brand_id: Uuid, request: &SetBrandPriceFloorRequest) -> Result<Option<BrandPriceFloor>, Error> {
+        // INSERT ... SELECT из brands: для несуществующего бренда не вставится ни одной строки
+        sqlx::query_as!(
+            BrandPriceFloor,
+            r#"
+            INSERT INTO brand_price_floors (brand_id, min_car_price, min_part_price)
+            SELECT id, $2, $3 FROM brands WHERE id = $1
+            ON CONFLICT (brand_id) DO UPDATE
+            SET min_car_price = EXCLUDED.min_car_price,
+                min_part_price = EXCLUDED.min_part_price,
+                updated_at = NOW()
+            RETURNING brand_id, min_car_price, min_part_price, updated_at
+            "#,
+            brand_id,
+            request.min_car_price,
+            request.min_part_price
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn find_stock_defaults(&self, brand_id: Uuid) -> Result<Option<BrandStockDefaults>, Error> {
+        sqlx::query_as!(
+            BrandStockDefaults,
+            r#"
+            SELECT brand_id, min_stock_level, max_stock_level, updated_at
+            FROM brand_stock_defaults
+            WHERE brand_id = $1
+            "#,
+            brand_id
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn set_stock_defaults(&self, brand_id: Uuid, request: &SetBrandStockDefaultsRequest) -> Result<Option<BrandStockDefaults>, Error> {
+        sqlx::query_as!(
+            BrandStockDefaults,
+            r#"
+            INSERT INTO brand_stock_defaults (brand_id, min_stock_level, max_stock_level)
+            SELECT id, $2, $3 FROM brands WHERE id = $1
+            ON CONFLICT (brand_id) DO UPDATE
+            SET min_stock_level = EXCLUDED.min_stock_level,
+                max_stock_level = EXCLUDED.max_stock_level,
+                updated_at = NOW()
+            RETURNING brand_id, min_stock_level, max_stock_level, updated_at
+            "#,
+            brand_id,
+            request.min_stock_level,
+            request.max_stock_level
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Бренд из заданной страны; у каждого теста свои номера, так как тесты идут параллельно
+    async fn insert_brand(pool: &DbPool, n: u8, country: &str) -> Uuid {
+        let id = Uuid::from_bytes([0xb0, 0xb0, 0xb0, 0xb0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, 1]);
+        remove_brand(pool, id).await;
+        sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, $3)")
+            .bind(id)
+            .bind(format!("Test Brand {}", id))
+            .bind(country)
+            .execute(pool).await.unwrap();
+        id
+    }
+
+    async fn remove_brand(pool: &DbPool, id: Uuid) {
+        sqlx::query("DELETE FROM brands WHERE id = $1")
+            .bind(id)
+            .execute(pool).await.unwrap();
+    }
+
+    fn ids(brands: &[Brand]) -> Vec<Uuid> {
+        brands.iter().map(|brand| brand.id).collect()
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn country_filter_matches_exactly_or_by_substring() {
+        let pool = test_pool().await;
+        let inner = insert_brand(&pool, 1, "Testland").await;
+        let outer = insert_brand(&pool, 2, "Greater Testland").await;
+        let repo = BrandRepositoryImpl::new(pool.clone());
+
+        // Точное совпадение не учитывает регистр, но не находит страну, в названии которой есть искомая
+        let exact = repo.find_by_country("testland", CountryMatch::Exact).await.unwrap();
+        let contains = repo.find_by_country("testland", CountryMatch::Contains).await.unwrap();
+        remove_brand(&pool, inner).await;
+        remove_brand(&pool, outer).await;
+
+        assert!(ids(&exact).contains(&inner));
+        assert!(!ids(&exact).contains(&outer));
+        assert!(ids(&contains).contains(&inner));
+        assert!(ids(&contains).contains(&outer));
+    }
+}
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use sqlx::Error;
 use uuid::Uuid;
 
-use crate::models::{CarModel, CreateCarModelRequest, UpdateCarModelRequest};
+use crate::models::{CarModel, CarModelWithBrand, CarModelDetailed, CarModelWithCount, CreateCarModelRequest, UpdateCarModelRequest, DeletionReport};
 use crate::database::DbPool;
 
 #[async_trait]
@@ -11,10 +11,14 @@ pub trait CarModelRepository: Send + Sync {
     async fn find_by_id(&self, id: Uuid) -> Result<Option<CarModel>, Error>;
     async fn find_by_brand_id(&self, brand_id: Uuid) -> Result<Vec<CarModel>, Error>;
     async fn find_by_name(&self, name: &str) -> Result<Vec<CarModel>, Error>;
+    async fn find_by_name_detailed(&self, name: &str) -> Result<Vec<CarModelDetailed>, Error>;
+    async fn find_by_brand_with_counts(&self, brand_id: Uuid) -> Result<Vec<CarModelWithCount>, Error>;
+    async fn find_with_brand_by_ids(&self, ids: &[Uuid]) -> Result<Vec<CarModelWithBrand>, Error>;
     async fn exists_by_brand_and_name(&self, brand_id: Uuid, name: &str) -> Result<bool, Error>;
+    async fn belongs_to_brand(&self, model_id: Uuid, brand_id: Uuid) -> Result<bool, Error>;
     async fn save(&self, create_request: &CreateCarModelRequest) -> Result<CarModel, Error>;
     async fn update(&self, id: Uuid, update_request: &UpdateCarModelRequest) -> Result<Option<CarModel>, Error>;
-    async fn delete(&self, id: Uuid) -> Result<bool, Error>;
+    async fn delete_cascade(&self, id: Uuid, dry_run: bool) -> Result<Option<DeletionReport>, Error>;
 }
 
 #[derive(Clone)]
@@ -87,6 +91,57 @@ impl CarModelRepository for CarModelRepositoryImpl {
             .await
     }
 
+    async fn find_by_name_detailed(&self, name: &str) -> Result<Vec<CarModelDetailed>, Error> {
+        sqlx::query_as!(
+            CarModelDetailed,
+            r#"
+            SELECT m.id, m.name, m.brand_id, b.name as brand_name, b.country,
+                   m.created_at, m.updated_at
+            FROM car_models m
+            JOIN brands b ON m.brand_id = b.id
+            WHERE m.name ILIKE $1
+            ORDER BY m.name, b.name
+            "#,
+            format!("%{}%", name)
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_by_brand_with_counts(&self, brand_id: Uuid) -> Result<Vec<CarModelWithCount>, Error> {
+        // LEFT JOIN: модели без автомобилей в наличии тоже попадают в список с нулём
+        sqlx::query_as!(
+            CarModelWithCount,
+            r#"
+            SELECT m.id, m.name, COUNT(c.id) as "available_cars!"
+            FROM car_models m
+            LEFT JOIN cars c ON c.model_id = m.id AND c.status = 'Available' AND c.deleted_at IS NULL
+            WHERE m.brand_id = $1
+            GROUP BY m.id, m.name
+            ORDER BY m.name
+            "#,
+            brand_id
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_with_brand_by_ids(&self, ids: &[Uuid]) -> Result<Vec<CarModelWithBrand>, Error> {
+        sqlx::query_as!(
+            CarModelWithBrand,
+            r#"
+            SELECT m.id, m.name, m.brand_id, b.name as brand_name
+            FROM car_models m
+            JOIN brands b ON m.brand_id = b.id
+            WHERE m.id = ANY($1)
+            ORDER BY b.name, m.name
+            "#,
+            ids
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
     async fn exists_by_brand_and_name(&self, brand_id: Uuid, name: &str) -> Result<bool, Error> {
         let result = sqlx::query(
             "SELECT id FROM car_models WHERE brand_id = $1 AND name = $2 LIMIT 1"
@@ -99,6 +154,16 @@ impl CarModelRepository for CarModelRepositoryImpl {
         Ok(result.is_some())
     }
 
+    async fn belongs_to_brand(&self, model_id: Uuid, brand_id: Uuid) -> Result<bool, Error> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM car_models WHERE id = $1 AND brand_id = $2) as "exists!""#,
+            model_id,
+            brand_id
+        )
+            .fetch_one(&self.pool)
+            .await
+    }
+
     async fn save(&self, create_request: &CreateCarModelRequest) -> Result<CarModel, Error> {
         let now = chrono::Utc::now();
 
@@ -145,14 +210,47 @@ impl CarModelRepository for CarModelRepositoryImpl {
         }
     }
 
-    async fn delete(&self, id: Uuid) -> Result<bool, Error> {
-        let result = sqlx::query(
-            "DELETE FROM car_models WHERE id = $1"
-        )
+    async fn delete_cascade(&self, id: Uuid, dry_run: bool) -> Result<Option<DeletionReport>, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists = sqlx::query_scalar!("SELECT id FROM car_models WHERE id = $1 FOR UPDATE", id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Ok(None);
+        }
+
+        let mut report = DeletionReport::new(dry_run);
+
+        report.add_blocker("cars", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM cars WHERE model_id = $1"#, id
+        ).fetch_one(&mut *tx).await?);
+        report.add_blocker("parts", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM parts WHERE car_model_id = $1"#, id
+        ).fetch_one(&mut *tx).await?);
+        report.add_blocker("works", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM works WHERE car_model_id = $1"#, id
+        ).fetch_one(&mut *tx).await?);
+        report.add_blocker("service_campaigns", sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM service_campaigns WHERE car_model_id = $1"#, id
+        ).fetch_one(&mut *tx).await?);
+
+        if report.is_blocked() {
+            return Ok(Some(report));
+        }
+
+        let result = sqlx::query("DELETE FROM car_models WHERE id = $1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        report.deleted = result.rows_affected();
 
-        Ok(result.rows_affected() > 0)
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+
+        Ok(Some(report))
     }
-}
\ No newline at end of file
+}
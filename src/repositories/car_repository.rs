@@ -1,23 +1,41 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::Error;
 use uuid::Uuid;
 
-use crate::models::{Car, CreateCarRequest, UpdateCarRequest, CarStatus, FuelType, Transmission, ServiceCampaign};
+use crate::models::{Car, CreateCarRequest, UpdateCarRequest, CarStatus, FuelType, Transmission, ServiceCampaign, CarChangesCursor, CarExportRow, CarDetail, CarRecord,
+    CarTimelineEntry, CarTimelineEvent, CarFacets, FacetCount, PriceFacet};
 use crate::database::DbPool;
 
 #[async_trait]
 pub trait CarRepository: Send + Sync {
     async fn find_all(&self) -> Result<Vec<Car>, Error>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Car>, Error>;
+    async fn find_detail_by_id(&self, id: Uuid) -> Result<Option<CarDetail>, Error>;
     async fn find_by_status(&self, status: CarStatus) -> Result<Vec<Car>, Error>;
     async fn find_by_brand_id(&self, brand_id: Uuid) -> Result<Vec<Car>, Error>;
     async fn find_by_model_id(&self, model_id: Uuid) -> Result<Vec<Car>, Error>;
     async fn find_by_vin(&self, vin: &str) -> Result<Option<Car>, Error>;
+    async fn find_by_vins(&self, vins: &[String]) -> Result<Vec<Car>, Error>;
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Car>, Error>;
     async fn exists_by_vin(&self, vin: &str) -> Result<bool, Error>;
+    async fn find_id_by_vin(&self, vin: &str) -> Result<Option<Uuid>, Error>;
     async fn save(&self, create_request: &CreateCarRequest) -> Result<Car, Error>;
-    async fn update(&self, id: Uuid, update_request: &UpdateCarRequest) -> Result<Option<Car>, Error>;
+    async fn save_many(&self, create_requests: &[CreateCarRequest]) -> Result<Vec<Car>, Error>;
+    async fn duplicate(&self, id: Uuid, vin: &str, mileage: Option<i32>) -> Result<Option<Car>, Error>;
+    // expected_updated_at - версия из If-Match: запись обновляется, только если не менялась с тех пор (иначе None)
+    async fn update(&self, id: Uuid, update_request: &UpdateCarRequest, expected_updated_at: Option<DateTime<Utc>>) -> Result<Option<Car>, Error>;
     async fn delete(&self, id: Uuid) -> Result<bool, Error>;
-    async fn update_status(&self, id: Uuid, status: CarStatus) -> Result<Option<Car>, Error>;
+    async fn find_including_deleted(&self, id: Uuid) -> Result<Option<CarRecord>, Error>;
+    async fn restore(&self, id: Uuid) -> Result<Option<Car>, Error>;
+    async fn update_status(&self, id: Uuid, status: CarStatus, expected_updated_at: Option<DateTime<Utc>>) -> Result<Option<Car>, Error>;
+    async fn release_expired_reservations(&self, ttl_hours: i32) -> Result<Vec<Uuid>, Error>;
+    async fn find_timeline(&self, car_id: Uuid) -> Result<Option<Vec<CarTimelineEntry>>, Error>;
+    async fn find_changed_after_cursor(&self, cursor: Option<CarChangesCursor>, limit: i64) -> Result<Vec<CarRecord>, Error>;
+    async fn find_recently_updated(&self, hours: i64) -> Result<Vec<Car>, Error>;
+    async fn find_export_batch(&self, after_id: Option<Uuid>, limit: i64) -> Result<Vec<CarExportRow>, Error>;
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Car>, Error>;
+    async fn facets(&self, available_only: bool) -> Result<CarFacets, Error>;
 
     // Новые методы для работы с сервисными кампаниями
     async fn add_completed_campaign(&self, car_id: Uuid, campaign_id: Uuid) -> Result<Option<Car>, Error>;
@@ -25,16 +43,73 @@ pub trait CarRepository: Send + Sync {
     async fn get_cars_by_completed_campaign(&self, campaign_id: Uuid) -> Result<Vec<Car>, Error>;
     async fn get_pending_campaigns_for_car(&self, car_id: Uuid) -> Result<Vec<ServiceCampaign>, Error>;
     async fn clear_completed_campaigns(&self, car_id: Uuid) -> Result<Option<Car>, Error>;
+
+    // Опции автомобиля (ожидаются уже нормализованными)
+    async fn add_feature(&self, car_id: Uuid, feature: &str) -> Result<Option<Car>, Error>;
+    async fn remove_feature(&self, car_id: Uuid, feature: &str) -> Result<Option<Car>, Error>;
+    async fn find_by_feature(&self, feature: &str) -> Result<Vec<Car>, Error>;
+    async fn find_by_price_range(&self, min: Option<f64>, max: Option<f64>) -> Result<Vec<Car>, Error>;
 }
 
 #[derive(Clone)]
 pub struct CarRepositoryImpl {
     pool: DbPool,
+    price_rounding: Option<f64>,
+    max_array_len: Option<usize>,
+    strict_enum_decoding: bool,
 }
 
 impl CarRepositoryImpl {
     pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+        Self { pool, price_rounding: None, max_array_len: None, strict_enum_decoding: false }
+    }
+
+    // Цены при сохранении и обновлении округляются до ближайшего кратного шагу
+    pub fn with_price_rounding(mut self, price_rounding: Option<f64>) -> Self {
+        self.price_rounding = price_rounding;
+        self
+    }
+
+    // Лимит длины массивов при добавлении выполненной кампании или опции; None - без лимита
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.max_array_len = Some(max_array_len);
+        self
+    }
+
+    // Неизвестный статус кампании в строке БД - ошибка декодирования, а не Active
+    pub fn with_strict_enum_decoding(mut self, strict: bool) -> Self {
+        self.strict_enum_decoding = strict;
+        self
+    }
+
+    // Цена в том виде, в каком она будет записана: по ней же проверяется минимальная цена бренда
+    pub fn round_price(&self, price: f64) -> f64 {
+        round_to_increment(price, self.price_rounding)
+    }
+}
+
+// Половина шага округляется вверх (f64::round - от нуля)
+fn round_to_increment(price: f64, increment: Option<f64>) -> f64 {
+    match increment {
+        Some(increment) => (price / increment).round() * increment,
+        None => price,
+    }
+}
+
+fn fuel_type_str(fuel_type: &FuelType) -> &'static str {
+    match fuel_type {
+        FuelType::Petrol => "Petrol",
+        FuelType::Diesel => "Diesel",
+        FuelType::Electric => "Electric",
+        FuelType::Hybrid => "Hybrid",
+    }
+}
+
+fn transmission_str(transmission: &Transmission) -> &'static str {
+    match transmission {
+        Transmission::Manual => "Manual",
+        Transmission::Automatic => "Automatic",
+        Transmission::CVT => "CVT",
     }
 }
 
@@ -46,8 +121,9 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             SELECT id, brand_id, model_id, year, price, mileage, color, vin,
                    fuel_type as "fuel_type: _", transmission as "transmission: _",
-                   status as "status: _", completed_service_campaigns, created_at, updated_at
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
             FROM cars
+            WHERE deleted_at IS NULL
             ORDER BY created_at DESC
             "#
         )
@@ -61,9 +137,28 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             SELECT id, brand_id, model_id, year, price, mileage, color, vin,
                    fuel_type as "fuel_type: _", transmission as "transmission: _",
-                   status as "status: _", completed_service_campaigns, created_at, updated_at
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
             FROM cars
-            WHERE id = $1
+            WHERE deleted_at IS NULL AND id = $1
+            "#,
+            id
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn find_detail_by_id(&self, id: Uuid) -> Result<Option<CarDetail>, Error> {
+        sqlx::query_as!(
+            CarDetail,
+            r#"
+            SELECT c.id, c.brand_id, b.name as brand_name, b.country, c.model_id, m.name as model_name,
+                   c.year, c.price, c.mileage, c.color, c.vin,
+                   c.fuel_type as "fuel_type: _", c.transmission as "transmission: _",
+                   c.status as "status: _", c.completed_service_campaigns, c.features, c.created_at, c.updated_at
+            FROM cars c
+            JOIN brands b ON c.brand_id = b.id
+            JOIN car_models m ON c.model_id = m.id
+            WHERE c.id = $1 AND c.deleted_at IS NULL
             "#,
             id
         )
@@ -77,9 +172,9 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             SELECT id, brand_id, model_id, year, price, mileage, color, vin,
                    fuel_type as "fuel_type: _", transmission as "transmission: _",
-                   status as "status: _", completed_service_campaigns, created_at, updated_at
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
             FROM cars
-            WHERE status = $1
+            WHERE deleted_at IS NULL AND status = $1
             ORDER BY created_at DESC
             "#,
             status as CarStatus
@@ -94,9 +189,9 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             SELECT id, brand_id, model_id, year, price, mileage, color, vin,
                    fuel_type as "fuel_type: _", transmission as "transmission: _",
-                   status as "status: _", completed_service_campaigns, created_at, updated_at
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
             FROM cars
-            WHERE brand_id = $1
+            WHERE deleted_at IS NULL AND brand_id = $1
             ORDER BY created_at DESC
             "#,
             brand_id
@@ -111,9 +206,9 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             SELECT id, brand_id, model_id, year, price, mileage, color, vin,
                    fuel_type as "fuel_type: _", transmission as "transmission: _",
-                   status as "status: _", completed_service_campaigns, created_at, updated_at
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
             FROM cars
-            WHERE model_id = $1
+            WHERE deleted_at IS NULL AND model_id = $1
             ORDER BY created_at DESC
             "#,
             model_id
@@ -128,9 +223,9 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             SELECT id, brand_id, model_id, year, price, mileage, color, vin,
                    fuel_type as "fuel_type: _", transmission as "transmission: _",
-                   status as "status: _", completed_service_campaigns, created_at, updated_at
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
             FROM cars
-            WHERE vin = $1
+            WHERE deleted_at IS NULL AND vin = $1
             "#,
             vin
         )
@@ -138,6 +233,40 @@ impl CarRepository for CarRepositoryImpl {
             .await
     }
 
+    async fn find_by_vins(&self, vins: &[String]) -> Result<Vec<Car>, Error> {
+        sqlx::query_as!(
+            Car,
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: _", transmission as "transmission: _",
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            FROM cars
+            WHERE deleted_at IS NULL AND vin = ANY($1)
+            ORDER BY created_at DESC
+            "#,
+            vins
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Car>, Error> {
+        sqlx::query_as!(
+            Car,
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: _", transmission as "transmission: _",
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            FROM cars
+            WHERE deleted_at IS NULL AND id = ANY($1)
+            "#,
+            ids
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Учитывает и удалённые автомобили: VIN уникален в таблице, удалённый автомобиль можно восстановить
     async fn exists_by_vin(&self, vin: &str) -> Result<bool, Error> {
         let result = sqlx::query(
             "SELECT id FROM cars WHERE vin = $1 LIMIT 1"
@@ -149,22 +278,18 @@ impl CarRepository for CarRepositoryImpl {
         Ok(result.is_some())
     }
 
+    // VIN уникален среди всех записей, включая удалённые, поэтому deleted_at здесь не проверяется
+    async fn find_id_by_vin(&self, vin: &str) -> Result<Option<Uuid>, Error> {
+        sqlx::query_scalar!("SELECT id FROM cars WHERE vin = $1 LIMIT 1", vin)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
     async fn save(&self, create_request: &CreateCarRequest) -> Result<Car, Error> {
         let now = chrono::Utc::now();
 
-        let fuel_type_str = match create_request.fuel_type {
-            FuelType::Petrol => "Petrol",
-            FuelType::Diesel => "Diesel",
-            FuelType::Electric => "Electric",
-            FuelType::Hybrid => "Hybrid",
-        };
-
-        let transmission_str = match create_request.transmission {
-            Transmission::Manual => "Manual",
-            Transmission::Automatic => "Automatic",
-            Transmission::CVT => "CVT",
-        };
-
+        let fuel_type_str = fuel_type_str(&create_request.fuel_type);
+        let transmission_str = transmission_str(&create_request.transmission);
         let status_str = "Available";
 
         sqlx::query_as!(
@@ -175,13 +300,13 @@ impl CarRepository for CarRepositoryImpl {
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
                      fuel_type as "fuel_type: _", transmission as "transmission: _",
-                     status as "status: _", completed_service_campaigns, created_at, updated_at
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
             "#,
             Uuid::new_v4(),
             create_request.brand_id,
             create_request.model_id,
             create_request.year,
-            create_request.price,
+            self.round_price(create_request.price),
             create_request.mileage,
             create_request.color,
             create_request.vin,
@@ -195,24 +320,78 @@ impl CarRepository for CarRepositoryImpl {
             .await
     }
 
-    async fn update(&self, id: Uuid, update_request: &UpdateCarRequest) -> Result<Option<Car>, Error> {
+    async fn save_many(&self, create_requests: &[CreateCarRequest]) -> Result<Vec<Car>, Error> {
         let now = chrono::Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let mut cars = Vec::with_capacity(create_requests.len());
 
-        if let Some(car) = self.find_by_id(id).await? {
-            let fuel_type = update_request.fuel_type.as_ref().unwrap_or(&car.fuel_type);
-            let fuel_type_str = match fuel_type {
-                FuelType::Petrol => "Petrol",
-                FuelType::Diesel => "Diesel",
-                FuelType::Electric => "Electric",
-                FuelType::Hybrid => "Hybrid",
-            };
+        // Все автомобили сохраняются в одной транзакции: ошибка любой строки откатывает весь пакет
+        for create_request in create_requests {
+            let car = sqlx::query_as!(
+                Car,
+                r#"
+                INSERT INTO cars (id, brand_id, model_id, year, price, mileage, color, vin,
+                                fuel_type, transmission, status, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, 'Available', $11, $11)
+                RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
+                         fuel_type as "fuel_type: _", transmission as "transmission: _",
+                         status as "status: _", completed_service_campaigns, features, created_at, updated_at
+                "#,
+                Uuid::new_v4(),
+                create_request.brand_id,
+                create_request.model_id,
+                create_request.year,
+                self.round_price(create_request.price),
+                create_request.mileage,
+                create_request.color,
+                create_request.vin,
+                fuel_type_str(&create_request.fuel_type),
+                transmission_str(&create_request.transmission),
+                now
+            )
+                .fetch_one(&mut *tx)
+                .await?;
 
-            let transmission = update_request.transmission.as_ref().unwrap_or(&car.transmission);
-            let transmission_str = match transmission {
-                Transmission::Manual => "Manual",
-                Transmission::Automatic => "Automatic",
-                Transmission::CVT => "CVT",
-            };
+            cars.push(car);
+        }
+
+        tx.commit().await?;
+        Ok(cars)
+    }
+
+    async fn duplicate(&self, id: Uuid, vin: &str, mileage: Option<i32>) -> Result<Option<Car>, Error> {
+        let now = chrono::Utc::now();
+
+        // Статус, выполненные кампании и опции исходного автомобиля не переносятся
+        sqlx::query_as!(
+            Car,
+            r#"
+            INSERT INTO cars (id, brand_id, model_id, year, price, mileage, color, vin,
+                            fuel_type, transmission, status, created_at, updated_at)
+            SELECT $1, brand_id, model_id, year, price, $2, color, $3,
+                   fuel_type, transmission, 'Available', $4, $4
+            FROM cars
+            WHERE id = $5 AND deleted_at IS NULL
+            RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
+                     fuel_type as "fuel_type: _", transmission as "transmission: _",
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            "#,
+            Uuid::new_v4(),
+            mileage.unwrap_or(0),
+            vin,
+            now,
+            id
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn update(&self, id: Uuid, update_request: &UpdateCarRequest, expected_updated_at: Option<DateTime<Utc>>) -> Result<Option<Car>, Error> {
+        let now = chrono::Utc::now();
+
+        if let Some(car) = self.find_by_id(id).await? {
+            let fuel_type_str = fuel_type_str(update_request.fuel_type.as_ref().unwrap_or(&car.fuel_type));
+            let transmission_str = transmission_str(update_request.transmission.as_ref().unwrap_or(&car.transmission));
 
             let status = update_request.status.as_ref().unwrap_or(&car.status);
             let status_str = match status {
@@ -229,15 +408,15 @@ impl CarRepository for CarRepositoryImpl {
                 SET brand_id = $1, model_id = $2, year = $3, price = $4, mileage = $5,
                     color = $6, vin = $7, fuel_type = $8, transmission = $9, status = $10,
                     completed_service_campaigns = $11, updated_at = $12
-                WHERE id = $13
+                WHERE id = $13 AND deleted_at IS NULL AND ($14::timestamptz IS NULL OR updated_at = $14)
                 RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
                          fuel_type as "fuel_type: _", transmission as "transmission: _",
-                         status as "status: _", completed_service_campaigns, created_at, updated_at
+                         status as "status: _", completed_service_campaigns, features, created_at, updated_at
                 "#,
                 update_request.brand_id.unwrap_or(car.brand_id),
                 update_request.model_id.unwrap_or(car.model_id),
                 update_request.year.unwrap_or(car.year),
-                update_request.price.unwrap_or(car.price),
+                update_request.price.map(|price| self.round_price(price)).unwrap_or(car.price),
                 update_request.mileage.unwrap_or(car.mileage),
                 update_request.color.as_ref().unwrap_or(&car.color),
                 update_request.vin.as_ref().unwrap_or(&car.vin),
@@ -246,7 +425,8 @@ impl CarRepository for CarRepositoryImpl {
                 status_str,
                 update_request.completed_service_campaigns.as_ref().unwrap_or(&car.completed_service_campaigns),
                 now,
-                id
+                id,
+                expected_updated_at
             )
                 .fetch_optional(&self.pool)
                 .await?;
@@ -257,18 +437,87 @@ impl CarRepository for CarRepositoryImpl {
         }
     }
 
+    // Мягкое удаление: автомобиль помечается deleted_at и скрывается из выборок, заявки и история сохраняются.
+    // Незавершённые заявки (Pending, Approved) отклоняются в том же запросе - по удалённому автомобилю сделки нет
     async fn delete(&self, id: Uuid) -> Result<bool, Error> {
-        let result = sqlx::query(
-            "DELETE FROM cars WHERE id = $1"
+        let deleted: i64 = sqlx::query_scalar(
+            r#"
+            WITH deleted AS (
+                UPDATE cars
+                SET deleted_at = NOW(), updated_at = NOW()
+                WHERE id = $1 AND deleted_at IS NULL
+                RETURNING id
+            ), rejected AS (
+                UPDATE purchase_requests
+                SET status = 'Rejected', updated_at = NOW()
+                WHERE car_id IN (SELECT id FROM deleted) AND status IN ('Pending', 'Approved')
+            )
+            SELECT COUNT(*) FROM deleted
+            "#
         )
             .bind(id)
-            .execute(&self.pool)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn find_including_deleted(&self, id: Uuid) -> Result<Option<CarRecord>, Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: FuelType", transmission as "transmission: Transmission",
+                   status as "status: CarStatus", completed_service_campaigns, features, created_at, updated_at,
+                   deleted_at
+            FROM cars
+            WHERE id = $1
+            "#,
+            id
+        )
+            .fetch_optional(&self.pool)
             .await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(row.map(|row| CarRecord {
+            car: Car {
+                id: row.id,
+                brand_id: row.brand_id,
+                model_id: row.model_id,
+                year: row.year,
+                price: row.price,
+                mileage: row.mileage,
+                color: row.color,
+                vin: row.vin,
+                fuel_type: row.fuel_type,
+                transmission: row.transmission,
+                status: row.status,
+                completed_service_campaigns: row.completed_service_campaigns,
+                features: row.features,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            },
+            deleted_at: row.deleted_at,
+        }))
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<Car>, Error> {
+        sqlx::query_as!(
+            Car,
+            r#"
+            UPDATE cars
+            SET deleted_at = NULL, updated_at = $1
+            WHERE id = $2 AND deleted_at IS NOT NULL
+            RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
+                     fuel_type as "fuel_type: _", transmission as "transmission: _",
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            "#,
+            chrono::Utc::now(),
+            id
+        )
+            .fetch_optional(&self.pool)
+            .await
     }
 
-    async fn update_status(&self, id: Uuid, status: CarStatus) -> Result<Option<Car>, Error> {
+    async fn update_status(&self, id: Uuid, status: CarStatus, expected_updated_at: Option<DateTime<Utc>>) -> Result<Option<Car>, Error> {
         let now = chrono::Utc::now();
 
         let status_str = match status {
@@ -283,14 +532,15 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             UPDATE cars
             SET status = $1, updated_at = $2
-            WHERE id = $3
+            WHERE id = $3 AND deleted_at IS NULL AND ($4::timestamptz IS NULL OR updated_at = $4)
             RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
                      fuel_type as "fuel_type: _", transmission as "transmission: _",
-                     status as "status: _", completed_service_campaigns, created_at, updated_at
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
             "#,
             status_str,
             now,
-            id
+            id,
+            expected_updated_at
         )
             .fetch_optional(&self.pool)
             .await
@@ -298,8 +548,207 @@ impl CarRepository for CarRepositoryImpl {
 
     // НОВЫЕ МЕТОДЫ ДЛЯ СЕРВИСНЫХ КАМПАНИЙ
 
+    async fn release_expired_reservations(&self, ttl_hours: i32) -> Result<Vec<Uuid>, Error> {
+        let now = chrono::Utc::now();
+
+        // Резерв под одобренную заявку не снимаем - автомобиль ждёт завершения сделки
+        sqlx::query_scalar!(
+            r#"
+            UPDATE cars
+            SET status = 'Available', updated_at = $1
+            WHERE status = 'Reserved'
+              AND deleted_at IS NULL
+              AND updated_at < $1::timestamptz - make_interval(hours => $2)
+              AND NOT EXISTS (
+                  SELECT 1 FROM purchase_requests pr
+                  WHERE pr.car_id = cars.id AND pr.status = 'Approved'
+              )
+            RETURNING id
+            "#,
+            now,
+            ttl_hours
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_timeline(&self, car_id: Uuid) -> Result<Option<Vec<CarTimelineEntry>>, Error> {
+        let Some(car) = self.find_by_id(car_id).await? else {
+            return Ok(None);
+        };
+
+        // Изменения цены, статуса и выполненные кампании (журнал ведёт триггер на cars)
+        let history = sqlx::query!(
+            r#"
+            SELECT h.event_type, h.old_price, h.new_price, h.old_status, h.new_status,
+                   h.campaign_id, sc.name as "campaign_name?", h.created_at
+            FROM car_history h
+            LEFT JOIN service_campaigns sc ON h.campaign_id = sc.id
+            WHERE h.car_id = $1
+            ORDER BY h.created_at
+            "#,
+            car_id
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        // Цена при создании - исходная цена первого изменения, если цену меняли
+        let initial_price = history
+            .iter()
+            .find(|row| row.event_type == "price_changed")
+            .and_then(|row| row.old_price)
+            .unwrap_or(car.price);
+        let mut timeline = vec![CarTimelineEntry {
+            occurred_at: car.created_at,
+            event: CarTimelineEvent::Created { price: initial_price },
+        }];
+
+        timeline.extend(history.into_iter().filter_map(|row| {
+            let event = match row.event_type.as_str() {
+                "price_changed" => CarTimelineEvent::PriceChanged {
+                    old_price: row.old_price,
+                    new_price: row.new_price,
+                },
+                "status_changed" => CarTimelineEvent::StatusChanged {
+                    from: row.old_status,
+                    to: row.new_status,
+                },
+                "campaign_completed" => CarTimelineEvent::CampaignCompleted {
+                    campaign_id: row.campaign_id,
+                    campaign_name: row.campaign_name,
+                },
+                _ => return None,
+            };
+            Some(CarTimelineEntry { occurred_at: row.created_at, event })
+        }));
+
+        // Предложения покупателей и продажа (завершённая заявка)
+        let purchases = sqlx::query!(
+            r#"
+            SELECT id, customer_id, status, offer_price, created_at, updated_at
+            FROM purchase_requests
+            WHERE car_id = $1
+            "#,
+            car_id
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        for purchase in purchases {
+            timeline.push(CarTimelineEntry {
+                occurred_at: purchase.created_at,
+                event: CarTimelineEvent::OfferReceived {
+                    purchase_id: purchase.id,
+                    customer_id: purchase.customer_id,
+                    offer_price: purchase.offer_price,
+                },
+            });
+            if purchase.status == "Completed" {
+                timeline.push(CarTimelineEntry {
+                    occurred_at: purchase.updated_at,
+                    event: CarTimelineEvent::Sold {
+                        purchase_id: purchase.id,
+                        customer_id: purchase.customer_id,
+                        offer_price: purchase.offer_price,
+                    },
+                });
+            }
+        }
+
+        // Стабильная сортировка: при равном времени сохраняется порядок добавления
+        timeline.sort_by_key(|entry| entry.occurred_at);
+
+        Ok(Some(timeline))
+    }
+
+    async fn find_changed_after_cursor(&self, cursor: Option<CarChangesCursor>, limit: i64) -> Result<Vec<CarRecord>, Error> {
+        // Keyset-пагинация по (updated_at, id): новые вставки не сдвигают уже отданные страницы.
+        // Удалённые автомобили тоже попадают в ленту (удаление обновляет updated_at) - по deleted_at потребитель убирает их у себя
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: FuelType", transmission as "transmission: Transmission",
+                   status as "status: CarStatus", completed_service_campaigns, features, created_at, updated_at,
+                   deleted_at
+            FROM cars
+            WHERE $1::timestamptz IS NULL OR (updated_at, id) > ($1, $2::uuid)
+            ORDER BY updated_at, id
+            LIMIT $3
+            "#,
+            cursor.map(|c| c.updated_at),
+            cursor.map(|c| c.id),
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| CarRecord {
+            car: Car {
+                id: row.id,
+                brand_id: row.brand_id,
+                model_id: row.model_id,
+                year: row.year,
+                price: row.price,
+                mileage: row.mileage,
+                color: row.color,
+                vin: row.vin,
+                fuel_type: row.fuel_type,
+                transmission: row.transmission,
+                status: row.status,
+                completed_service_campaigns: row.completed_service_campaigns,
+                features: row.features,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            },
+            deleted_at: row.deleted_at,
+        }).collect())
+    }
+
+    async fn find_recently_updated(&self, hours: i64) -> Result<Vec<Car>, Error> {
+        let since = chrono::Utc::now() - chrono::Duration::hours(hours);
+
+        sqlx::query_as!(
+            Car,
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: _", transmission as "transmission: _",
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            FROM cars
+            WHERE deleted_at IS NULL AND updated_at >= $1
+            ORDER BY updated_at DESC
+            "#,
+            since
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_export_batch(&self, after_id: Option<Uuid>, limit: i64) -> Result<Vec<CarExportRow>, Error> {
+        sqlx::query_as!(
+            CarExportRow,
+            r#"
+            SELECT c.id, c.brand_id, c.model_id,
+                   b.name as "brand_name?", m.name as "model_name?",
+                   c.year, c.price, c.mileage, c.color, c.vin,
+                   c.fuel_type as "fuel_type: _", c.transmission as "transmission: _",
+                   c.status as "status: _", c.completed_service_campaigns, c.features, c.created_at, c.updated_at
+            FROM cars c
+            LEFT JOIN brands b ON c.brand_id = b.id
+            LEFT JOIN car_models m ON c.model_id = m.id
+            WHERE c.deleted_at IS NULL AND ($1::uuid IS NULL OR c.id > $1)
+            ORDER BY c.id
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
     async fn add_completed_campaign(&self, car_id: Uuid, campaign_id: Uuid) -> Result<Option<Car>, Error> {
         let now = chrono::Utc::now();
+        let max_array_len = self.max_array_len.map(|max| max as i64);
 
         sqlx::query_as!(
             Car,
@@ -307,15 +756,17 @@ impl CarRepository for CarRepositoryImpl {
             UPDATE cars
             SET completed_service_campaigns = array_append(completed_service_campaigns, $1),
                 updated_at = $2
-            WHERE id = $3
+            WHERE id = $3 AND deleted_at IS NULL
             AND NOT $1 = ANY(completed_service_campaigns)
+            AND ($4::int8 IS NULL OR cardinality(completed_service_campaigns) < $4)
             RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
                      fuel_type as "fuel_type: _", transmission as "transmission: _",
-                     status as "status: _", completed_service_campaigns, created_at, updated_at
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
             "#,
             campaign_id,
             now,
-            car_id
+            car_id,
+            max_array_len
         )
             .fetch_optional(&self.pool)
             .await
@@ -330,10 +781,10 @@ impl CarRepository for CarRepositoryImpl {
             UPDATE cars
             SET completed_service_campaigns = array_remove(completed_service_campaigns, $1),
                 updated_at = $2
-            WHERE id = $3
+            WHERE id = $3 AND deleted_at IS NULL
             RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
                      fuel_type as "fuel_type: _", transmission as "transmission: _",
-                     status as "status: _", completed_service_campaigns, created_at, updated_at
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
             "#,
             campaign_id,
             now,
@@ -352,10 +803,10 @@ impl CarRepository for CarRepositoryImpl {
             UPDATE cars
             SET completed_service_campaigns = '{}',
                 updated_at = $1
-            WHERE id = $2
+            WHERE id = $2 AND deleted_at IS NULL
             RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
                      fuel_type as "fuel_type: _", transmission as "transmission: _",
-                     status as "status: _", completed_service_campaigns, created_at, updated_at
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
             "#,
             now,
             car_id
@@ -370,9 +821,9 @@ impl CarRepository for CarRepositoryImpl {
             r#"
             SELECT id, brand_id, model_id, year, price, mileage, color, vin,
                    fuel_type as "fuel_type: _", transmission as "transmission: _",
-                   status as "status: _", completed_service_campaigns, created_at, updated_at
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
             FROM cars
-            WHERE $1 = ANY(completed_service_campaigns)
+            WHERE deleted_at IS NULL AND $1 = ANY(completed_service_campaigns)
             ORDER BY created_at DESC
             "#,
             campaign_id
@@ -412,15 +863,10 @@ impl CarRepository for CarRepositoryImpl {
             .await?;
 
         // Ручное преобразование в ServiceCampaign
-        let campaigns = rows.into_iter().map(|row| {
-            let status = match row.status.as_str() {
-                "active" => crate::models::ServiceCampaignStatus::Active,
-                "completed" => crate::models::ServiceCampaignStatus::Completed,
-                "cancelled" => crate::models::ServiceCampaignStatus::Cancelled,
-                _ => crate::models::ServiceCampaignStatus::Active,
-            };
+        rows.into_iter().map(|row| {
+            let status = crate::models::ServiceCampaignStatus::from_db(&row.status, self.strict_enum_decoding)?;
 
-            ServiceCampaign {
+            Ok(ServiceCampaign {
                 id: row.id,
                 article: row.article,
                 name: row.name,
@@ -435,9 +881,298 @@ impl CarRepository for CarRepositoryImpl {
                 status,
                 created_at: row.created_at,
                 updated_at: row.updated_at,
+            })
+        }).collect()
+    }
+
+    async fn add_feature(&self, car_id: Uuid, feature: &str) -> Result<Option<Car>, Error> {
+        let now = chrono::Utc::now();
+
+        let max_array_len = self.max_array_len.map(|max| max as i64);
+
+        // Повторное добавление не дублирует опцию и не меняет updated_at; новая опция сверх лимита не добавляется
+        sqlx::query_as!(
+            Car,
+            r#"
+            UPDATE cars
+            SET features = CASE WHEN $1 = ANY(features) THEN features ELSE array_append(features, $1) END,
+                updated_at = CASE WHEN $1 = ANY(features) THEN updated_at ELSE $2 END
+            WHERE id = $3 AND deleted_at IS NULL
+            AND ($1 = ANY(features) OR $4::int8 IS NULL OR cardinality(features) < $4)
+            RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
+                     fuel_type as "fuel_type: _", transmission as "transmission: _",
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            "#,
+            feature,
+            now,
+            car_id,
+            max_array_len
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn remove_feature(&self, car_id: Uuid, feature: &str) -> Result<Option<Car>, Error> {
+        let now = chrono::Utc::now();
+
+        sqlx::query_as!(
+            Car,
+            r#"
+            UPDATE cars
+            SET features = array_remove(features, $1),
+                updated_at = CASE WHEN $1 = ANY(features) THEN $2 ELSE updated_at END
+            WHERE id = $3 AND deleted_at IS NULL
+            RETURNING id, brand_id, model_id, year, price, mileage, color, vin,
+                     fuel_type as "fuel_type: _", transmission as "transmission: _",
+                     status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            "#,
+            feature,
+            now,
+            car_id
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn find_by_feature(&self, feature: &str) -> Result<Vec<Car>, Error> {
+        sqlx::query_as!(
+            Car,
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: _", transmission as "transmission: _",
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            FROM cars
+            WHERE deleted_at IS NULL AND $1 = ANY(features)
+            ORDER BY created_at DESC
+            "#,
+            feature
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Границы включительные; отсутствующая граница заменяется на 0 / +Infinity, а не на `$1 IS NULL OR ...`,
+    // чтобы условие оставалось простым диапазоном и планировщик мог использовать idx_cars_price
+    async fn find_by_price_range(&self, min: Option<f64>, max: Option<f64>) -> Result<Vec<Car>, Error> {
+        sqlx::query_as!(
+            Car,
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: _", transmission as "transmission: _",
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            FROM cars
+            WHERE deleted_at IS NULL AND price BETWEEN $1 AND $2
+            ORDER BY price, id
+            "#,
+            min.unwrap_or(0.0),
+            max.unwrap_or(f64::INFINITY)
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Car>, Error> {
+        sqlx::query_as!(
+            Car,
+            r#"
+            SELECT id, brand_id, model_id, year, price, mileage, color, vin,
+                   fuel_type as "fuel_type: _", transmission as "transmission: _",
+                   status as "status: _", completed_service_campaigns, features, created_at, updated_at
+            FROM cars
+            WHERE deleted_at IS NULL
+              AND (vin ILIKE $1
+                   OR color ILIKE $1
+                   OR brand_id IN (SELECT id FROM brands WHERE name ILIKE $1)
+                   OR model_id IN (SELECT id FROM car_models WHERE name ILIKE $1))
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            format!("%{}%", query),
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn facets(&self, available_only: bool) -> Result<CarFacets, Error> {
+        // Один проход по таблице: строка на каждое значение fuel_type, transmission и status
+        // плюс итоговая строка (все колонки NULL) с общим количеством и диапазоном цен
+        let rows = sqlx::query!(
+            r#"
+            SELECT fuel_type as "fuel_type?", transmission as "transmission?", status as "status?",
+                   COUNT(*) as "count!", MIN(price) as min_price, MAX(price) as max_price
+            FROM cars
+            WHERE deleted_at IS NULL AND (NOT $1 OR status = 'Available')
+            GROUP BY GROUPING SETS ((fuel_type), (transmission), (status), ())
+            ORDER BY COUNT(*) DESC, fuel_type, transmission, status
+            "#,
+            available_only
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut facets = CarFacets::default();
+        for row in rows {
+            let count = row.count;
+            match (row.fuel_type, row.transmission, row.status) {
+                (Some(value), _, _) => facets.fuel_types.push(FacetCount { value, count }),
+                (_, Some(value), _) => facets.transmissions.push(FacetCount { value, count }),
+                (_, _, Some(value)) => facets.statuses.push(FacetCount { value, count }),
+                (None, None, None) => {
+                    facets.total = count;
+                    facets.price = row.min_price
+                        .zip(row.max_price)
+                        .map(|(min, max)| PriceFacet { min, max, count });
+                }
             }
-        }).collect();
+        }
+
+        Ok(facets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Бренд с моделью для автомобилей теста; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestBrand {
+        brand_id: Uuid,
+        model_id: Uuid,
+    }
+
+    impl TestBrand {
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xca, 0xca, 0xca, 0xca, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let brand = Self { brand_id: id(1), model_id: id(2) };
+            brand.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(brand.brand_id)
+                .bind(format!("Test Brand {}", brand.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(brand.model_id)
+                .bind(brand.brand_id)
+                .execute(pool).await.unwrap();
+            brand
+        }
+
+        async fn car(&self, pool: &DbPool, vin: &str, price: f64) -> Car {
+            CarRepositoryImpl::new(pool.clone())
+                .save(&CreateCarRequest {
+                    brand_id: self.brand_id,
+                    model_id: self.model_id,
+                    year: 2020,
+                    price,
+                    mileage: 0,
+                    color: "Black".to_string(),
+                    vin: vin.to_string(),
+                    fuel_type: FuelType::Petrol,
+                    transmission: Transmission::Manual,
+                })
+                .await
+                .unwrap()
+        }
+
+        // Модели удаляются каскадом вместе с брендом
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM cars WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    fn ids(cars: &[Car]) -> Vec<Uuid> {
+        cars.iter().map(|car| car.id).collect()
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn price_range_with_equal_bounds_finds_the_exact_price() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 1).await;
+        // Цены с копейками, чтобы не совпасть с автомобилями других тестов
+        let exact = brand.car(&pool, "CACACA00000000101", 1_234_567.89).await;
+        let dearer = brand.car(&pool, "CACACA00000000102", 1_234_567.9).await;
+
+        let found = CarRepositoryImpl::new(pool.clone())
+            .find_by_price_range(Some(1_234_567.89), Some(1_234_567.89)).await.unwrap();
+        brand.remove(&pool).await;
+
+        // Обе границы включаются
+        assert!(ids(&found).contains(&exact.id));
+        assert!(!ids(&found).contains(&dearer.id));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn price_range_with_only_min_price_has_no_upper_bound() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 2).await;
+        let cheap = brand.car(&pool, "CACACA00000000201", 1_000.01).await;
+        let dear = brand.car(&pool, "CACACA00000000202", 987_654_321.01).await;
+
+        let found = CarRepositoryImpl::new(pool.clone())
+            .find_by_price_range(Some(1_000.02), None).await.unwrap();
+        brand.remove(&pool).await;
+
+        // Без max_price верхняя граница - бесконечность
+        assert!(ids(&found).contains(&dear.id));
+        assert!(!ids(&found).contains(&cheap.id));
+    }
+
+    #[test]
+    fn round_to_increment_rounds_up_above_half_step() {
+        assert_eq!(round_to_increment(1_000_600.0, Some(1000.0)), 1_001_000.0);
+        assert_eq!(round_to_increment(19_990.0, Some(100.0)), 20_000.0);
+    }
+
+    #[test]
+    fn round_to_increment_rounds_down_below_half_step() {
+        assert_eq!(round_to_increment(1_000_400.0, Some(1000.0)), 1_000_000.0);
+        assert_eq!(round_to_increment(20_049.0, Some(100.0)), 20_000.0);
+    }
+
+    #[test]
+    fn round_to_increment_rounds_half_step_up() {
+        assert_eq!(round_to_increment(1_000_500.0, Some(1000.0)), 1_001_000.0);
+        assert_eq!(round_to_increment(2_500.0, Some(1000.0)), 3_000.0);
+    }
 
-        Ok(campaigns)
+    #[test]
+    fn round_to_increment_keeps_price_when_disabled() {
+        assert_eq!(round_to_increment(1_000_499.99, None), 1_000_499.99);
     }
-}
\ No newline at end of file
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn features_are_added_removed_and_filtered() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 3).await;
+        let equipped = brand.car(&pool, "CACACA00000000301", 10_000.0).await;
+        let plain = brand.car(&pool, "CACACA00000000302", 10_000.0).await;
+        let repo = CarRepositoryImpl::new(pool.clone()).with_max_array_len(2);
+
+        repo.add_feature(equipped.id, "test-sunroof-3").await.unwrap();
+        let repeated = repo.add_feature(equipped.id, "test-sunroof-3").await.unwrap().unwrap();
+        let second = repo.add_feature(equipped.id, "test-heated-seats-3").await.unwrap().unwrap();
+        // Третья опция превышает лимит длины массива и не добавляется
+        let over_limit = repo.add_feature(equipped.id, "test-tow-bar-3").await.unwrap();
+        let with_sunroof = repo.find_by_feature("test-sunroof-3").await.unwrap();
+        let removed = repo.remove_feature(equipped.id, "test-sunroof-3").await.unwrap().unwrap();
+        let after_removal = repo.find_by_feature("test-sunroof-3").await.unwrap();
+        brand.remove(&pool).await;
+
+        assert_eq!(repeated.features, vec!["test-sunroof-3"]);
+        assert_eq!(second.features, vec!["test-sunroof-3", "test-heated-seats-3"]);
+        assert!(over_limit.is_none());
+        assert_eq!(ids(&with_sunroof), vec![equipped.id]);
+        assert!(!ids(&with_sunroof).contains(&plain.id));
+        assert_eq!(removed.features, vec!["test-heated-seats-3"]);
+        assert!(after_removal.is_empty());
+    }
+}
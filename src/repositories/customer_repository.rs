@@ -2,19 +2,29 @@ use async_trait::async_trait;
 use sqlx::Error;
 use uuid::Uuid;
 
-use crate::models::{Customer, CreateCustomerRequest};
+use chrono::{DateTime, Utc};
+
+use crate::models::{Customer, CreateCustomerRequest, UpdateCustomerRequest};
 use crate::database::DbPool;
 
 #[async_trait]
 pub trait CustomerRepository: Send + Sync {
-    async fn find_all(&self) -> Result<Vec<Customer>, Error>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Customer>, Error>;
     async fn find_by_email(&self, email: &str) -> Result<Option<Customer>, Error>;
     async fn find_by_name(&self, first_name: &str, last_name: &str) -> Result<Vec<Customer>, Error>;
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Customer>, Error>;
     async fn save(&self, create_request: &CreateCustomerRequest) -> Result<Customer, Error>;
-    async fn update(&self, id: Uuid, update_request: &CreateCustomerRequest) -> Result<Option<Customer>, Error>;
+    async fn update(&self, id: Uuid, update_request: &UpdateCustomerRequest) -> Result<Option<Customer>, Error>;
     async fn delete(&self, id: Uuid) -> Result<bool, Error>;
+    async fn anonymize(&self, id: Uuid) -> Result<Option<Customer>, Error>;
     async fn exists_by_email(&self, email: &str) -> Result<bool, Error>;
+    async fn find_by_registration_range(
+        &self,
+        registered_after: Option<DateTime<Utc>>,
+        registered_before: Option<DateTime<Utc>>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Customer>, i64), Error>;
 }
 #[derive(Clone)]
 pub struct CustomerRepositoryImpl {
@@ -29,19 +39,6 @@ impl CustomerRepositoryImpl {
 
 #[async_trait]
 impl CustomerRepository for CustomerRepositoryImpl {
-    async fn find_all(&self) -> Result<Vec<Customer>, Error> {
-        sqlx::query_as!(
-            Customer,
-            r#"
-            SELECT id, first_name, last_name, email, phone, created_at
-            FROM customers
-            ORDER BY created_at DESC
-            "#
-        )
-            .fetch_all(&self.pool)
-            .await
-    }
-
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Customer>, Error> {
         sqlx::query_as!(
             Customer,
@@ -107,23 +104,32 @@ impl CustomerRepository for CustomerRepositoryImpl {
             .await
     }
 
-    async fn update(&self, id: Uuid, update_request: &CreateCustomerRequest) -> Result<Option<Customer>, Error> {
-        sqlx::query_as!(
-            Customer,
-            r#"
-            UPDATE customers
-            SET first_name = $1, last_name = $2, email = $3, phone = $4
-            WHERE id = $5
-            RETURNING id, first_name, last_name, email, phone, created_at
-            "#,
-            update_request.first_name,
-            update_request.last_name,
-            update_request.email,
-            update_request.phone,
-            id
-        )
-            .fetch_optional(&self.pool)
-            .await
+    async fn update(&self, id: Uuid, update_request: &UpdateCustomerRequest) -> Result<Option<Customer>, Error> {
+        if let Some(current_customer) = self.find_by_id(id).await? {
+            let first_name = update_request.first_name.as_ref().unwrap_or(&current_customer.first_name);
+            let last_name = update_request.last_name.as_ref().unwrap_or(&current_customer.last_name);
+            let email = update_request.email.as_ref().unwrap_or(&current_customer.email);
+            let phone = update_request.phone.as_ref().unwrap_or(&current_customer.phone);
+
+            sqlx::query_as!(
+                Customer,
+                r#"
+                UPDATE customers
+                SET first_name = $1, last_name = $2, email = $3, phone = $4
+                WHERE id = $5
+                RETURNING id, first_name, last_name, email, phone, created_at
+                "#,
+                first_name,
+                last_name,
+                email,
+                phone,
+                id
+            )
+                .fetch_optional(&self.pool)
+                .await
+        } else {
+            Ok(None)
+        }
     }
 
     async fn delete(&self, id: Uuid) -> Result<bool, Error> {
@@ -147,4 +153,195 @@ impl CustomerRepository for CustomerRepositoryImpl {
 
         Ok(result.is_some())
     }
-}
\ No newline at end of file
+
+    async fn find_by_registration_range(
+        &self,
+        registered_after: Option<DateTime<Utc>>,
+        registered_before: Option<DateTime<Utc>>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Customer>, i64), Error> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM customers
+            WHERE ($1::timestamptz IS NULL OR created_at >= $1)
+              AND ($2::timestamptz IS NULL OR created_at < $2)
+            "#,
+            registered_after,
+            registered_before
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        let customers = sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT id, first_name, last_name, email, phone, created_at
+            FROM customers
+            WHERE ($1::timestamptz IS NULL OR created_at >= $1)
+              AND ($2::timestamptz IS NULL OR created_at < $2)
+            ORDER BY created_at, id
+            LIMIT $3 OFFSET $4
+            "#,
+            registered_after,
+            registered_before,
+            limit,
+            offset
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok((customers, total))
+    }
+
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Customer>, Error> {
+        sqlx::query_as!(
+            Customer,
+            r#"
+            SELECT id, first_name, last_name, email, phone, created_at
+            FROM customers
+            WHERE first_name ILIKE $1
+               OR last_name ILIKE $1
+               OR (first_name || ' ' || last_name) ILIKE $1
+               OR email ILIKE $1
+               OR phone ILIKE $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            format!("%{}%", query),
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Персональные данные заменяются заглушками без возможности восстановления;
+    // id и заявки на покупку сохраняются, примечания к заявкам очищаются - в них мог быть свободный текст с PII
+    async fn anonymize(&self, id: Uuid) -> Result<Option<Customer>, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        // email уникален, поэтому заглушка строится из id
+        let email = format!("anonymized-{}@anonymized.invalid", id);
+        let customer = sqlx::query_as!(
+            Customer,
+            r#"
+            UPDATE customers
+            SET first_name = 'Anonymized', last_name = 'Customer', email = $1, phone = ''
+            WHERE id = $2
+            RETURNING id, first_name, last_name, email, phone, created_at
+            "#,
+            email,
+            id
+        )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let Some(customer) = customer else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            "UPDATE purchase_requests SET notes = NULL WHERE customer_id = $1 AND notes IS NOT NULL"
+        )
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(customer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Клиент с заявкой на автомобиль; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestPurchase {
+        brand_id: Uuid,
+        customer_id: Uuid,
+        purchase_id: Uuid,
+    }
+
+    impl TestPurchase {
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xc1, 0xc1, 0xc1, 0xc1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let fixture = Self { brand_id: id(1), customer_id: id(4), purchase_id: id(5) };
+            fixture.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(fixture.brand_id)
+                .bind(format!("Test Brand {}", fixture.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(id(2))
+                .bind(fixture.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query(
+                "INSERT INTO cars (id, brand_id, model_id, year, price, mileage, color, fuel_type, transmission, vin)
+                 VALUES ($1, $2, $3, 2020, 1000000, 0, 'White', 'Petrol', 'Manual', $4)"
+            )
+                .bind(id(3))
+                .bind(fixture.brand_id)
+                .bind(id(2))
+                .bind(format!("C1C1C1000000000{:02}", n))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO customers (id, first_name, last_name, email, phone) VALUES ($1, 'Ivan', 'Petrov', $2, '+70000000000')")
+                .bind(fixture.customer_id)
+                .bind(format!("test-customer-{}@example.com", n))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO purchase_requests (id, car_id, customer_id, offer_price, notes) VALUES ($1, $2, $3, 950000, 'Call after 18:00')")
+                .bind(fixture.purchase_id)
+                .bind(id(3))
+                .bind(fixture.customer_id)
+                .execute(pool).await.unwrap();
+            fixture
+        }
+
+        // Заявки удаляются каскадом вместе с клиентом и автомобилем
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM customers WHERE id = $1")
+                .bind(self.customer_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM cars WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn anonymize_scrubs_pii_and_keeps_purchases_linked() {
+        let pool = test_pool().await;
+        let fixture = TestPurchase::insert(&pool, 1).await;
+
+        let customer = CustomerRepositoryImpl::new(pool.clone())
+            .anonymize(fixture.customer_id).await.unwrap().unwrap();
+        let (customer_id, notes): (Uuid, Option<String>) = sqlx::query_as(
+            "SELECT customer_id, notes FROM purchase_requests WHERE id = $1"
+        )
+            .bind(fixture.purchase_id)
+            .fetch_one(&pool).await.unwrap();
+        fixture.remove(&pool).await;
+
+        assert_eq!((customer.first_name.as_str(), customer.last_name.as_str()), ("Anonymized", "Customer"));
+        assert_eq!(customer.email, format!("anonymized-{}@anonymized.invalid", fixture.customer_id));
+        assert_eq!(customer.phone, "");
+        assert_eq!(customer_id, fixture.customer_id);
+        assert_eq!(notes, None);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn anonymize_unknown_customer_returns_none() {
+        let pool = test_pool().await;
+
+        let customer = CustomerRepositoryImpl::new(pool).anonymize(Uuid::nil()).await.unwrap();
+
+        assert!(customer.is_none());
+    }
+}
@@ -10,8 +10,9 @@ pub mod warehouse_repository;
 
 pub use car_repository::{CarRepository, CarRepositoryImpl};
 pub use customer_repository::{CustomerRepository, CustomerRepositoryImpl};
-pub use purchase_repository::{PurchaseRepository, PurchaseRepositoryImpl};
+pub use purchase_repository::{PurchaseRepository, PurchaseRepositoryImpl, ApproveAndSellError};
 pub use part_repository::{PartRepository, PartRepositoryImpl};
 pub use brand_repository::{BrandRepository, BrandRepositoryImpl};
 pub use car_model_repository::{CarModelRepository, CarModelRepositoryImpl};
-pub use work_repository::{WorkRepository, WorkRepositoryImpl};
\ No newline at end of file
+pub use work_repository::{WorkRepository, WorkRepositoryImpl};
+pub use warehouse_repository::StockError;
\ No newline at end of file
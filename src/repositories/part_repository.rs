@@ -1,32 +1,70 @@
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
-use sqlx::Error;
+use chrono::{DateTime, Utc};
+use sqlx::{Error, Row};
 use uuid::Uuid;
 
-use crate::models::{Part, CreatePartRequest, UpdatePartRequest};
+use crate::models::{
+    Part, CreatePartRequest, UpdatePartRequest, BatchDeletionReport, CompatibleVinsResult, CompatibleVinsStatus, CrossCompatiblePart,
+    CampaignPartDemand, PartStock, PartCatalogFilter, PartCatalogSort, SortOrder, PartCatalogRow, PartWithMargin
+};
+use crate::config::ArticleCase;
 use crate::database::DbPool;
 
 #[async_trait]
 pub trait PartRepository: Send + Sync {
     async fn find_all(&self) -> Result<Vec<Part>, Error>;
+    async fn find_all_with_margin(&self) -> Result<Vec<PartWithMargin>, Error>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Part>, Error>;
     async fn find_by_article(&self, article: &str) -> Result<Option<Part>, Error>;
     async fn find_by_brand(&self, brand_id: Uuid) -> Result<Vec<Part>, Error>;
     async fn find_by_car_model(&self, car_model_id: Uuid) -> Result<Vec<Part>, Error>;
     async fn find_by_vin(&self, vin: &str) -> Result<Vec<Part>, Error>;
+    async fn find_by_name(&self, name: &str) -> Result<Vec<Part>, Error>;
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Part>, Error>;
     async fn exists_by_article(&self, article: &str) -> Result<bool, Error>;
     async fn save(&self, create_request: &CreatePartRequest) -> Result<Part, Error>;
-    async fn update(&self, id: Uuid, update_request: &UpdatePartRequest) -> Result<Option<Part>, Error>;
+    /// expected_updated_at - версия из If-Match: обновление выполняется, только если запись не менялась
+    async fn update(&self, id: Uuid, update_request: &UpdatePartRequest, expected_updated_at: Option<DateTime<Utc>>) -> Result<Option<Part>, Error>;
     async fn delete(&self, id: Uuid) -> Result<bool, Error>;
+    async fn delete_batch(&self, ids: &[Uuid], dry_run: bool) -> Result<BatchDeletionReport, Error>;
+    async fn add_compatible_vins_batch(&self, part_ids: &[Uuid], vins: &[String]) -> Result<Vec<CompatibleVinsResult>, Error>;
+    async fn find_cross_compatible(&self) -> Result<Vec<CrossCompatiblePart>, Error>;
+    async fn campaign_demand(&self, part_id: Uuid) -> Result<Vec<CampaignPartDemand>, Error>;
+    async fn find_stock_by_car_model(&self, car_model_id: Uuid) -> Result<Vec<PartStock>, Error>;
+    async fn catalog(
+        &self,
+        filter: &PartCatalogFilter,
+        limit: i64,
+        offset: i64,
+        sort: PartCatalogSort,
+        order: SortOrder,
+    ) -> Result<(Vec<PartCatalogRow>, i64), Error>;
 }
 
 #[derive(Clone)]
 pub struct PartRepositoryImpl {
     pool: DbPool,
+    article_case: ArticleCase,
+    max_array_len: Option<usize>,
 }
 
 impl PartRepositoryImpl {
     pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+        Self { pool, article_case: ArticleCase::Preserve, max_array_len: None }
+    }
+
+    // Артикул при сохранении и обновлении приводится к заданному регистру
+    pub fn with_article_case(mut self, article_case: ArticleCase) -> Self {
+        self.article_case = article_case;
+        self
+    }
+
+    // Лимит длины списка совместимости при добавлении VIN; None - без лимита
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.max_array_len = Some(max_array_len);
+        self
     }
 }
 
@@ -58,6 +96,20 @@ impl PartRepository for PartRepositoryImpl {
         }).collect())
     }
 
+    // Сначала запчасти с наименьшей наценкой; без закупочной цены - в конце
+    async fn find_all_with_margin(&self) -> Result<Vec<PartWithMargin>, Error> {
+        let mut parts: Vec<PartWithMargin> = self.find_all().await?
+            .into_iter()
+            .map(PartWithMargin::from)
+            .collect();
+        parts.sort_by(|a, b| match (a.margin_percent, b.margin_percent) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        });
+
+        Ok(parts)
+    }
+
     async fn find_by_id(&self, id: Uuid) -> Result<Option<Part>, Error> {
         let row = sqlx::query!(
             r#"
@@ -91,7 +143,7 @@ impl PartRepository for PartRepositoryImpl {
             SELECT id, article, name, brand_id, car_model_id, purchase_price, sale_price,
                    compatible_vins, created_at, updated_at
             FROM parts
-            WHERE article = $1
+            WHERE LOWER(article) = LOWER($1)
             "#,
             article
         )
@@ -196,9 +248,37 @@ impl PartRepository for PartRepositoryImpl {
         }).collect())
     }
 
+    async fn find_by_name(&self, name: &str) -> Result<Vec<Part>, Error> {
+        let parts = sqlx::query!(
+            r#"
+            SELECT id, article, name, brand_id, car_model_id, purchase_price, sale_price,
+                   compatible_vins, created_at, updated_at
+            FROM parts
+            WHERE name ILIKE $1
+            ORDER BY name
+            "#,
+            format!("%{}%", name)
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(parts.into_iter().map(|row| Part {
+            id: row.id,
+            article: row.article,
+            name: row.name,
+            brand_id: row.brand_id.unwrap(),
+            car_model_id: row.car_model_id.unwrap(),
+            purchase_price: row.purchase_price,
+            sale_price: row.sale_price,
+            compatible_vins: row.compatible_vins,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }).collect())
+    }
+
     async fn exists_by_article(&self, article: &str) -> Result<bool, Error> {
         let result = sqlx::query(
-            "SELECT id FROM parts WHERE article = $1 LIMIT 1"
+            "SELECT id FROM parts WHERE LOWER(article) = LOWER($1) LIMIT 1"
         )
             .bind(article)
             .fetch_optional(&self.pool)
@@ -220,7 +300,7 @@ impl PartRepository for PartRepositoryImpl {
                      compatible_vins, created_at, updated_at
             "#,
             id,
-            create_request.article,
+            self.article_case.apply(&create_request.article),
             create_request.name,
             create_request.brand_id,
             create_request.car_model_id,
@@ -247,11 +327,14 @@ impl PartRepository for PartRepositoryImpl {
         })
     }
 
-    async fn update(&self, id: Uuid, update_request: &UpdatePartRequest) -> Result<Option<Part>, Error> {
+    async fn update(&self, id: Uuid, update_request: &UpdatePartRequest, expected_updated_at: Option<DateTime<Utc>>) -> Result<Option<Part>, Error> {
         let now = chrono::Utc::now();
         
         if let Some(current_part) = self.find_by_id(id).await? {
-            let article = update_request.article.as_ref().unwrap_or(&current_part.article);
+            let article = match &update_request.article {
+                Some(article) => self.article_case.apply(article),
+                None => current_part.article.clone(),
+            };
             let name = update_request.name.as_ref().unwrap_or(&current_part.name);
             let brand_id = update_request.brand_id.unwrap_or(current_part.brand_id);
             let car_model_id = update_request.car_model_id.unwrap_or(current_part.car_model_id);
@@ -264,7 +347,7 @@ impl PartRepository for PartRepositoryImpl {
                 UPDATE parts
                 SET article = $1, name = $2, brand_id = $3, car_model_id = $4, purchase_price = $5,
                     sale_price = $6, compatible_vins = $7, updated_at = $8
-                WHERE id = $9
+                WHERE id = $9 AND ($10::timestamptz IS NULL OR updated_at = $10)
                 RETURNING id, article, name, brand_id, car_model_id, purchase_price, sale_price,
                          compatible_vins, created_at, updated_at
                 "#,
@@ -276,7 +359,8 @@ impl PartRepository for PartRepositoryImpl {
                 sale_price,
                 compatible_vins,
                 now,
-                id
+                id,
+                expected_updated_at
             )
                 .fetch_optional(&self.pool)
                 .await?;
@@ -308,4 +392,477 @@ impl PartRepository for PartRepositoryImpl {
 
         Ok(result.rows_affected() > 0)
     }
-}
\ No newline at end of file
+
+    async fn delete_batch(&self, ids: &[Uuid], dry_run: bool) -> Result<BatchDeletionReport, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let existing: Vec<Uuid> = sqlx::query_scalar!(
+            "SELECT id FROM parts WHERE id = ANY($1) FOR UPDATE",
+            ids
+        )
+            .fetch_all(&mut *tx)
+            .await?;
+
+        // Запчасти, которые нужны активным сервисным кампаниям, не удаляем
+        let required: Vec<Uuid> = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT part_id as "part_id!"
+            FROM service_campaigns, UNNEST(required_parts) as part_id
+            WHERE status = 'active' AND part_id = ANY($1)
+            "#,
+            ids
+        )
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let mut report = BatchDeletionReport {
+            dry_run,
+            deleted: Vec::new(),
+            not_found: Vec::new(),
+            blocked: BTreeMap::new(),
+            cascaded: BTreeMap::new(),
+        };
+
+        for id in ids {
+            if !existing.contains(id) {
+                report.not_found.push(*id);
+            } else if required.contains(id) {
+                report.blocked.insert(*id, "Required by an active service campaign".to_string());
+            } else if !report.deleted.contains(id) {
+                report.deleted.push(*id);
+            }
+        }
+
+        if report.deleted.is_empty() {
+            return Ok(report);
+        }
+
+        let warehouse_items = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM warehouse WHERE part_id = ANY($1)"#,
+            &report.deleted
+        )
+            .fetch_one(&mut *tx)
+            .await?;
+        if warehouse_items > 0 {
+            report.cascaded.insert("warehouse".to_string(), warehouse_items);
+        }
+
+        sqlx::query("DELETE FROM parts WHERE id = ANY($1)")
+            .bind(&report.deleted)
+            .execute(&mut *tx)
+            .await?;
+
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+
+        Ok(report)
+    }
+
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Part>, Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, article, name, brand_id, car_model_id, purchase_price, sale_price,
+                   compatible_vins, created_at, updated_at
+            FROM parts
+            WHERE name ILIKE $1 OR article ILIKE $1
+            ORDER BY name
+            LIMIT $2
+            "#,
+            format!("%{}%", query),
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| Part {
+            id: row.id,
+            article: row.article,
+            name: row.name,
+            brand_id: row.brand_id.unwrap(),
+            car_model_id: row.car_model_id.unwrap(),
+            purchase_price: row.purchase_price,
+            sale_price: row.sale_price,
+            compatible_vins: row.compatible_vins,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }).collect())
+    }
+
+    async fn add_compatible_vins_batch(&self, part_ids: &[Uuid], vins: &[String]) -> Result<Vec<CompatibleVinsResult>, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        // Блокируем строки в порядке id, чтобы параллельные пакеты не взаимоблокировались
+        let rows = sqlx::query!(
+            "SELECT id, compatible_vins FROM parts WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+            part_ids
+        )
+            .fetch_all(&mut *tx)
+            .await?;
+        let mut existing: BTreeMap<Uuid, Vec<String>> = rows.into_iter()
+            .map(|row| (row.id, row.compatible_vins))
+            .collect();
+
+        let mut results = Vec::with_capacity(part_ids.len());
+        for &part_id in part_ids {
+            let Some(current) = existing.get_mut(&part_id) else {
+                results.push(CompatibleVinsResult {
+                    part_id,
+                    status: CompatibleVinsStatus::NotFound,
+                    added_vins: Vec::new(),
+                });
+                continue;
+            };
+
+            let added_vins: Vec<String> = vins.iter()
+                .filter(|vin| !current.iter().any(|known| known.eq_ignore_ascii_case(vin)))
+                .cloned()
+                .collect();
+
+            if self.max_array_len.is_some_and(|max| current.len() + added_vins.len() > max) {
+                results.push(CompatibleVinsResult {
+                    part_id,
+                    status: CompatibleVinsStatus::LimitExceeded,
+                    added_vins: Vec::new(),
+                });
+                continue;
+            }
+
+            if !added_vins.is_empty() {
+                sqlx::query!(
+                    "UPDATE parts SET compatible_vins = compatible_vins || $2, updated_at = NOW() WHERE id = $1",
+                    part_id,
+                    &added_vins
+                )
+                    .execute(&mut *tx)
+                    .await?;
+                // Повторный id в запросе не должен добавить те же VIN второй раз
+                current.extend(added_vins.iter().cloned());
+            }
+
+            let status = if added_vins.is_empty() {
+                CompatibleVinsStatus::Unchanged
+            } else {
+                CompatibleVinsStatus::Updated
+            };
+            results.push(CompatibleVinsResult { part_id, status, added_vins });
+        }
+
+        tx.commit().await?;
+        Ok(results)
+    }
+
+    async fn find_cross_compatible(&self) -> Result<Vec<CrossCompatiblePart>, Error> {
+        // VIN из списка совместимости -> автомобиль -> модель; VIN без автомобиля и пустые списки
+        // отсекаются внутренним соединением
+        sqlx::query_as!(
+            CrossCompatiblePart,
+            r#"
+            SELECT p.id AS "part_id!", p.article AS "article!", p.name AS "name!",
+                   p.brand_id AS "brand_id!", p.car_model_id AS "car_model_id!",
+                   COUNT(DISTINCT c.model_id) AS "model_count!",
+                   ARRAY_AGG(DISTINCT c.model_id) AS "model_ids!"
+            FROM parts p
+            JOIN cars c ON c.vin = ANY(p.compatible_vins) AND c.deleted_at IS NULL
+            GROUP BY p.id
+            HAVING COUNT(DISTINCT c.model_id) > 1
+            ORDER BY COUNT(DISTINCT c.model_id) DESC, p.article
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn campaign_demand(&self, part_id: Uuid) -> Result<Vec<CampaignPartDemand>, Error> {
+        // Автомобили кампании - как при пересчёте выполнения: модель кампании и target_vins,
+        // пустой target_vins - вся модель. Невыполненные - без кампании в completed_service_campaigns
+        sqlx::query_as!(
+            CampaignPartDemand,
+            r#"
+            SELECT sc.id AS "campaign_id!", sc.article AS "article!", sc.name AS "name!",
+                   cardinality(array_positions(sc.required_parts, $1))::bigint AS "units_per_car!",
+                   COUNT(c.id) AS "outstanding_cars!",
+                   cardinality(array_positions(sc.required_parts, $1)) * COUNT(c.id) AS "quantity!"
+            FROM service_campaigns sc
+            LEFT JOIN cars c ON c.brand_id = sc.brand_id
+                            AND c.model_id = sc.car_model_id
+                            AND (cardinality(sc.target_vins) = 0 OR c.vin = ANY(sc.target_vins))
+                            AND NOT sc.id = ANY(c.completed_service_campaigns)
+                            AND c.deleted_at IS NULL
+            WHERE sc.status = 'active'
+              AND $1 = ANY(sc.required_parts)
+            GROUP BY sc.id
+            ORDER BY cardinality(array_positions(sc.required_parts, $1)) * COUNT(c.id) DESC, sc.article
+            "#,
+            part_id
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_stock_by_car_model(&self, car_model_id: Uuid) -> Result<Vec<PartStock>, Error> {
+        sqlx::query_as!(
+            PartStock,
+            r#"
+            SELECT p.id AS part_id, p.article, p.name,
+                   w.id IS NOT NULL AS "stocked!",
+                   COALESCE(w.quantity, 0) AS "quantity!",
+                   COALESCE(w.quantity, 0) > 0 AS "in_stock!"
+            FROM parts p
+            LEFT JOIN warehouse w ON w.part_id = p.id
+            WHERE p.car_model_id = $1
+            ORDER BY COALESCE(w.quantity, 0) > 0 DESC, p.article
+            "#,
+            car_model_id
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn catalog(
+        &self,
+        filter: &PartCatalogFilter,
+        limit: i64,
+        offset: i64,
+        sort: PartCatalogSort,
+        order: SortOrder,
+    ) -> Result<(Vec<PartCatalogRow>, i64), Error> {
+        let where_clause = r#"
+            WHERE ($1::uuid IS NULL OR p.brand_id = $1)
+              AND ($2::uuid IS NULL OR p.car_model_id = $2)
+              AND ($3::float8 IS NULL OR p.sale_price >= $3)
+              AND ($4::float8 IS NULL OR p.sale_price <= $4)
+        "#;
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM parts p {}", where_clause))
+            .bind(filter.brand_id)
+            .bind(filter.car_model_id)
+            .bind(filter.min_price)
+            .bind(filter.max_price)
+            .fetch_one(&self.pool)
+            .await?;
+
+        // Запчасти без маржи (нулевая цена продажи) всегда в конце, независимо от направления сортировки
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT p.id, p.article, p.name, p.brand_id, p.car_model_id, p.purchase_price, p.sale_price,
+                   p.compatible_vins, p.created_at, p.updated_at,
+                   COALESCE(w.quantity, 0) AS quantity,
+                   ROUND(((p.sale_price - p.purchase_price) / NULLIF(p.sale_price, 0) * 100)::numeric, 2)::float8 AS margin_percent
+            FROM parts p
+            LEFT JOIN warehouse w ON w.part_id = p.id
+            {}
+            ORDER BY {} {} NULLS LAST, p.id
+            LIMIT $5 OFFSET $6
+            "#,
+            where_clause,
+            sort.column(),
+            order.sql()
+        ))
+            .bind(filter.brand_id)
+            .bind(filter.car_model_id)
+            .bind(filter.min_price)
+            .bind(filter.max_price)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let items = rows
+            .into_iter()
+            .map(|row| {
+                Ok(PartCatalogRow {
+                    part: Part {
+                        id: row.try_get("id")?,
+                        article: row.try_get("article")?,
+                        name: row.try_get("name")?,
+                        brand_id: row.try_get("brand_id")?,
+                        car_model_id: row.try_get("car_model_id")?,
+                        purchase_price: row.try_get("purchase_price")?,
+                        sale_price: row.try_get("sale_price")?,
+                        compatible_vins: row.try_get("compatible_vins")?,
+                        created_at: row.try_get("created_at")?,
+                        updated_at: row.try_get("updated_at")?,
+                    },
+                    quantity: row.try_get("quantity")?,
+                    margin_percent: row.try_get("margin_percent")?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok((items, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Бренд с моделью для запчастей теста; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestBrand {
+        brand_id: Uuid,
+        model_id: Uuid,
+    }
+
+    impl TestBrand {
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xba, 0xba, 0xba, 0xba, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let brand = Self { brand_id: id(1), model_id: id(2) };
+            brand.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(brand.brand_id)
+                .bind(format!("Test Brand {}", brand.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(brand.model_id)
+                .bind(brand.brand_id)
+                .execute(pool).await.unwrap();
+            brand
+        }
+
+        async fn part(&self, pool: &DbPool, article: &str, purchase_price: f64, sale_price: f64) -> Part {
+            PartRepositoryImpl::new(pool.clone())
+                .save(&CreatePartRequest {
+                    article: article.to_string(),
+                    name: format!("Test Part {}", article),
+                    brand_id: self.brand_id,
+                    car_model_id: self.model_id,
+                    purchase_price,
+                    sale_price,
+                    compatible_vins: Vec::new(),
+                })
+                .await
+                .unwrap()
+        }
+
+        // Складские позиции удаляются каскадом вместе с запчастями, модели - вместе с брендом
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM parts WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    fn articles(rows: &[PartCatalogRow]) -> Vec<&str> {
+        rows.iter().map(|row| row.part.article.as_str()).collect()
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn catalog_filters_sorts_by_margin_and_paginates() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 1).await;
+        brand.part(&pool, "TEST-CATALOG-HALF", 50.0, 100.0).await;
+        brand.part(&pool, "TEST-CATALOG-TENTH", 90.0, 100.0).await;
+        brand.part(&pool, "TEST-CATALOG-FREE", 0.0, 0.0).await;
+        brand.part(&pool, "TEST-CATALOG-DEAR", 100.0, 400.0).await;
+        let repo = PartRepositoryImpl::new(pool.clone());
+        let filter = PartCatalogFilter { brand_id: Some(brand.brand_id), max_price: Some(200.0), ..Default::default() };
+
+        let (first_page, first_total) = repo
+            .catalog(&filter, 2, 0, PartCatalogSort::MarginPercent, SortOrder::Desc).await.unwrap();
+        let (second_page, second_total) = repo
+            .catalog(&filter, 2, 2, PartCatalogSort::MarginPercent, SortOrder::Desc).await.unwrap();
+        let min_price_filter = PartCatalogFilter { brand_id: Some(brand.brand_id), min_price: Some(100.0), ..Default::default() };
+        let (by_min_price, by_min_price_total) = repo
+            .catalog(&min_price_filter, 10, 0, PartCatalogSort::SalePrice, SortOrder::Asc).await.unwrap();
+        brand.remove(&pool).await;
+
+        // Нулевая цена продажи - маржа не определена, такая запчасть последней и при DESC
+        assert_eq!(articles(&first_page), ["TEST-CATALOG-HALF", "TEST-CATALOG-TENTH"]);
+        assert_eq!(first_page[0].margin_percent, Some(50.0));
+        assert_eq!(articles(&second_page), ["TEST-CATALOG-FREE"]);
+        assert_eq!(second_page[0].margin_percent, None);
+        assert_eq!(second_page[0].quantity, 0);
+        assert_eq!((first_total, second_total), (3, 3));
+        assert_eq!(by_min_price.len(), 3);
+        assert_eq!(by_min_price.last().unwrap().part.article, "TEST-CATALOG-DEAR");
+        assert_eq!(by_min_price_total, 3);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn find_by_name_ignores_case() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 2).await;
+        let part = brand.part(&pool, "TEST-NAME-Gasket", 10.0, 20.0).await;
+        let repo = PartRepositoryImpl::new(pool.clone());
+
+        let lower = repo.find_by_name("test part test-name-gasket").await.unwrap();
+        let upper = repo.find_by_name("PART TEST-NAME-GASK").await.unwrap();
+        brand.remove(&pool).await;
+
+        // Поиск по подстроке без учёта регистра: находится по запросу в любом регистре
+        assert!(lower.iter().any(|found| found.id == part.id));
+        assert!(upper.iter().any(|found| found.id == part.id));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn dry_run_batch_delete_reports_without_deleting() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 3).await;
+        let stocked = brand.part(&pool, "TEST-BATCH-STOCKED", 10.0, 20.0).await;
+        let plain = brand.part(&pool, "TEST-BATCH-PLAIN", 10.0, 20.0).await;
+        sqlx::query("INSERT INTO warehouse (id, part_id, quantity) VALUES ($1, $2, 4)")
+            .bind(Uuid::new_v4())
+            .bind(stocked.id)
+            .execute(&pool).await.unwrap();
+        let missing = Uuid::new_v4();
+        let repo = PartRepositoryImpl::new(pool.clone());
+
+        let report = repo.delete_batch(&[stocked.id, plain.id, missing], true).await.unwrap();
+        let stocked_after = repo.find_by_id(stocked.id).await.unwrap();
+        let plain_after = repo.find_by_id(plain.id).await.unwrap();
+        let warehouse_after: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM warehouse WHERE part_id = $1")
+            .bind(stocked.id)
+            .fetch_one(&pool).await.unwrap();
+        brand.remove(&pool).await;
+
+        // Отчёт такой же, как при настоящем удалении, но транзакция откатывается
+        assert!(report.dry_run);
+        assert_eq!(report.deleted, vec![stocked.id, plain.id]);
+        assert_eq!(report.not_found, vec![missing]);
+        assert_eq!(report.cascaded.get("warehouse"), Some(&1));
+        assert!(stocked_after.is_some());
+        assert!(plain_after.is_some());
+        assert_eq!(warehouse_after, 1);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn bulk_compatible_vins_introduce_no_duplicates() {
+        let pool = test_pool().await;
+        let brand = TestBrand::insert(&pool, 4).await;
+        let known = brand.part(&pool, "TEST-VINS-KNOWN", 10.0, 20.0).await;
+        let fresh = brand.part(&pool, "TEST-VINS-FRESH", 10.0, 20.0).await;
+        let missing = Uuid::new_v4();
+        let repo = PartRepositoryImpl::new(pool.clone());
+        repo.add_compatible_vins_batch(&[known.id], &["BABABA00000000401".to_string()]).await.unwrap();
+
+        // Уже известный VIN и повтор id в запросе не дублируют записи
+        let vins = ["BABABA00000000401".to_string(), "BABABA00000000402".to_string()];
+        let results = repo.add_compatible_vins_batch(&[known.id, fresh.id, known.id, missing], &vins).await.unwrap();
+        let known_after = repo.find_by_id(known.id).await.unwrap().unwrap();
+        let fresh_after = repo.find_by_id(fresh.id).await.unwrap().unwrap();
+        brand.remove(&pool).await;
+
+        let outcome: Vec<(Uuid, CompatibleVinsStatus, Vec<String>)> = results.into_iter()
+            .map(|result| (result.part_id, result.status, result.added_vins))
+            .collect();
+        assert_eq!(outcome, vec![
+            (known.id, CompatibleVinsStatus::Updated, vec!["BABABA00000000402".to_string()]),
+            (fresh.id, CompatibleVinsStatus::Updated, vins.to_vec()),
+            (known.id, CompatibleVinsStatus::Unchanged, Vec::new()),
+            (missing, CompatibleVinsStatus::NotFound, Vec::new()),
+        ]);
+        assert_eq!(known_after.compatible_vins, vec!["BABABA00000000401", "BABABA00000000402"]);
+        assert_eq!(fresh_after.compatible_vins, vins.to_vec());
+    }
+}
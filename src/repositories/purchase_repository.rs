@@ -1,21 +1,67 @@
+use std::collections::{HashMap, HashSet};
+
 use async_trait::async_trait;
 use sqlx::Error;
 use uuid::Uuid;
 
-use crate::models::{PurchaseRequest, CreatePurchaseRequest, RequestStatus};
+use chrono::{DateTime, Utc};
+
+use crate::models::{
+    PurchaseRequest, CreatePurchaseRequest, RequestStatus, MostRequestedCar, AvgPriceByModel, StalePurchaseRequest, OldestPendingPurchase, SaleMargin,
+    StatusTransitionResult, PurchaseFilter, BrandDaysToSale, TopCustomer, EnumVariants
+};
 use crate::database::DbPool;
 
+/// Причина, по которой заявку нельзя одобрить с продажей автомобиля; при любой ошибке транзакция откатывается
+#[derive(Debug)]
+pub enum ApproveAndSellError {
+    PurchaseNotFound,
+    /// Заявка в статусе, из которого нельзя перейти в Approved
+    InvalidTransition(RequestStatus),
+    CarAlreadySold,
+    Database(Error),
+}
+
+impl std::fmt::Display for ApproveAndSellError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApproveAndSellError::PurchaseNotFound => write!(f, "Purchase request not found"),
+            ApproveAndSellError::InvalidTransition(status) => write!(f, "Cannot change status from {:?} to Approved", status),
+            ApproveAndSellError::CarAlreadySold => write!(f, "Car is already sold"),
+            ApproveAndSellError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApproveAndSellError {}
+
+impl From<Error> for ApproveAndSellError {
+    fn from(e: Error) -> Self {
+        ApproveAndSellError::Database(e)
+    }
+}
+
 #[async_trait]
 pub trait PurchaseRepository: Send + Sync {
-    async fn find_all(&self) -> Result<Vec<PurchaseRequest>, Error>;
+    async fn find_filtered_paginated(&self, filter: &PurchaseFilter, limit: i64, offset: i64) -> Result<(Vec<PurchaseRequest>, i64), Error>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<PurchaseRequest>, Error>;
     async fn find_by_customer_id(&self, customer_id: Uuid) -> Result<Vec<PurchaseRequest>, Error>;
     async fn find_by_car_id(&self, car_id: Uuid) -> Result<Vec<PurchaseRequest>, Error>;
     async fn find_by_status(&self, status: RequestStatus) -> Result<Vec<PurchaseRequest>, Error>;
     async fn save(&self, create_request: &CreatePurchaseRequest) -> Result<PurchaseRequest, Error>;
     async fn update_status(&self, id: Uuid, status: RequestStatus) -> Result<Option<PurchaseRequest>, Error>;
+    async fn update_status_batch(&self, ids: &[Uuid], status: RequestStatus) -> Result<Vec<StatusTransitionResult>, Error>;
+    async fn approve_and_sell(&self, purchase_id: Uuid) -> Result<PurchaseRequest, ApproveAndSellError>;
     async fn delete(&self, id: Uuid) -> Result<bool, Error>;
     async fn exists_by_car_and_customer(&self, car_id: Uuid, customer_id: Uuid) -> Result<bool, Error>;
+    async fn count_active_for_car(&self, car_id: Uuid) -> Result<i64, Error>;
+    async fn top_customers(&self, limit: i64) -> Result<Vec<TopCustomer>, Error>;
+    async fn most_requested_cars(&self, limit: i64, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<MostRequestedCar>, Error>;
+    async fn avg_price_by_model(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<AvgPriceByModel>, Error>;
+    async fn find_stale_pending(&self, days: i32) -> Result<Vec<StalePurchaseRequest>, Error>;
+    async fn find_oldest_pending_per_car(&self) -> Result<Vec<OldestPendingPurchase>, Error>;
+    async fn sale_margins(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<SaleMargin>, Error>;
+    async fn avg_days_to_sale(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<BrandDaysToSale>, Error>;
 }
 #[derive(Clone)]
 pub struct PurchaseRepositoryImpl {
@@ -30,20 +76,6 @@ impl PurchaseRepositoryImpl {
 
 #[async_trait]
 impl PurchaseRepository for PurchaseRepositoryImpl {
-    async fn find_all(&self) -> Result<Vec<PurchaseRequest>, Error> {
-        sqlx::query_as!(
-            PurchaseRequest,
-            r#"
-            SELECT id, car_id, customer_id, status as "status: _",
-                   offer_price, notes, created_at, updated_at
-            FROM purchase_requests
-            ORDER BY created_at DESC
-            "#
-        )
-            .fetch_all(&self.pool)
-            .await
-    }
-
     async fn find_by_id(&self, id: Uuid) -> Result<Option<PurchaseRequest>, Error> {
         sqlx::query_as!(
             PurchaseRequest,
@@ -151,6 +183,78 @@ impl PurchaseRepository for PurchaseRepositoryImpl {
             .await
     }
 
+    async fn update_status_batch(&self, ids: &[Uuid], status: RequestStatus) -> Result<Vec<StatusTransitionResult>, Error> {
+        let mut tx = self.pool.begin().await?;
+        let now = chrono::Utc::now();
+
+        // Блокируем строки, чтобы статус не поменялся между проверкой перехода и обновлением
+        let current: HashMap<Uuid, RequestStatus> = sqlx::query!(
+            r#"
+            SELECT id, status as "status: RequestStatus"
+            FROM purchase_requests
+            WHERE id = ANY($1)
+            FOR UPDATE
+            "#,
+            ids
+        )
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|row| (row.id, row.status))
+            .collect();
+
+        let mut results = Vec::with_capacity(ids.len());
+        let mut seen = HashSet::new();
+        let mut to_update = Vec::new();
+
+        for &id in ids {
+            let result = match current.get(&id) {
+                _ if !seen.insert(id) => StatusTransitionResult {
+                    id,
+                    success: false,
+                    previous_status: None,
+                    error: Some("Duplicate id in request".to_string()),
+                },
+                None => StatusTransitionResult {
+                    id,
+                    success: false,
+                    previous_status: None,
+                    error: Some("Purchase request not found".to_string()),
+                },
+                Some(previous) if !previous.can_transition_to(&status) => StatusTransitionResult {
+                    id,
+                    success: false,
+                    previous_status: Some(previous.clone()),
+                    error: Some(format!("Cannot change status from {:?} to {:?}", previous, status)),
+                },
+                Some(previous) => {
+                    to_update.push(id);
+                    StatusTransitionResult {
+                        id,
+                        success: true,
+                        previous_status: Some(previous.clone()),
+                        error: None,
+                    }
+                }
+            };
+            results.push(result);
+        }
+
+        if !to_update.is_empty() {
+            sqlx::query!(
+                "UPDATE purchase_requests SET status = $1, updated_at = $2 WHERE id = ANY($3)",
+                status as RequestStatus,
+                now,
+                &to_update
+            )
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(results)
+    }
+
     async fn delete(&self, id: Uuid) -> Result<bool, Error> {
         let result = sqlx::query(
             "DELETE FROM purchase_requests WHERE id = $1"
@@ -173,4 +277,416 @@ impl PurchaseRepository for PurchaseRepositoryImpl {
 
         Ok(result.is_some())
     }
-}
\ No newline at end of file
+
+    async fn most_requested_cars(&self, limit: i64, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<MostRequestedCar>, Error> {
+        sqlx::query_as!(
+            MostRequestedCar,
+            r#"
+            SELECT c.id as car_id, b.name as brand_name, m.name as model_name,
+                   c.year, c.vin, c.price, COUNT(pr.id) as "request_count!"
+            FROM purchase_requests pr
+            JOIN cars c ON pr.car_id = c.id
+            JOIN brands b ON c.brand_id = b.id
+            JOIN car_models m ON c.model_id = m.id
+            WHERE ($1::timestamptz IS NULL OR pr.created_at >= $1)
+              AND ($2::timestamptz IS NULL OR pr.created_at < $2)
+            GROUP BY c.id, b.name, m.name
+            ORDER BY COUNT(pr.id) DESC, c.id
+            LIMIT $3
+            "#,
+            from,
+            to,
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn avg_price_by_model(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<AvgPriceByModel>, Error> {
+        // Модели без завершённых продаж попадают в отчёт с avg_price = null и sales_count = 0
+        sqlx::query_as!(
+            AvgPriceByModel,
+            r#"
+            SELECT m.id as model_id, m.name as model_name,
+                   b.id as brand_id, b.name as brand_name,
+                   AVG(pr.offer_price) as avg_price, COUNT(pr.id) as "sales_count!"
+            FROM car_models m
+            JOIN brands b ON m.brand_id = b.id
+            LEFT JOIN cars c ON c.model_id = m.id
+            LEFT JOIN purchase_requests pr ON pr.car_id = c.id
+                AND pr.status = 'Completed'
+                AND ($1::timestamptz IS NULL OR pr.created_at >= $1)
+                AND ($2::timestamptz IS NULL OR pr.created_at < $2)
+            GROUP BY m.id, m.name, b.id, b.name
+            ORDER BY b.name, m.name
+            "#,
+            from,
+            to
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_stale_pending(&self, days: i32) -> Result<Vec<StalePurchaseRequest>, Error> {
+        sqlx::query_as!(
+            StalePurchaseRequest,
+            r#"
+            SELECT pr.id, pr.offer_price, pr.notes, pr.created_at,
+                   EXTRACT(DAY FROM NOW() - pr.created_at)::int as "days_pending!",
+                   cu.id as customer_id, cu.first_name || ' ' || cu.last_name as "customer_name!",
+                   cu.email as customer_email, cu.phone as customer_phone,
+                   c.id as car_id, c.vin, b.name as brand_name, m.name as model_name,
+                   c.year, c.price
+            FROM purchase_requests pr
+            JOIN customers cu ON pr.customer_id = cu.id
+            JOIN cars c ON pr.car_id = c.id
+            JOIN brands b ON c.brand_id = b.id
+            JOIN car_models m ON c.model_id = m.id
+            WHERE pr.status = 'Pending'
+              AND pr.created_at < NOW() - make_interval(days => $1)
+            ORDER BY pr.created_at
+            "#,
+            days
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_oldest_pending_per_car(&self) -> Result<Vec<OldestPendingPurchase>, Error> {
+        // DISTINCT ON оставляет по каждому автомобилю самую раннюю заявку,
+        // внешняя сортировка выстраивает очередь по времени поступления
+        sqlx::query_as!(
+            OldestPendingPurchase,
+            r#"
+            SELECT oldest.id as "id!", oldest.car_id as "car_id!", c.vin,
+                   oldest.offer_price, oldest.notes, oldest.created_at as "created_at!",
+                   oldest.pending_count as "pending_count!",
+                   cu.id as customer_id, cu.first_name || ' ' || cu.last_name as "customer_name!",
+                   cu.email as customer_email, cu.phone as customer_phone
+            FROM (
+                SELECT DISTINCT ON (pr.car_id)
+                       pr.id, pr.car_id, pr.customer_id, pr.offer_price, pr.notes, pr.created_at,
+                       COUNT(*) OVER (PARTITION BY pr.car_id) as pending_count
+                FROM purchase_requests pr
+                WHERE pr.status = 'Pending'
+                ORDER BY pr.car_id, pr.created_at, pr.id
+            ) oldest
+            JOIN customers cu ON oldest.customer_id = cu.id
+            JOIN cars c ON oldest.car_id = c.id
+            ORDER BY oldest.created_at, oldest.id
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn sale_margins(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<SaleMargin>, Error> {
+        sqlx::query_as!(
+            SaleMargin,
+            r#"
+            SELECT pr.id as purchase_id, c.id as car_id, c.vin,
+                   b.name as brand_name, m.name as model_name,
+                   pr.updated_at as sold_at, pr.offer_price, c.price as cost_price,
+                   pr.offer_price - c.price as margin
+            FROM purchase_requests pr
+            JOIN cars c ON pr.car_id = c.id
+            JOIN brands b ON c.brand_id = b.id
+            JOIN car_models m ON c.model_id = m.id
+            WHERE pr.status = 'Completed'
+              AND ($1::timestamptz IS NULL OR pr.updated_at >= $1)
+              AND ($2::timestamptz IS NULL OR pr.updated_at < $2)
+            ORDER BY pr.updated_at DESC
+            "#,
+            from,
+            to
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_filtered_paginated(&self, filter: &PurchaseFilter, limit: i64, offset: i64) -> Result<(Vec<PurchaseRequest>, i64), Error> {
+        let status = filter.status.as_ref().map(|status| status.value());
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM purchase_requests
+            WHERE ($1::varchar IS NULL OR status = $1)
+              AND ($2::timestamptz IS NULL OR created_at >= $2)
+              AND ($3::timestamptz IS NULL OR created_at < $3)
+            "#,
+            status,
+            filter.from,
+            filter.to
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        let requests = sqlx::query_as!(
+            PurchaseRequest,
+            r#"
+            SELECT id, car_id, customer_id, status as "status: _",
+                   offer_price, notes, created_at, updated_at
+            FROM purchase_requests
+            WHERE ($1::varchar IS NULL OR status = $1)
+              AND ($2::timestamptz IS NULL OR created_at >= $2)
+              AND ($3::timestamptz IS NULL OR created_at < $3)
+            ORDER BY created_at DESC, id
+            LIMIT $4 OFFSET $5
+            "#,
+            status,
+            filter.from,
+            filter.to,
+            limit,
+            offset
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok((requests, total))
+    }
+
+    async fn avg_days_to_sale(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<BrandDaysToSale>, Error> {
+        // Продажа - завершённая заявка, момент продажи - её updated_at; период фильтрует по моменту продажи.
+        // Отрицательный срок (карточка автомобиля создана после продажи, например при повторном вводе)
+        // считается ошибкой данных: такие продажи только подсчитываются в excluded_count
+        sqlx::query_as!(
+            BrandDaysToSale,
+            r#"
+            WITH sales AS (
+                SELECT c.brand_id,
+                       EXTRACT(EPOCH FROM pr.updated_at - c.created_at) / 86400.0 as days
+                FROM purchase_requests pr
+                JOIN cars c ON pr.car_id = c.id
+                WHERE pr.status = 'Completed'
+                  AND ($1::timestamptz IS NULL OR pr.updated_at >= $1)
+                  AND ($2::timestamptz IS NULL OR pr.updated_at < $2)
+            )
+            SELECT b.id as brand_id, b.name as brand_name,
+                   COUNT(*) FILTER (WHERE s.days >= 0) as "sales_count!",
+                   (AVG(s.days) FILTER (WHERE s.days >= 0))::float8 as avg_days,
+                   COUNT(*) FILTER (WHERE s.days < 0) as "excluded_count!"
+            FROM sales s
+            JOIN brands b ON s.brand_id = b.id
+            GROUP BY b.id, b.name
+            ORDER BY b.name
+            "#,
+            from,
+            to
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Незавершённые заявки - в статусах, из которых ещё возможен переход (Pending, Approved)
+    async fn count_active_for_car(&self, car_id: Uuid) -> Result<i64, Error> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM purchase_requests WHERE car_id = $1 AND status IN ('Pending', 'Approved')"
+        )
+            .bind(car_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn top_customers(&self, limit: i64) -> Result<Vec<TopCustomer>, Error> {
+        // SUM пропускает NULL offer_price; клиент, у которого все цены NULL, получает 0
+        sqlx::query_as!(
+            TopCustomer,
+            r#"
+            SELECT cu.id as customer_id, cu.first_name, cu.last_name, cu.email,
+                   COUNT(pr.id) as "purchases_count!",
+                   COALESCE(SUM(pr.offer_price), 0) as "total_spent!"
+            FROM purchase_requests pr
+            JOIN customers cu ON pr.customer_id = cu.id
+            WHERE pr.status = 'Completed'
+            GROUP BY cu.id
+            ORDER BY COALESCE(SUM(pr.offer_price), 0) DESC, COUNT(pr.id) DESC, cu.id
+            LIMIT $1
+            "#,
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Одобрение заявки и продажа автомобиля - одна транзакция: либо изменились оба, либо ничего
+    async fn approve_and_sell(&self, purchase_id: Uuid) -> Result<PurchaseRequest, ApproveAndSellError> {
+        let mut tx = self.pool.begin().await?;
+        let now = chrono::Utc::now();
+
+        let purchase = sqlx::query!(
+            r#"
+            SELECT car_id, status as "status: RequestStatus"
+            FROM purchase_requests
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            purchase_id
+        )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(ApproveAndSellError::PurchaseNotFound)?;
+
+        if !purchase.status.can_transition_to(&RequestStatus::Approved) {
+            return Err(ApproveAndSellError::InvalidTransition(purchase.status));
+        }
+
+        // Блокируем автомобиль, чтобы параллельное одобрение другой заявки не продало его дважды
+        let car_status: String = sqlx::query_scalar("SELECT status FROM cars WHERE id = $1 FOR UPDATE")
+            .bind(purchase.car_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if car_status == "Sold" {
+            return Err(ApproveAndSellError::CarAlreadySold);
+        }
+
+        let approved = sqlx::query_as!(
+            PurchaseRequest,
+            r#"
+            UPDATE purchase_requests
+            SET status = $1, updated_at = $2
+            WHERE id = $3
+            RETURNING id, car_id, customer_id, status as "status: _",
+                     offer_price, notes, created_at, updated_at
+            "#,
+            RequestStatus::Approved as RequestStatus,
+            now,
+            purchase_id
+        )
+            .fetch_one(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE cars SET status = 'Sold', updated_at = $1 WHERE id = $2")
+            .bind(now)
+            .bind(purchase.car_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(approved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Автомобиль и клиент для заявок; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestSale {
+        brand_id: Uuid,
+        car_id: Uuid,
+        customer_id: Uuid,
+    }
+
+    impl TestSale {
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xd2, 0xd2, 0xd2, 0xd2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let fixture = Self { brand_id: id(1), car_id: id(3), customer_id: id(4) };
+            fixture.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(fixture.brand_id)
+                .bind(format!("Test Brand {}", fixture.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(id(2))
+                .bind(fixture.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query(
+                "INSERT INTO cars (id, brand_id, model_id, year, price, mileage, color, fuel_type, transmission, vin)
+                 VALUES ($1, $2, $3, 2020, 1000000, 0, 'White', 'Petrol', 'Manual', $4)"
+            )
+                .bind(fixture.car_id)
+                .bind(fixture.brand_id)
+                .bind(id(2))
+                .bind(format!("D2D2D2000000000{:02}", n))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO customers (id, first_name, last_name, email, phone) VALUES ($1, 'Ivan', 'Petrov', $2, '+70000000000')")
+                .bind(fixture.customer_id)
+                .bind(format!("test-purchase-repo-{}@example.com", n))
+                .execute(pool).await.unwrap();
+            fixture
+        }
+
+        async fn purchase(&self, pool: &DbPool, status: &str, created_at: &str, updated_at: &str) -> Uuid {
+            sqlx::query_scalar(
+                "INSERT INTO purchase_requests (car_id, customer_id, status, offer_price, created_at, updated_at)
+                 VALUES ($1, $2, $3, 1100000, $4::timestamptz, $5::timestamptz)
+                 RETURNING id"
+            )
+                .bind(self.car_id)
+                .bind(self.customer_id)
+                .bind(status)
+                .bind(created_at)
+                .bind(updated_at)
+                .fetch_one(pool).await.unwrap()
+        }
+
+        // Заявки удаляются каскадом вместе с клиентом и автомобилем
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM customers WHERE id = $1")
+                .bind(self.customer_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM cars WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    fn at(moment: &str) -> Option<DateTime<Utc>> {
+        Some(moment.parse().unwrap())
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn sale_margins_are_filtered_by_the_moment_of_sale() {
+        let pool = test_pool().await;
+        let sale = TestSale::insert(&pool, 1).await;
+        let repo = PurchaseRepositoryImpl::new(pool.clone());
+        // Заявка создана в январе 2002 года, а продажа завершена в марте
+        let purchase_id = sale.purchase(&pool, "Completed", "2002-01-10T10:00:00Z", "2002-03-10T10:00:00Z").await;
+
+        let sold_in_march = repo.sale_margins(at("2002-03-01T00:00:00Z"), at("2002-04-01T00:00:00Z")).await.unwrap();
+        let sold_in_january = repo.sale_margins(at("2002-01-01T00:00:00Z"), at("2002-02-01T00:00:00Z")).await.unwrap();
+        sale.remove(&pool).await;
+
+        let margin = sold_in_march.iter().find(|margin| margin.purchase_id == purchase_id).unwrap();
+        assert_eq!(margin.margin, Some(100000.0));
+        assert!(sold_in_january.iter().all(|margin| margin.purchase_id != purchase_id));
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn batch_transitions_update_only_allowed_requests() {
+        let pool = test_pool().await;
+        let sale = TestSale::insert(&pool, 2).await;
+        let repo = PurchaseRepositoryImpl::new(pool.clone());
+        let pending = sale.purchase(&pool, "Pending", "2004-01-10T10:00:00Z", "2004-01-10T10:00:00Z").await;
+        let completed = sale.purchase(&pool, "Completed", "2004-01-11T10:00:00Z", "2004-01-12T10:00:00Z").await;
+        let missing = Uuid::new_v4();
+
+        let results = repo.update_status_batch(&[pending, completed, pending, missing], RequestStatus::Approved).await.unwrap();
+        let pending_after = repo.find_by_id(pending).await.unwrap().unwrap();
+        let completed_after = repo.find_by_id(completed).await.unwrap().unwrap();
+        sale.remove(&pool).await;
+
+        // Результаты в порядке запроса; повтор id и недопустимый переход не мешают остальным
+        let outcome: Vec<(Uuid, bool, Option<RequestStatus>)> = results.iter()
+            .map(|result| (result.id, result.success, result.previous_status.clone()))
+            .collect();
+        assert_eq!(outcome, vec![
+            (pending, true, Some(RequestStatus::Pending)),
+            (completed, false, Some(RequestStatus::Completed)),
+            (pending, false, None),
+            (missing, false, None),
+        ]);
+        assert_eq!(results[3].error.as_deref(), Some("Purchase request not found"));
+        assert_eq!(pending_after.status, RequestStatus::Approved);
+        assert_eq!(completed_after.status, RequestStatus::Completed);
+    }
+}
@@ -2,12 +2,15 @@ use async_trait::async_trait;
 use sqlx::{Error, Row};
 use uuid::Uuid;
 
-use crate::models::{ServiceCampaign, CreateServiceCampaignRequest, UpdateServiceCampaignRequest, ServiceCampaignStatus};
+use crate::models::{
+    ServiceCampaign, CreateServiceCampaignRequest, UpdateServiceCampaignRequest, ServiceCampaignStatus,
+    ServiceCampaignFilter, EnumVariants, TargetedVin, CampaignCompletion
+};
+use crate::config::ArticleCase;
 use crate::database::DbPool;
 
 #[async_trait]
 pub trait ServiceCampaignRepository: Send + Sync {
-    async fn find_all(&self) -> Result<Vec<ServiceCampaign>, Error>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<ServiceCampaign>, Error>;
     async fn find_by_article(&self, article: &str) -> Result<Option<ServiceCampaign>, Error>;
     async fn find_by_brand(&self, brand_id: Uuid) -> Result<Vec<ServiceCampaign>, Error>;
@@ -16,32 +19,43 @@ pub trait ServiceCampaignRepository: Send + Sync {
     async fn find_by_mandatory(&self, is_mandatory: bool) -> Result<Vec<ServiceCampaign>, Error>;
     async fn find_by_completed(&self, is_completed: bool) -> Result<Vec<ServiceCampaign>, Error>;
     async fn find_by_vin(&self, vin: &str) -> Result<Vec<ServiceCampaign>, Error>;
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<ServiceCampaign>, Error>;
+    async fn find_filtered_paginated(&self, filter: &ServiceCampaignFilter, limit: i64, offset: i64) -> Result<(Vec<ServiceCampaign>, i64), Error>;
     async fn exists_by_article(&self, article: &str) -> Result<bool, Error>;
     async fn save(&self, create_request: &CreateServiceCampaignRequest) -> Result<ServiceCampaign, Error>;
+    async fn save_many(&self, create_requests: &[&CreateServiceCampaignRequest]) -> Result<Vec<ServiceCampaign>, Error>;
     async fn update(&self, id: Uuid, update_request: &UpdateServiceCampaignRequest) -> Result<Option<ServiceCampaign>, Error>;
     async fn delete(&self, id: Uuid) -> Result<bool, Error>;
     async fn update_status(&self, id: Uuid, status: ServiceCampaignStatus) -> Result<Option<ServiceCampaign>, Error>;
     async fn mark_completed(&self, id: Uuid) -> Result<Option<ServiceCampaign>, Error>;
     async fn mark_pending(&self, id: Uuid) -> Result<Option<ServiceCampaign>, Error>;
+    async fn reactivate(&self, id: Uuid) -> Result<Option<ServiceCampaign>, Error>;
+    async fn collect_target_vins(&self, status: ServiceCampaignStatus, expand_models: bool) -> Result<Vec<TargetedVin>, Error>;
+    async fn recalculate_completion(&self, id: Uuid) -> Result<Option<CampaignCompletion>, Error>;
 }
 
 #[derive(Clone)]
 pub struct ServiceCampaignRepositoryImpl {
     pool: DbPool,
+    article_case: ArticleCase,
+    strict_enum_decoding: bool,
 }
 
 impl ServiceCampaignRepositoryImpl {
     pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+        Self { pool, article_case: ArticleCase::Preserve, strict_enum_decoding: false }
     }
-    
-    fn status_from_str(status: &str) -> ServiceCampaignStatus {
-        match status.to_lowercase().as_str() {
-            "active" => ServiceCampaignStatus::Active,
-            "completed" => ServiceCampaignStatus::Completed,
-            "cancelled" => ServiceCampaignStatus::Cancelled,
-            _ => ServiceCampaignStatus::Active, // default
-        }
+
+    // Артикул при сохранении и обновлении приводится к заданному регистру
+    pub fn with_article_case(mut self, article_case: ArticleCase) -> Self {
+        self.article_case = article_case;
+        self
+    }
+
+    // Неизвестный статус в строке БД - ошибка декодирования, а не Active
+    pub fn with_strict_enum_decoding(mut self, strict: bool) -> Self {
+        self.strict_enum_decoding = strict;
+        self
     }
     
     fn campaign_from_row(&self, row: sqlx::postgres::PgRow) -> Result<ServiceCampaign, Error> {
@@ -62,7 +76,7 @@ impl ServiceCampaignRepositoryImpl {
             required_works,
             is_mandatory: row.try_get("is_mandatory")?,
             is_completed: row.try_get("is_completed")?,
-            status: Self::status_from_str(&status_str),
+            status: ServiceCampaignStatus::from_db(&status_str, self.strict_enum_decoding)?,
             created_at: row.try_get("created_at")?,
             updated_at: row.try_get("updated_at")?,
         })
@@ -71,27 +85,6 @@ impl ServiceCampaignRepositoryImpl {
 
 #[async_trait]
 impl ServiceCampaignRepository for ServiceCampaignRepositoryImpl {
-    async fn find_all(&self) -> Result<Vec<ServiceCampaign>, Error> {
-        let rows = sqlx::query(
-            r#"
-            SELECT id, article, name, description, brand_id, car_model_id,
-                   target_vins, required_parts, required_works,
-                   is_mandatory, is_completed,
-                   status, created_at, updated_at
-            FROM service_campaigns
-            ORDER BY created_at DESC
-            "#
-        )
-            .fetch_all(&self.pool)
-            .await?;
-
-        let mut campaigns = Vec::new();
-        for row in rows {
-            campaigns.push(self.campaign_from_row(row)?);
-        }
-        Ok(campaigns)
-    }
-
     async fn find_by_id(&self, id: Uuid) -> Result<Option<ServiceCampaign>, Error> {
         let row = sqlx::query(
             r#"
@@ -121,7 +114,7 @@ impl ServiceCampaignRepository for ServiceCampaignRepositoryImpl {
                    is_mandatory, is_completed,
                    status, created_at, updated_at
             FROM service_campaigns
-            WHERE article = $1
+            WHERE LOWER(article) = LOWER($1)
             "#
         )
             .bind(article)
@@ -278,9 +271,61 @@ impl ServiceCampaignRepository for ServiceCampaignRepositoryImpl {
         Ok(campaigns)
     }
 
+    async fn find_filtered_paginated(&self, filter: &ServiceCampaignFilter, limit: i64, offset: i64) -> Result<(Vec<ServiceCampaign>, i64), Error> {
+        let status = filter.status.as_ref().map(|status| status.value());
+        let where_clause = r#"
+            WHERE ($1::uuid IS NULL OR brand_id = $1)
+              AND ($2::uuid IS NULL OR car_model_id = $2)
+              AND ($3::varchar IS NULL OR status = $3)
+              AND ($4::bool IS NULL OR is_mandatory = $4)
+              AND ($5::bool IS NULL OR is_completed = $5)
+              AND ($6::text IS NULL OR $6 = ANY(target_vins))
+        "#;
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM service_campaigns {}", where_clause))
+            .bind(filter.brand_id)
+            .bind(filter.car_model_id)
+            .bind(status)
+            .bind(filter.is_mandatory)
+            .bind(filter.is_completed)
+            .bind(filter.vin.as_deref())
+            .fetch_one(&self.pool)
+            .await?;
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT id, article, name, description, brand_id, car_model_id,
+                   target_vins, required_parts, required_works,
+                   is_mandatory, is_completed,
+                   status, created_at, updated_at
+            FROM service_campaigns
+            {}
+            ORDER BY created_at DESC, id
+            LIMIT $7 OFFSET $8
+            "#,
+            where_clause
+        ))
+            .bind(filter.brand_id)
+            .bind(filter.car_model_id)
+            .bind(status)
+            .bind(filter.is_mandatory)
+            .bind(filter.is_completed)
+            .bind(filter.vin.as_deref())
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut campaigns = Vec::new();
+        for row in rows {
+            campaigns.push(self.campaign_from_row(row)?);
+        }
+        Ok((campaigns, total))
+    }
+
     async fn exists_by_article(&self, article: &str) -> Result<bool, Error> {
         let result = sqlx::query(
-            "SELECT id FROM service_campaigns WHERE article = $1 LIMIT 1"
+            "SELECT id FROM service_campaigns WHERE LOWER(article) = LOWER($1) LIMIT 1"
         )
             .bind(article)
             .fetch_optional(&self.pool)
@@ -306,7 +351,7 @@ impl ServiceCampaignRepository for ServiceCampaignRepositoryImpl {
             "#
         )
             .bind(id)
-            .bind(&create_request.article)
+            .bind(self.article_case.apply(&create_request.article))
             .bind(&create_request.name)
             .bind(&create_request.description)
             .bind(create_request.brand_id)
@@ -323,10 +368,55 @@ impl ServiceCampaignRepository for ServiceCampaignRepositoryImpl {
         self.campaign_from_row(row)
     }
 
+    async fn save_many(&self, create_requests: &[&CreateServiceCampaignRequest]) -> Result<Vec<ServiceCampaign>, Error> {
+        let now = chrono::Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let mut campaigns = Vec::with_capacity(create_requests.len());
+
+        // Все кампании сохраняются в одной транзакции: ошибка любой строки откатывает весь пакет
+        for create_request in create_requests {
+            let row = sqlx::query(
+                r#"
+                INSERT INTO service_campaigns (id, article, name, description, brand_id, car_model_id,
+                                             target_vins, required_parts, required_works,
+                                             is_mandatory, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                RETURNING id, article, name, description, brand_id, car_model_id,
+                         target_vins, required_parts, required_works,
+                         is_mandatory, is_completed,
+                         status, created_at, updated_at
+                "#
+            )
+                .bind(Uuid::new_v4())
+                .bind(self.article_case.apply(&create_request.article))
+                .bind(&create_request.name)
+                .bind(&create_request.description)
+                .bind(create_request.brand_id)
+                .bind(create_request.car_model_id)
+                .bind(&create_request.target_vins)
+                .bind(&create_request.required_parts)
+                .bind(&create_request.required_works)
+                .bind(create_request.is_mandatory)
+                .bind(now)
+                .bind(now)
+                .fetch_one(&mut *tx)
+                .await?;
+
+            campaigns.push(self.campaign_from_row(row)?);
+        }
+
+        tx.commit().await?;
+        Ok(campaigns)
+    }
+
     async fn update(&self, id: Uuid, update_request: &UpdateServiceCampaignRequest) -> Result<Option<ServiceCampaign>, Error> {
         let now = chrono::Utc::now();
 
         if let Some(current_campaign) = self.find_by_id(id).await? {
+            let article = match &update_request.article {
+                Some(article) => self.article_case.apply(article),
+                None => current_campaign.article.clone(),
+            };
             let status = update_request.status.as_ref().unwrap_or(&current_campaign.status);
             let status_str = match status {
                 ServiceCampaignStatus::Active => "active",
@@ -347,7 +437,7 @@ impl ServiceCampaignRepository for ServiceCampaignRepositoryImpl {
                          status, created_at, updated_at
                 "#
             )
-                .bind(update_request.article.as_ref().unwrap_or(&current_campaign.article))
+                .bind(article)
                 .bind(update_request.name.as_ref().unwrap_or(&current_campaign.name))
                 .bind(update_request.description.as_ref().or(current_campaign.description.as_ref()))
                 .bind(update_request.brand_id.unwrap_or(current_campaign.brand_id))
@@ -464,4 +554,275 @@ impl ServiceCampaignRepository for ServiceCampaignRepositoryImpl {
             None => Ok(None),
         }
     }
-}
\ No newline at end of file
+
+    async fn reactivate(&self, id: Uuid) -> Result<Option<ServiceCampaign>, Error> {
+        let now = chrono::Utc::now();
+
+        // Переход возможен только из статуса cancelled, иначе запись не обновляется
+        let row = sqlx::query(
+            r#"
+            UPDATE service_campaigns
+            SET is_completed = false, status = 'active', updated_at = $1
+            WHERE id = $2 AND status = 'cancelled'
+            RETURNING id, article, name, description, brand_id, car_model_id,
+                     target_vins, required_parts, required_works,
+                     is_mandatory, is_completed,
+                     status, created_at, updated_at
+            "#
+        )
+            .bind(now)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.campaign_from_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<ServiceCampaign>, Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, article, name, description, brand_id, car_model_id,
+                   target_vins, required_parts, required_works,
+                   is_mandatory, is_completed,
+                   status, created_at, updated_at
+            FROM service_campaigns
+            WHERE name ILIKE $1 OR article ILIKE $1 OR description ILIKE $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#
+        )
+            .bind(format!("%{}%", query))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut campaigns = Vec::new();
+        for row in rows {
+            campaigns.push(self.campaign_from_row(row)?);
+        }
+        Ok(campaigns)
+    }
+
+    async fn collect_target_vins(&self, status: ServiceCampaignStatus, expand_models: bool) -> Result<Vec<TargetedVin>, Error> {
+        // Пустой target_vins означает "вся модель": при expand_models такие кампании
+        // разворачиваются в VIN всех автомобилей модели
+        let rows = sqlx::query!(
+            r#"
+            WITH matching AS (
+                SELECT id, car_model_id, target_vins
+                FROM service_campaigns
+                WHERE status = $1
+            ),
+            targets AS (
+                SELECT vin, m.id as campaign_id
+                FROM matching m, UNNEST(m.target_vins) as vin
+                UNION ALL
+                SELECT c.vin, m.id
+                FROM matching m
+                JOIN cars c ON c.model_id = m.car_model_id AND c.deleted_at IS NULL
+                WHERE $2 AND cardinality(m.target_vins) = 0
+            )
+            SELECT t.vin as "vin!", c.id as "car_id?",
+                   ARRAY_AGG(DISTINCT t.campaign_id) as "campaign_ids!"
+            FROM targets t
+            LEFT JOIN cars c ON c.vin = t.vin AND c.deleted_at IS NULL
+            GROUP BY t.vin, c.id
+            ORDER BY t.vin
+            "#,
+            status.value(),
+            expand_models
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| TargetedVin {
+            vin: row.vin,
+            car_id: row.car_id,
+            campaign_ids: row.campaign_ids,
+        }).collect())
+    }
+
+    async fn recalculate_completion(&self, id: Uuid) -> Result<Option<CampaignCompletion>, Error> {
+        let now = chrono::Utc::now();
+
+        // updated_at меняется только при фактической смене статуса; в итоговом SELECT таблица
+        // видна до обновления, поэтому новое значение берётся из updated
+        let row = sqlx::query!(
+            r#"
+            WITH counts AS (
+                SELECT sc.id,
+                       COUNT(c.id) as applicable,
+                       COUNT(c.id) FILTER (WHERE NOT sc.id = ANY(c.completed_service_campaigns)) as remaining,
+                       COUNT(c.id) > 0
+                           AND COUNT(c.id) FILTER (WHERE NOT sc.id = ANY(c.completed_service_campaigns)) = 0 as done
+                FROM service_campaigns sc
+                LEFT JOIN cars c ON c.brand_id = sc.brand_id
+                                AND c.model_id = sc.car_model_id
+                                AND (cardinality(sc.target_vins) = 0 OR c.vin = ANY(sc.target_vins))
+                                AND c.deleted_at IS NULL
+                WHERE sc.id = $1
+                GROUP BY sc.id
+            ),
+            updated AS (
+                UPDATE service_campaigns sc
+                SET is_completed = counts.done,
+                    status = CASE WHEN counts.done THEN 'completed' ELSE 'active' END,
+                    updated_at = $2
+                FROM counts
+                WHERE sc.id = counts.id
+                  AND sc.status <> 'cancelled'
+                  AND (sc.is_completed <> counts.done
+                       OR sc.status <> CASE WHEN counts.done THEN 'completed' ELSE 'active' END)
+                RETURNING sc.id, sc.status, sc.is_completed
+            )
+            SELECT counts.id as "id!",
+                   COALESCE(updated.status, sc.status) as "status!",
+                   COALESCE(updated.is_completed, sc.is_completed) as "is_completed!",
+                   counts.applicable as "applicable!",
+                   counts.remaining as "remaining!"
+            FROM counts
+            JOIN service_campaigns sc ON sc.id = counts.id
+            LEFT JOIN updated ON updated.id = counts.id
+            "#,
+            id,
+            now
+        )
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(CampaignCompletion {
+                campaign_id: row.id,
+                status: ServiceCampaignStatus::from_db(&row.status, self.strict_enum_decoding)?,
+                is_completed: row.is_completed,
+                applicable_cars: row.applicable,
+                remaining_cars: row.remaining,
+            })),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Бренд с моделью и кампанией по этой модели; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestCampaign {
+        brand_id: Uuid,
+        model_id: Uuid,
+        campaign_id: Uuid,
+    }
+
+    impl TestCampaign {
+        // Кампания создаётся уже помеченной выполненной, чтобы пересчёт обязан был её изменить
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xef, 0xef, 0xef, 0xef, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let campaign = Self { brand_id: id(1), model_id: id(2), campaign_id: id(3) };
+            campaign.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(campaign.brand_id)
+                .bind(format!("Test Brand {}", campaign.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(campaign.model_id)
+                .bind(campaign.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query(
+                "INSERT INTO service_campaigns (id, article, name, brand_id, car_model_id, is_completed, status)
+                 VALUES ($1, $2, 'Test Campaign', $3, $4, TRUE, 'completed')"
+            )
+                .bind(campaign.campaign_id)
+                .bind(format!("TEST-CAMPAIGN-{}", n))
+                .bind(campaign.brand_id)
+                .bind(campaign.model_id)
+                .execute(pool).await.unwrap();
+            campaign
+        }
+
+        async fn insert_car(&self, pool: &DbPool, vin: &str, completed: bool) {
+            let completed_campaigns = if completed { vec![self.campaign_id] } else { Vec::new() };
+            sqlx::query(
+                "INSERT INTO cars (brand_id, model_id, year, price, mileage, color, fuel_type, transmission, vin, completed_service_campaigns)
+                 VALUES ($1, $2, 2020, 1000000, 0, 'White', 'Petrol', 'Manual', $3, $4)"
+            )
+                .bind(self.brand_id)
+                .bind(self.model_id)
+                .bind(vin)
+                .bind(completed_campaigns)
+                .execute(pool).await.unwrap();
+        }
+
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM service_campaigns WHERE id = $1")
+                .bind(self.campaign_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM cars WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn campaign_without_applicable_cars_is_not_completed() {
+        let pool = test_pool().await;
+        let campaign = TestCampaign::insert(&pool, 1).await;
+
+        let completion = ServiceCampaignRepositoryImpl::new(pool.clone())
+            .recalculate_completion(campaign.campaign_id).await.unwrap().unwrap();
+        campaign.remove(&pool).await;
+
+        assert_eq!(completion.applicable_cars, 0);
+        assert!(!completion.is_completed);
+        assert_eq!(completion.status, ServiceCampaignStatus::Active);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn campaign_done_on_every_applicable_car_is_completed() {
+        let pool = test_pool().await;
+        let campaign = TestCampaign::insert(&pool, 2).await;
+        campaign.insert_car(&pool, "EFEFEF00000000002", true).await;
+        let repo = ServiceCampaignRepositoryImpl::new(pool.clone());
+
+        let completed = repo.recalculate_completion(campaign.campaign_id).await.unwrap().unwrap();
+        campaign.insert_car(&pool, "EFEFEF00000000003", false).await;
+        let reopened = repo.recalculate_completion(campaign.campaign_id).await.unwrap().unwrap();
+        campaign.remove(&pool).await;
+
+        assert_eq!((completed.applicable_cars, completed.remaining_cars), (1, 0));
+        assert!(completed.is_completed);
+        assert_eq!(completed.status, ServiceCampaignStatus::Completed);
+        assert_eq!((reopened.applicable_cars, reopened.remaining_cars), (2, 1));
+        assert_eq!(reopened.status, ServiceCampaignStatus::Active);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn only_cancelled_campaign_is_reactivated() {
+        let pool = test_pool().await;
+        let campaign = TestCampaign::insert(&pool, 3).await;
+        let repo = ServiceCampaignRepositoryImpl::new(pool.clone());
+
+        // Выполненная кампания не реактивируется
+        let from_completed = repo.reactivate(campaign.campaign_id).await.unwrap();
+        repo.update_status(campaign.campaign_id, ServiceCampaignStatus::Cancelled).await.unwrap().unwrap();
+        let reactivated = repo.reactivate(campaign.campaign_id).await.unwrap();
+        let again = repo.reactivate(campaign.campaign_id).await.unwrap();
+        campaign.remove(&pool).await;
+
+        assert!(from_completed.is_none());
+        let reactivated = reactivated.unwrap();
+        assert_eq!(reactivated.status, ServiceCampaignStatus::Active);
+        assert!(!reactivated.is_completed);
+        assert!(again.is_none());
+    }
+}
@@ -1,13 +1,47 @@
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
+use chrono::NaiveDate;
 use sqlx::Error;
 use uuid::Uuid;
 
 use crate::models::warehouse::{
     WarehouseItem, WarehouseItemWithPart, CreateWarehouseItemRequest,
-    UpdateWarehouseItemRequest, StockMovementRequest, StockMovementType
+    UpdateWarehouseItemRequest, StockMovementRequest, StockMovementType, LowStockPart,
+    InventorySnapshotSummary, SnapshotPartDelta, WarehouseDuplicate,
+    ReservePartItem, ReservedPart, InsufficientPart, PartsReservation, ReorderCost, ReorderCostItem,
+    StockForecast, StockMovement, WarehouseStats, DEFAULT_MIN_STOCK_LEVEL, DEFAULT_MAX_STOCK_LEVEL
 };
 use crate::database::DbPool;
 
+/// Ошибка движения запаса: позиции нет или расход превышает остаток
+#[derive(Debug)]
+pub enum StockError {
+    NotFound,
+    InsufficientStock { available: i32, requested: i32 },
+    Database(Error),
+}
+
+impl std::fmt::Display for StockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StockError::NotFound => write!(f, "Warehouse item not found"),
+            StockError::InsufficientStock { available, requested } => {
+                write!(f, "Insufficient stock: requested {}, available {}", requested, available)
+            }
+            StockError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for StockError {}
+
+impl From<Error> for StockError {
+    fn from(e: Error) -> Self {
+        StockError::Database(e)
+    }
+}
+
 #[async_trait]
 pub trait WarehouseRepository: Send + Sync {
     async fn find_all(&self) -> Result<Vec<WarehouseItemWithPart>, Error>;
@@ -17,11 +51,27 @@ pub trait WarehouseRepository: Send + Sync {
     async fn find_by_article(&self, article: &str) -> Result<Option<WarehouseItemWithPart>, Error>;
     async fn find_by_location(&self, location: &str) -> Result<Vec<WarehouseItemWithPart>, Error>;
     async fn exists_by_part_id(&self, part_id: Uuid) -> Result<bool, Error>;
+    /// Уровни запаса новой позиции: из запроса, иначе значения бренда запчасти, иначе общие
+    async fn resolve_stock_levels(&self, part_id: Uuid, min_stock_level: Option<i32>, max_stock_level: Option<i32>) -> Result<(i32, i32), Error>;
     async fn save(&self, create_request: &CreateWarehouseItemRequest) -> Result<WarehouseItem, Error>;
     async fn update(&self, id: Uuid, update_request: &UpdateWarehouseItemRequest) -> Result<Option<WarehouseItem>, Error>;
     async fn delete(&self, id: Uuid) -> Result<bool, Error>;
-    async fn update_stock(&self, part_id: Uuid, movement_request: &StockMovementRequest) -> Result<Option<WarehouseItem>, Error>;
+    async fn update_stock(&self, part_id: Uuid, movement_request: &StockMovementRequest) -> Result<WarehouseItem, StockError>;
+    async fn find_movements(&self, part_id: Uuid, limit: i64, offset: i64) -> Result<(Vec<StockMovement>, i64), Error>;
+    async fn update_location(&self, id: Uuid, location: &str) -> Result<Option<WarehouseItem>, Error>;
+    async fn reserve_parts(&self, items: &[ReservePartItem], reference: &str, reason: Option<&str>) -> Result<PartsReservation, Error>;
     async fn get_total_value(&self) -> Result<f64, Error>;
+    async fn reorder_cost(&self) -> Result<ReorderCost, Error>;
+    async fn stats(&self) -> Result<WarehouseStats, Error>;
+    async fn forecast_depletion(&self, part_id: Uuid, days: i32) -> Result<Option<StockForecast>, Error>;
+    async fn low_stock_by_model(&self, car_model_id: Uuid) -> Result<Vec<LowStockPart>, Error>;
+    async fn find_duplicates(&self) -> Result<Vec<WarehouseDuplicate>, Error>;
+    async fn find_orphans(&self) -> Result<Vec<WarehouseItem>, Error>;
+    async fn delete_orphans(&self) -> Result<Vec<Uuid>, Error>;
+    /// None - склад пуст: снимок без строк нельзя было бы найти при сравнении, поэтому он не сохраняется
+    async fn create_snapshot(&self, snapshot_date: NaiveDate) -> Result<Option<InventorySnapshotSummary>, Error>;
+    async fn find_snapshot_summary(&self, snapshot_date: NaiveDate) -> Result<Option<InventorySnapshotSummary>, Error>;
+    async fn compare_snapshots(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<SnapshotPartDelta>, Error>;
 }
 
 #[derive(Clone)]
@@ -153,8 +203,36 @@ impl WarehouseRepository for WarehouseRepositoryImpl {
         Ok(result.is_some())
     }
 
+    async fn resolve_stock_levels(&self, part_id: Uuid, min_stock_level: Option<i32>, max_stock_level: Option<i32>) -> Result<(i32, i32), Error> {
+        // Незаданные уровни: сначала значения бренда запчасти (brand_stock_defaults), затем общие
+        let levels = sqlx::query!(
+            r#"
+            SELECT COALESCE($2, (SELECT d.min_stock_level
+                                 FROM brand_stock_defaults d
+                                 JOIN parts p ON p.brand_id = d.brand_id
+                                 WHERE p.id = $1), $4) as "min_stock_level!",
+                   COALESCE($3, (SELECT d.max_stock_level
+                                 FROM brand_stock_defaults d
+                                 JOIN parts p ON p.brand_id = d.brand_id
+                                 WHERE p.id = $1), $5) as "max_stock_level!"
+            "#,
+            part_id,
+            min_stock_level,
+            max_stock_level,
+            DEFAULT_MIN_STOCK_LEVEL,
+            DEFAULT_MAX_STOCK_LEVEL
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((levels.min_stock_level, levels.max_stock_level))
+    }
+
     async fn save(&self, create_request: &CreateWarehouseItemRequest) -> Result<WarehouseItem, Error> {
         let now = chrono::Utc::now();
+        let (min_stock_level, max_stock_level) = self
+            .resolve_stock_levels(create_request.part_id, create_request.min_stock_level, create_request.max_stock_level)
+            .await?;
 
         sqlx::query_as!(
             WarehouseItem,
@@ -166,8 +244,8 @@ impl WarehouseRepository for WarehouseRepositoryImpl {
             Uuid::new_v4(),
             create_request.part_id,
             create_request.quantity,
-            create_request.min_stock_level.unwrap_or(0),
-            create_request.max_stock_level.unwrap_or(100),
+            min_stock_level,
+            max_stock_level,
             create_request.location,
             now,
             now
@@ -217,43 +295,283 @@ impl WarehouseRepository for WarehouseRepositoryImpl {
         Ok(result.rows_affected() > 0)
     }
 
-    async fn update_stock(&self, part_id: Uuid, movement_request: &StockMovementRequest) -> Result<Option<WarehouseItem>, Error> {
+    async fn update_stock(&self, part_id: Uuid, movement_request: &StockMovementRequest) -> Result<WarehouseItem, StockError> {
         let now = chrono::Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        // Блокируем позицию, чтобы остаток не изменился между проверкой и списанием
+        let available = sqlx::query_scalar!(
+            "SELECT quantity FROM warehouse WHERE part_id = $1 FOR UPDATE",
+            part_id
+        )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(StockError::NotFound)?;
+
+        if movement_request.movement_type == StockMovementType::Outgoing && movement_request.quantity > available {
+            return Err(StockError::InsufficientStock { available, requested: movement_request.quantity });
+        }
 
-        let new_quantity = match movement_request.movement_type {
+        let item = match movement_request.movement_type {
             StockMovementType::Incoming => {
-                sqlx::query!(
-                    "UPDATE warehouse SET quantity = quantity + $1, updated_at = $2 WHERE part_id = $3",
+                sqlx::query_as!(
+                    WarehouseItem,
+                    r#"
+                    UPDATE warehouse SET quantity = quantity + $1, updated_at = $2 WHERE part_id = $3
+                    RETURNING id, part_id, quantity, min_stock_level, max_stock_level, location, created_at, updated_at
+                    "#,
                     movement_request.quantity,
                     now,
                     part_id
                 )
+                    .fetch_optional(&mut *tx)
+                    .await?
             }
             StockMovementType::Outgoing => {
-                sqlx::query!(
-                    "UPDATE warehouse SET quantity = quantity - $1, updated_at = $2 WHERE part_id = $3 AND quantity >= $1",
+                sqlx::query_as!(
+                    WarehouseItem,
+                    r#"
+                    UPDATE warehouse SET quantity = quantity - $1, updated_at = $2 WHERE part_id = $3 AND quantity >= $1
+                    RETURNING id, part_id, quantity, min_stock_level, max_stock_level, location, created_at, updated_at
+                    "#,
                     movement_request.quantity,
                     now,
                     part_id
                 )
+                    .fetch_optional(&mut *tx)
+                    .await?
             }
             StockMovementType::Adjustment => {
-                sqlx::query!(
-                    "UPDATE warehouse SET quantity = $1, updated_at = $2 WHERE part_id = $3",
+                sqlx::query_as!(
+                    WarehouseItem,
+                    r#"
+                    UPDATE warehouse SET quantity = $1, updated_at = $2 WHERE part_id = $3
+                    RETURNING id, part_id, quantity, min_stock_level, max_stock_level, location, created_at, updated_at
+                    "#,
                     movement_request.quantity,
                     now,
                     part_id
                 )
+                    .fetch_optional(&mut *tx)
+                    .await?
             }
+        };
+
+        let item = item.ok_or(StockError::NotFound)?;
+
+        let movement_type = match movement_request.movement_type {
+            StockMovementType::Incoming => "incoming",
+            StockMovementType::Outgoing => "outgoing",
+            StockMovementType::Adjustment => "adjustment",
+        };
+
+        // Движение пишется в той же транзакции, что и изменение остатка
+        sqlx::query!(
+            r#"
+            INSERT INTO stock_movements (id, warehouse_item_id, part_id, movement_type, quantity, resulting_quantity, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            Uuid::new_v4(),
+            item.id,
+            item.part_id,
+            movement_type,
+            movement_request.quantity,
+            item.quantity,
+            now
+        )
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(item)
+    }
+
+    async fn find_movements(&self, part_id: Uuid, limit: i64, offset: i64) -> Result<(Vec<StockMovement>, i64), Error> {
+        let movements = sqlx::query_as!(
+            StockMovement,
+            r#"
+            SELECT id, warehouse_item_id, part_id, movement_type, quantity, resulting_quantity,
+                   from_location, to_location, reference, reason, created_at
+            FROM stock_movements
+            WHERE part_id = $1
+            ORDER BY created_at DESC, id
+            LIMIT $2 OFFSET $3
+            "#,
+            part_id,
+            limit,
+            offset
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM stock_movements WHERE part_id = $1"#,
+            part_id
+        )
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((movements, total))
+    }
+
+    async fn update_location(&self, id: Uuid, location: &str) -> Result<Option<WarehouseItem>, Error> {
+        let mut tx = self.pool.begin().await?;
+        let now = chrono::Utc::now();
+
+        let current = sqlx::query!(
+            "SELECT location FROM warehouse WHERE id = $1 FOR UPDATE",
+            id
+        )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let Some(current) = current else {
+            return Ok(None);
+        };
+
+        let item = sqlx::query_as!(
+            WarehouseItem,
+            r#"
+            UPDATE warehouse
+            SET location = $1, updated_at = $2
+            WHERE id = $3
+            RETURNING id, part_id, quantity, min_stock_level, max_stock_level,
+                     location, created_at, updated_at
+            "#,
+            location,
+            now,
+            id
+        )
+            .fetch_one(&mut *tx)
+            .await?;
+
+        // Перемещение фиксируем только при фактической смене места хранения
+        if current.location.as_deref() != Some(location) {
+            sqlx::query!(
+                r#"
+                INSERT INTO stock_movements (id, warehouse_item_id, part_id, movement_type, quantity, resulting_quantity,
+                                             from_location, to_location, created_at)
+                VALUES ($1, $2, $3, 'relocation', $4, $4, $5, $6, $7)
+                "#,
+                Uuid::new_v4(),
+                item.id,
+                item.part_id,
+                item.quantity,
+                current.location,
+                location,
+                now
+            )
+                .execute(&mut *tx)
+                .await?;
         }
-            .execute(&self.pool)
+
+        tx.commit().await?;
+        Ok(Some(item))
+    }
+
+    async fn reserve_parts(&self, items: &[ReservePartItem], reference: &str, reason: Option<&str>) -> Result<PartsReservation, Error> {
+        // Повторяющиеся запчасти суммируются; BTreeMap задаёт единый порядок блокировок
+        let mut requested: BTreeMap<Uuid, i64> = BTreeMap::new();
+        for item in items {
+            *requested.entry(item.part_id).or_insert(0) += i64::from(item.quantity);
+        }
+        let part_ids: Vec<Uuid> = requested.keys().copied().collect();
+
+        let mut tx = self.pool.begin().await?;
+        let now = chrono::Utc::now();
+
+        // Блокируем позиции до конца транзакции, чтобы параллельное резервирование
+        // не списало тот же остаток между проверкой и обновлением
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, part_id, quantity
+            FROM warehouse
+            WHERE part_id = ANY($1)
+            ORDER BY part_id, quantity DESC, id
+            FOR UPDATE
+            "#,
+            &part_ids
+        )
+            .fetch_all(&mut *tx)
             .await?;
 
-        if new_quantity.rows_affected() > 0 {
-            self.find_by_part_id(part_id).await
-        } else {
-            Ok(None)
+        let mut stock: BTreeMap<Uuid, Vec<(Uuid, i64)>> = BTreeMap::new();
+        for row in rows {
+            stock.entry(row.part_id).or_default().push((row.id, i64::from(row.quantity)));
+        }
+
+        let insufficient: Vec<InsufficientPart> = requested
+            .iter()
+            .filter_map(|(part_id, quantity)| {
+                let available: i64 = stock.get(part_id).map(|items| items.iter().map(|(_, qty)| qty).sum()).unwrap_or(0);
+                (available < *quantity).then_some(InsufficientPart {
+                    part_id: *part_id,
+                    requested: *quantity,
+                    available,
+                })
+            })
+            .collect();
+        if !insufficient.is_empty() {
+            tx.rollback().await?;
+            return Ok(PartsReservation::Insufficient(insufficient));
+        }
+
+        let mut reserved = Vec::with_capacity(requested.len());
+        for (part_id, quantity) in requested {
+            let items = stock.remove(&part_id).unwrap_or_default();
+            let available: i64 = items.iter().map(|(_, qty)| qty).sum();
+
+            // Если запчасть лежит в нескольких позициях, списываем начиная с самой большой
+            let mut remaining_to_take = quantity;
+            for (warehouse_item_id, item_quantity) in items {
+                if remaining_to_take == 0 {
+                    break;
+                }
+                let take = remaining_to_take.min(item_quantity);
+                if take == 0 {
+                    continue;
+                }
+                remaining_to_take -= take;
+
+                // take не превышает остаток позиции, который помещается в i32
+                let take = take as i32;
+                let resulting_quantity = sqlx::query_scalar!(
+                    "UPDATE warehouse SET quantity = quantity - $1, updated_at = $2 WHERE id = $3 RETURNING quantity",
+                    take,
+                    now,
+                    warehouse_item_id
+                )
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO stock_movements (id, warehouse_item_id, part_id, movement_type, quantity, resulting_quantity,
+                                                 reference, reason, created_at)
+                    VALUES ($1, $2, $3, 'outgoing', $4, $5, $6, $7, $8)
+                    "#,
+                    Uuid::new_v4(),
+                    warehouse_item_id,
+                    part_id,
+                    take,
+                    resulting_quantity,
+                    reference,
+                    reason,
+                    now
+                )
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            reserved.push(ReservedPart {
+                part_id,
+                quantity,
+                remaining: available - quantity,
+            });
         }
+
+        tx.commit().await?;
+        Ok(PartsReservation::Reserved(reserved))
     }
 
     async fn get_total_value(&self) -> Result<f64, Error> {
@@ -269,4 +587,448 @@ impl WarehouseRepository for WarehouseRepositoryImpl {
 
         Ok(result.total_value.unwrap_or(0.0))
     }
-}
\ No newline at end of file
+
+    async fn reorder_cost(&self) -> Result<ReorderCost, Error> {
+        let items = sqlx::query_as!(
+            ReorderCostItem,
+            r#"
+            SELECT
+                p.id as part_id, p.article as part_article, p.name as part_name,
+                w.quantity, w.min_stock_level, w.max_stock_level,
+                GREATEST(w.max_stock_level - w.quantity, 0) as "suggested_reorder!",
+                p.purchase_price,
+                GREATEST(w.max_stock_level - w.quantity, 0) * p.purchase_price as "reorder_cost!"
+            FROM warehouse w
+            JOIN parts p ON w.part_id = p.id
+            WHERE w.quantity <= w.min_stock_level
+            ORDER BY GREATEST(w.max_stock_level - w.quantity, 0) * p.purchase_price DESC, p.article
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(ReorderCost::new(items))
+    }
+
+    async fn stats(&self) -> Result<WarehouseStats, Error> {
+        // Агрегат без GROUP BY всегда возвращает одну строку, поэтому пустой склад даёт нули
+        sqlx::query_as!(
+            WarehouseStats,
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE w.quantity > 0) as "parts_stocked!",
+                COALESCE(SUM(w.quantity), 0) as "total_units!",
+                COALESCE(SUM(w.quantity * p.purchase_price), 0) as "value_at_cost!",
+                COALESCE(SUM(w.quantity * p.sale_price), 0) as "value_at_retail!",
+                COUNT(*) FILTER (WHERE w.quantity <= w.min_stock_level) as "low_stock_items!",
+                COUNT(*) FILTER (WHERE w.quantity > w.max_stock_level) as "overstock_items!",
+                COUNT(DISTINCT NULLIF(TRIM(w.location), '')) as "locations!"
+            FROM warehouse w
+            JOIN parts p ON w.part_id = p.id
+            "#
+        )
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn forecast_depletion(&self, part_id: Uuid, days: i32) -> Result<Option<StockForecast>, Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                w.quantity, w.min_stock_level,
+                (SELECT COALESCE(SUM(m.quantity), 0)
+                 FROM stock_movements m
+                 WHERE m.warehouse_item_id = w.id
+                   AND m.movement_type = 'outgoing'
+                   AND m.created_at >= NOW() - make_interval(days => $2)) as "outgoing!"
+            FROM warehouse w
+            WHERE w.part_id = $1
+            "#,
+            part_id,
+            days
+        )
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| StockForecast::new(part_id, row.quantity, row.min_stock_level, days, row.outgoing)))
+    }
+
+    async fn low_stock_by_model(&self, car_model_id: Uuid) -> Result<Vec<LowStockPart>, Error> {
+        sqlx::query_as!(
+            LowStockPart,
+            r#"
+            SELECT
+                p.id as part_id, p.article as part_article, p.name as part_name,
+                w.quantity, w.min_stock_level, w.max_stock_level, w.location,
+                GREATEST(w.max_stock_level - w.quantity, 0) as "suggested_reorder!"
+            FROM warehouse w
+            JOIN parts p ON w.part_id = p.id
+            WHERE p.car_model_id = $1 AND w.quantity <= w.min_stock_level
+            ORDER BY w.quantity ASC, p.article
+            "#,
+            car_model_id
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_duplicates(&self) -> Result<Vec<WarehouseDuplicate>, Error> {
+        // Проверка целостности: у каждой запчасти должна быть ровно одна складская позиция
+        sqlx::query_as!(
+            WarehouseDuplicate,
+            r#"
+            SELECT part_id, COUNT(*) as "item_count!",
+                   ARRAY_AGG(id ORDER BY created_at) as "warehouse_item_ids!"
+            FROM warehouse
+            GROUP BY part_id
+            HAVING COUNT(*) > 1
+            ORDER BY part_id
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn find_orphans(&self) -> Result<Vec<WarehouseItem>, Error> {
+        // Позиции, чья запчасть удалена до появления проверки зависимостей; JOIN в find_all их не показывает
+        sqlx::query_as!(
+            WarehouseItem,
+            r#"
+            SELECT w.id, w.part_id, w.quantity, w.min_stock_level, w.max_stock_level,
+                   w.location, w.created_at, w.updated_at
+            FROM warehouse w
+            WHERE NOT EXISTS (SELECT 1 FROM parts p WHERE p.id = w.part_id)
+            ORDER BY w.created_at
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn delete_orphans(&self) -> Result<Vec<Uuid>, Error> {
+        sqlx::query_scalar!(
+            r#"
+            DELETE FROM warehouse w
+            WHERE NOT EXISTS (SELECT 1 FROM parts p WHERE p.id = w.part_id)
+            RETURNING w.id
+            "#
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn create_snapshot(&self, snapshot_date: NaiveDate) -> Result<Option<InventorySnapshotSummary>, Error> {
+        // Повторный снимок за ту же дату перезаписывает предыдущий
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            "DELETE FROM inventory_snapshots WHERE snapshot_date = $1",
+            snapshot_date
+        )
+            .execute(&mut *tx)
+            .await?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO inventory_snapshots (snapshot_date, part_id, part_article, part_name, quantity, unit_cost)
+            SELECT $1, w.part_id, p.article, p.name, w.quantity, p.purchase_price
+            FROM warehouse w
+            JOIN parts p ON w.part_id = p.id
+            "#,
+            snapshot_date
+        )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        // Пустой склад: откатываем, чтобы не потерять прежний снимок за эту дату
+        if inserted == 0 {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+
+        tx.commit().await?;
+
+        self.find_snapshot_summary(snapshot_date).await
+    }
+
+    async fn find_snapshot_summary(&self, snapshot_date: NaiveDate) -> Result<Option<InventorySnapshotSummary>, Error> {
+        sqlx::query_as!(
+            InventorySnapshotSummary,
+            r#"
+            SELECT snapshot_date, COUNT(*) as "items_count!",
+                   COALESCE(SUM(quantity * unit_cost), 0) as "total_value!"
+            FROM inventory_snapshots
+            WHERE snapshot_date = $1
+            GROUP BY snapshot_date
+            "#,
+            snapshot_date
+        )
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn compare_snapshots(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<SnapshotPartDelta>, Error> {
+        // FULL JOIN: запчасть, отсутствующая в одном из снимков, считается с нулевым количеством
+        sqlx::query_as!(
+            SnapshotPartDelta,
+            r#"
+            SELECT
+                COALESCE(t.part_id, f.part_id) as "part_id!",
+                COALESCE(t.part_article, f.part_article) as "part_article!",
+                COALESCE(t.part_name, f.part_name) as "part_name!",
+                COALESCE(f.quantity, 0) as "from_quantity!",
+                COALESCE(t.quantity, 0) as "to_quantity!",
+                COALESCE(t.quantity, 0) - COALESCE(f.quantity, 0) as "quantity_change!",
+                COALESCE(f.quantity * f.unit_cost, 0) as "from_value!",
+                COALESCE(t.quantity * t.unit_cost, 0) as "to_value!",
+                COALESCE(t.quantity * t.unit_cost, 0) - COALESCE(f.quantity * f.unit_cost, 0) as "value_change!"
+            FROM (SELECT * FROM inventory_snapshots WHERE snapshot_date = $1) f
+            FULL OUTER JOIN (SELECT * FROM inventory_snapshots WHERE snapshot_date = $2) t
+                ON f.part_id = t.part_id
+            ORDER BY 2
+            "#,
+            from,
+            to
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    // Запчасть с одной складской позицией; у каждого теста свой номер, так как тесты идут параллельно
+    struct TestPart {
+        part_id: Uuid,
+    }
+
+    impl TestPart {
+        async fn insert(pool: &DbPool, n: u8, quantity: i32) -> Self {
+            let part = Self { part_id: Uuid::from_bytes([0xcd, 0xcd, 0xcd, 0xcd, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, 1]) };
+            part.remove(pool).await;
+            sqlx::query("INSERT INTO parts (id, article, name, purchase_price, sale_price) VALUES ($1, $2, 'Test Part', 10, 20)")
+                .bind(part.part_id)
+                .bind(format!("TEST-STOCK-{}", n))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO warehouse (id, part_id, quantity) VALUES ($1, $2, $3)")
+                .bind(Uuid::new_v4())
+                .bind(part.part_id)
+                .bind(quantity)
+                .execute(pool).await.unwrap();
+            part
+        }
+
+        // Складская позиция и движения удаляются каскадом вместе с запчастью
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM parts WHERE id = $1")
+                .bind(self.part_id)
+                .execute(pool).await.unwrap();
+        }
+
+        async fn quantity(&self, pool: &DbPool) -> i32 {
+            sqlx::query_scalar("SELECT quantity FROM warehouse WHERE part_id = $1")
+                .bind(self.part_id)
+                .fetch_one(pool).await.unwrap()
+        }
+
+        async fn movements(&self, pool: &DbPool) -> i64 {
+            sqlx::query_scalar("SELECT COUNT(*) FROM stock_movements WHERE part_id = $1")
+                .bind(self.part_id)
+                .fetch_one(pool).await.unwrap()
+        }
+    }
+
+    // Бренд с моделью и складскими позициями её запчастей; у каждого теста свой номер
+    struct TestModel {
+        brand_id: Uuid,
+        model_id: Uuid,
+    }
+
+    impl TestModel {
+        async fn insert(pool: &DbPool, n: u8) -> Self {
+            let id = |kind: u8| Uuid::from_bytes([0xce, 0xce, 0xce, 0xce, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, n, kind]);
+            let model = Self { brand_id: id(1), model_id: id(2) };
+            model.remove(pool).await;
+            sqlx::query("INSERT INTO brands (id, name, country) VALUES ($1, $2, 'Nowhere')")
+                .bind(model.brand_id)
+                .bind(format!("Test Brand {}", model.brand_id))
+                .execute(pool).await.unwrap();
+            sqlx::query("INSERT INTO car_models (id, name, brand_id) VALUES ($1, 'Test Model', $2)")
+                .bind(model.model_id)
+                .bind(model.brand_id)
+                .execute(pool).await.unwrap();
+            model
+        }
+
+        async fn stocked_part(&self, pool: &DbPool, article: &str, quantity: i32, min_stock_level: i32, max_stock_level: i32) -> Uuid {
+            let part_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO parts (article, name, brand_id, car_model_id, purchase_price, sale_price)
+                 VALUES ($1, 'Test Part', $2, $3, 10, 20)
+                 RETURNING id"
+            )
+                .bind(article)
+                .bind(self.brand_id)
+                .bind(self.model_id)
+                .fetch_one(pool).await.unwrap();
+            sqlx::query("INSERT INTO warehouse (id, part_id, quantity, min_stock_level, max_stock_level) VALUES ($1, $2, $3, $4, $5)")
+                .bind(Uuid::new_v4())
+                .bind(part_id)
+                .bind(quantity)
+                .bind(min_stock_level)
+                .bind(max_stock_level)
+                .execute(pool).await.unwrap();
+            part_id
+        }
+
+        // Складские позиции удаляются каскадом вместе с запчастями, модель - вместе с брендом
+        async fn remove(&self, pool: &DbPool) {
+            sqlx::query("DELETE FROM parts WHERE brand_id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+            sqlx::query("DELETE FROM brands WHERE id = $1")
+                .bind(self.brand_id)
+                .execute(pool).await.unwrap();
+        }
+    }
+
+    fn outgoing(quantity: i32) -> StockMovementRequest {
+        StockMovementRequest { quantity, movement_type: StockMovementType::Outgoing }
+    }
+
+    #[test]
+    fn insufficient_stock_message_names_both_quantities() {
+        let error = StockError::InsufficientStock { available: 5, requested: 6 };
+
+        assert_eq!(error.to_string(), "Insufficient stock: requested 6, available 5");
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn outgoing_equal_to_stock_empties_item() {
+        let pool = test_pool().await;
+        let part = TestPart::insert(&pool, 1, 5).await;
+        let repo = WarehouseRepositoryImpl::new(pool.clone());
+
+        let result = repo.update_stock(part.part_id, &outgoing(5)).await;
+        let quantity = part.quantity(&pool).await;
+        part.remove(&pool).await;
+
+        assert_eq!(result.unwrap().quantity, 0);
+        assert_eq!(quantity, 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn outgoing_over_stock_is_insufficient_and_changes_nothing() {
+        let pool = test_pool().await;
+        let part = TestPart::insert(&pool, 2, 5).await;
+        let repo = WarehouseRepositoryImpl::new(pool.clone());
+
+        let result = repo.update_stock(part.part_id, &outgoing(6)).await;
+        let quantity = part.quantity(&pool).await;
+        let movements = part.movements(&pool).await;
+        part.remove(&pool).await;
+
+        assert!(matches!(result, Err(StockError::InsufficientStock { available: 5, requested: 6 })));
+        assert_eq!(quantity, 5);
+        assert_eq!(movements, 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn reserve_parts_with_one_short_part_reserves_nothing() {
+        let pool = test_pool().await;
+        let enough = TestPart::insert(&pool, 3, 10).await;
+        let short = TestPart::insert(&pool, 4, 2).await;
+        let repo = WarehouseRepositoryImpl::new(pool.clone());
+
+        let items = [
+            ReservePartItem { part_id: enough.part_id, quantity: 4 },
+            ReservePartItem { part_id: short.part_id, quantity: 3 },
+        ];
+        let result = repo.reserve_parts(&items, "RO-TEST-1692", None).await.unwrap();
+        let quantities = (enough.quantity(&pool).await, short.quantity(&pool).await);
+        let movements = enough.movements(&pool).await + short.movements(&pool).await;
+        enough.remove(&pool).await;
+        short.remove(&pool).await;
+
+        let PartsReservation::Insufficient(parts) = result else {
+            panic!("reservation with a short part must not succeed");
+        };
+        assert_eq!(parts.len(), 1);
+        assert_eq!((parts[0].part_id, parts[0].requested, parts[0].available), (short.part_id, 3, 2));
+        assert_eq!(quantities, (10, 2));
+        assert_eq!(movements, 0);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn created_snapshot_can_be_found_by_date() {
+        let pool = test_pool().await;
+        let part = TestPart::insert(&pool, 5, 7).await;
+        let repo = WarehouseRepositoryImpl::new(pool.clone());
+        let snapshot_date = NaiveDate::from_ymd_opt(1999, 1, 5).unwrap();
+
+        let created = repo.create_snapshot(snapshot_date).await.unwrap();
+        let found = repo.find_snapshot_summary(snapshot_date).await.unwrap();
+        let part_quantity: i32 = sqlx::query_scalar("SELECT quantity FROM inventory_snapshots WHERE snapshot_date = $1 AND part_id = $2")
+            .bind(snapshot_date)
+            .bind(part.part_id)
+            .fetch_one(&pool).await.unwrap();
+        sqlx::query("DELETE FROM inventory_snapshots WHERE snapshot_date = $1")
+            .bind(snapshot_date)
+            .execute(&pool).await.unwrap();
+        part.remove(&pool).await;
+
+        let created = created.expect("warehouse with stock must be snapshotted");
+        assert!(created.items_count >= 1);
+        assert_eq!(found.map(|summary| summary.items_count), Some(created.items_count));
+        assert_eq!(part_quantity, 7);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn low_stock_by_model_lists_parts_at_or_below_minimum() {
+        let pool = test_pool().await;
+        let model = TestModel::insert(&pool, 1).await;
+        let low = model.stocked_part(&pool, "TEST-LOW-1", 2, 5, 20).await;
+        // Остаток на минимуме - тоже низкий; максимум ниже остатка не даёт отрицательного дозаказа
+        let at_min = model.stocked_part(&pool, "TEST-LOW-2", 5, 5, 3).await;
+        model.stocked_part(&pool, "TEST-LOW-3", 6, 5, 20).await;
+
+        let parts = WarehouseRepositoryImpl::new(pool.clone()).low_stock_by_model(model.model_id).await.unwrap();
+        model.remove(&pool).await;
+
+        let listed: Vec<(Uuid, i32)> = parts.iter().map(|part| (part.part_id, part.suggested_reorder)).collect();
+        assert_eq!(listed, vec![(low, 18), (at_min, 0)]);
+    }
+
+    #[actix_web::test]
+    #[ignore = "requires PostgreSQL (DATABASE_URL)"]
+    async fn seeded_orphan_is_found_and_deleted() {
+        let pool = test_pool().await;
+        let orphan_id = Uuid::new_v4();
+        // Внешний ключ не даёт сослаться на несуществующую запчасть, поэтому проверка отключается на время вставки
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query("SET LOCAL session_replication_role = replica")
+            .execute(&mut *tx).await.unwrap();
+        sqlx::query("INSERT INTO warehouse (id, part_id, quantity) VALUES ($1, $2, 3)")
+            .bind(orphan_id)
+            .bind(Uuid::new_v4())
+            .execute(&mut *tx).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let repo = WarehouseRepositoryImpl::new(pool.clone());
+        let orphans = repo.find_orphans().await.unwrap();
+        let deleted = repo.delete_orphans().await.unwrap();
+        let remaining = repo.find_orphans().await.unwrap();
+
+        assert!(orphans.iter().any(|item| item.id == orphan_id));
+        assert!(deleted.contains(&orphan_id));
+        assert!(remaining.iter().all(|item| item.id != orphan_id));
+    }
+}
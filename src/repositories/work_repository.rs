@@ -3,6 +3,7 @@ use sqlx::Error;
 use uuid::Uuid;
 
 use crate::models::{Work, CreateWorkRequest, UpdateWorkRequest};
+use crate::config::ArticleCase;
 use crate::database::DbPool;
 
 #[async_trait]
@@ -13,6 +14,14 @@ pub trait WorkRepository: Send + Sync {
     async fn find_by_brand(&self, brand_id: Uuid) -> Result<Vec<Work>, Error>;
     async fn find_by_car_model(&self, car_model_id: Uuid) -> Result<Vec<Work>, Error>;
     async fn find_by_name(&self, name: &str) -> Result<Vec<Work>, Error>;
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Work>, Error>;
+    async fn find_by_hours_range(
+        &self,
+        min_hours: Option<f64>,
+        max_hours: Option<f64>,
+        brand_id: Option<Uuid>,
+        car_model_id: Option<Uuid>,
+    ) -> Result<Vec<Work>, Error>;
     async fn exists_by_article(&self, article: &str) -> Result<bool, Error>;
     async fn save(&self, create_request: &CreateWorkRequest) -> Result<Work, Error>;
     async fn update(&self, id: Uuid, update_request: &UpdateWorkRequest) -> Result<Option<Work>, Error>;
@@ -22,11 +31,18 @@ pub trait WorkRepository: Send + Sync {
 #[derive(Clone)]
 pub struct WorkRepositoryImpl {
     pool: DbPool,
+    article_case: ArticleCase,
 }
 
 impl WorkRepositoryImpl {
     pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+        Self { pool, article_case: ArticleCase::Preserve }
+    }
+
+    // Артикул при сохранении и обновлении приводится к заданному регистру
+    pub fn with_article_case(mut self, article_case: ArticleCase) -> Self {
+        self.article_case = article_case;
+        self
     }
 }
 
@@ -65,7 +81,7 @@ impl WorkRepository for WorkRepositoryImpl {
             r#"
             SELECT id, name, article, norm_hours, brand_id, car_model_id, created_at, updated_at
             FROM works
-            WHERE article = $1
+            WHERE LOWER(article) = LOWER($1)
             "#,
             article
         )
@@ -118,9 +134,36 @@ impl WorkRepository for WorkRepositoryImpl {
             .await
     }
 
+    async fn find_by_hours_range(
+        &self,
+        min_hours: Option<f64>,
+        max_hours: Option<f64>,
+        brand_id: Option<Uuid>,
+        car_model_id: Option<Uuid>,
+    ) -> Result<Vec<Work>, Error> {
+        sqlx::query_as!(
+            Work,
+            r#"
+            SELECT id, name, article, norm_hours, brand_id, car_model_id, created_at, updated_at
+            FROM works
+            WHERE ($1::float8 IS NULL OR norm_hours >= $1)
+              AND ($2::float8 IS NULL OR norm_hours <= $2)
+              AND ($3::uuid IS NULL OR brand_id = $3)
+              AND ($4::uuid IS NULL OR car_model_id = $4)
+            ORDER BY norm_hours, name
+            "#,
+            min_hours,
+            max_hours,
+            brand_id,
+            car_model_id
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+
     async fn exists_by_article(&self, article: &str) -> Result<bool, Error> {
         let result = sqlx::query(
-            "SELECT id FROM works WHERE article = $1 LIMIT 1"
+            "SELECT id FROM works WHERE LOWER(article) = LOWER($1) LIMIT 1"
         )
             .bind(article)
             .fetch_optional(&self.pool)
@@ -141,7 +184,7 @@ impl WorkRepository for WorkRepositoryImpl {
             "#,
             Uuid::new_v4(),
             create_request.name,
-            create_request.article,
+            self.article_case.apply(&create_request.article),
             create_request.norm_hours,
             create_request.brand_id,
             create_request.car_model_id,
@@ -156,6 +199,11 @@ impl WorkRepository for WorkRepositoryImpl {
         let now = chrono::Utc::now();
 
         if let Some(work) = self.find_by_id(id).await? {
+            let article = match &update_request.article {
+                Some(article) => self.article_case.apply(article),
+                None => work.article.clone(),
+            };
+
             let updated_work = sqlx::query_as!(
                 Work,
                 r#"
@@ -165,7 +213,7 @@ impl WorkRepository for WorkRepositoryImpl {
                 RETURNING id, name, article, norm_hours, brand_id, car_model_id, created_at, updated_at
                 "#,
                 update_request.name.as_ref().unwrap_or(&work.name),
-                update_request.article.as_ref().unwrap_or(&work.article),
+                article,
                 update_request.norm_hours.unwrap_or(work.norm_hours),
                 update_request.brand_id.unwrap_or(work.brand_id),
                 update_request.car_model_id.unwrap_or(work.car_model_id),
@@ -191,4 +239,21 @@ impl WorkRepository for WorkRepositoryImpl {
 
         Ok(result.rows_affected() > 0)
     }
-}
\ No newline at end of file
+
+    async fn search(&self, query: &str, limit: i64) -> Result<Vec<Work>, Error> {
+        sqlx::query_as!(
+            Work,
+            r#"
+            SELECT id, name, article, norm_hours, brand_id, car_model_id, created_at, updated_at
+            FROM works
+            WHERE name ILIKE $1 OR article ILIKE $1
+            ORDER BY name
+            LIMIT $2
+            "#,
+            format!("%{}%", query),
+            limit
+        )
+            .fetch_all(&self.pool)
+            .await
+    }
+}